@@ -2,52 +2,70 @@ use std::env;
 use std::fs;
 use std::path::Path;
 
+/// Country codes of the built-in calendars, read from `holidays_<code>.json`
+const CALENDARS: &[&str] = &["ru", "de", "rs"];
+
 fn main() {
     let out_dir = env::var("OUT_DIR").unwrap();
     let dest_path = Path::new(&out_dir).join("holidays_data.rs");
 
-    let json = fs::read_to_string("holidays_ru.json").expect("Failed to read holidays_ru.json");
-    let data: serde_json::Value = serde_json::from_str(&json).expect("Failed to parse JSON");
+    let mut code = String::from("/// (year, month, day)\npub type Dates = &'static [(i32, u32, u32)];\n\n");
+    code.push_str("/// ((year, month, day), holiday name)\npub type Names = &'static [((i32, u32, u32), &'static str)];\n\n");
+    code.push_str("/// (country code, holidays, transferred workdays, holiday names)\n");
+    code.push_str("pub static CALENDARS: &[(&str, Dates, Dates, Names)] = &[\n");
+
+    for country in CALENDARS {
+        let file = format!("holidays_{country}.json");
+        let json = fs::read_to_string(&file).unwrap_or_else(|e| panic!("Failed to read {file}: {e}"));
+        let data: serde_json::Value = serde_json::from_str(&json).unwrap_or_else(|e| panic!("Failed to parse {file}: {e}"));
+
+        code.push_str(&format!("    (\n        \"{country}\",\n"));
+        code.push_str(&dates(&data, "holidays"));
+        code.push_str(&dates(&data, "workdays"));
+        code.push_str(&names(&data));
+        code.push_str("    ),\n");
+        println!("cargo:rerun-if-changed={file}");
+    }
 
-    let mut code = String::from("pub static HOLIDAYS: &[(i32, u32, u32)] = &[\n");
+    code.push_str("];\n");
 
+    fs::write(&dest_path, code).expect("Failed to write generated code");
+}
+
+/// `&[(year, month, day), ...]` for the `key` lists of every year
+fn dates(data: &serde_json::Value, key: &str) -> String {
+    let mut code = String::from("        &[\n");
     for (_year, year_data) in data.as_object().unwrap() {
-        if let Some(holidays) = year_data.get("holidays").and_then(|v| v.as_array()) {
-            for holiday in holidays {
-                if let Some(date_str) = holiday.as_str() {
+        if let Some(dates) = year_data.get(key).and_then(|v| v.as_array()) {
+            for date in dates {
+                if let Some(date_str) = date.as_str() {
                     let parts: Vec<&str> = date_str.split('-').collect();
                     if parts.len() == 3 {
                         let year: i32 = parts[0].parse().unwrap();
                         let month: u32 = parts[1].parse().unwrap();
                         let day: u32 = parts[2].parse().unwrap();
-                        code.push_str(&format!("    ({year}, {month}, {day}),\n"));
+                        code.push_str(&format!("            ({year}, {month}, {day}),\n"));
                     }
                 }
             }
         }
     }
+    code.push_str("        ],\n");
+    code
+}
 
-    code.push_str("];\n\n");
-    code.push_str("pub static WORKDAYS: &[(i32, u32, u32)] = &[\n");
-
+/// `&[((year, month, day), "name"), ...]` for the `names` maps of every year
+fn names(data: &serde_json::Value) -> String {
+    let mut code = String::from("        &[\n");
     for (_year, year_data) in data.as_object().unwrap() {
-        if let Some(workdays) = year_data.get("workdays").and_then(|v| v.as_array()) {
-            for workday in workdays {
-                if let Some(date_str) = workday.as_str() {
-                    let parts: Vec<&str> = date_str.split('-').collect();
-                    if parts.len() == 3 {
-                        let year: i32 = parts[0].parse().unwrap();
-                        let month: u32 = parts[1].parse().unwrap();
-                        let day: u32 = parts[2].parse().unwrap();
-                        code.push_str(&format!("    ({year}, {month}, {day}),\n"));
-                    }
-                }
+        if let Some(names) = year_data.get("names").and_then(|v| v.as_object()) {
+            for (date_str, name) in names {
+                let parts: Vec<u32> = date_str.split('-').map(|p| p.parse().unwrap()).collect();
+                let name = name.as_str().unwrap_or_else(|| panic!("Holiday name of {date_str} is not a string"));
+                code.push_str(&format!("            (({}, {}, {}), {name:?}),\n", parts[0], parts[1], parts[2]));
             }
         }
     }
-
-    code.push_str("];\n");
-
-    fs::write(&dest_path, code).expect("Failed to write generated code");
-    println!("cargo:rerun-if-changed=holidays_ru.json");
+    code.push_str("        ],\n");
+    code
 }
@@ -1,123 +1,400 @@
-use chrono::{Datelike, NaiveDate, TimeZone};
+use chrono::{Datelike, Duration, NaiveDate, TimeZone, Weekday};
+use std::str::FromStr;
 use chrono_tz::Tz;
 
+use crate::date_expr::DateExpr;
 use crate::error::AppError;
-use crate::timestamp::parse_org_timestamp;
+use crate::holidays::{Calendars, HolidayCalendar};
+use crate::query::Filter;
+use crate::sort::{compare_location, sort_day_agenda, sort_tasks, SortKey};
+use crate::timestamp::{add_months, closest_date, occurrences, DatePreference, ParsedTimestamp, RepeaterUnit, Warning};
 use crate::types::{DayAgenda, Task, TaskType, TaskWithOffset};
+use crate::urgency::{compute_urgency, UrgencyCoefficients};
 
 const DEADLINE_WARNING_DAYS: i64 = 14;
 
+/// Default warning window with `--workday-offsets`
+const DEADLINE_WARNING_WORKDAYS: i64 = 10;
+
+/// Spans longer than this many days list only days with entries
+const COMPACT_SPAN_DAYS: i64 = 31;
+
 #[derive(Debug)]
 pub enum AgendaOutput {
     Days(Vec<DayAgenda>),
     Tasks(Vec<Task>),
 }
 
-pub fn filter_agenda(
-    tasks: Vec<Task>,
-    mode: &str,
-    date: Option<&str>,
-    from: Option<&str>,
-    to: Option<&str>,
+/// Parameters controlling agenda construction
+pub struct AgendaOptions<'a> {
+    pub mode: &'a str,
+    pub date: Option<&'a DateExpr>,
+    pub from: Option<&'a DateExpr>,
+    pub to: Option<&'a DateExpr>,
+    pub tz: &'a str,
+    pub current_date: Option<&'a DateExpr>,
+    pub filter: Option<&'a Filter>,
+    pub sort: &'a [SortKey],
+    pub urgency: &'a UrgencyCoefficients,
+    /// Calendars for workday repeaters, chosen per task
+    pub calendars: &'a Calendars,
+    /// Count the default warning window and entry offsets in workdays
+    pub workday_offsets: bool,
+    /// First day of the week for `week` mode
+    pub week_start: Weekday,
+    /// Label weeks of `week`/`month`/`span` agendas with their number
+    pub week_numbers: Option<WeekNumbering>,
+    /// Length of the `span` agenda starting at `date`
+    pub span: Option<Span>,
+    /// Drop days without entries from multi-day agendas
+    pub compact: bool,
+}
+
+/// Length of a `--span` agenda
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Span {
+    Days(u32),
+    Months(u32),
+}
+
+impl FromStr for Span {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.trim().to_lowercase();
+        let invalid = || format!("Invalid span '{s}'. Use 10d, 2w, 3m, fortnight, quarter or year");
+        let span = match value.as_str() {
+            "fortnight" => Span::Days(14),
+            "quarter" => Span::Months(3),
+            "year" => Span::Months(12),
+            _ => {
+                let split = value.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+                let (count, unit) = value.split_at(split);
+                let count: u32 = count.parse().map_err(|_| invalid())?;
+                match unit {
+                    "d" => Span::Days(count),
+                    "w" => Span::Days(count.checked_mul(7).ok_or_else(invalid)?),
+                    "m" => Span::Months(count),
+                    "y" => Span::Months(count.checked_mul(12).ok_or_else(invalid)?),
+                    _ => return Err(invalid()),
+                }
+            }
+        };
+        match span {
+            Span::Days(0) | Span::Months(0) => Err(invalid()),
+            span => Ok(span),
+        }
+    }
+}
+
+impl Span {
+    /// Last day of the span beginning on `start`
+    pub fn end_date(self, start: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            Span::Days(days) => start.checked_add_signed(Duration::days(days as i64 - 1)),
+            Span::Months(months) => add_months(start, months as i32).map(|next| next - Duration::days(1)),
+        }
+    }
+}
+
+/// Week numbering of `--week-numbers`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WeekNumbering {
+    /// ISO 8601: weeks from Monday, week 1 holds the first Thursday of the year
+    Iso,
+    /// US: weeks from Sunday, week 1 holds January 1
+    Us,
+}
+
+impl FromStr for WeekNumbering {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "iso" => Ok(WeekNumbering::Iso),
+            "us" => Ok(WeekNumbering::Us),
+            _ => Err(format!("Invalid week numbering '{s}'. Use iso or us")),
+        }
+    }
+}
+
+impl WeekNumbering {
+    /// Number of the week beginning on `week_begin`, which need not match the
+    /// numbering's own first weekday: ISO counts the week's fourth day, US its last
+    pub fn week_number(self, week_begin: NaiveDate) -> u32 {
+        match self {
+            WeekNumbering::Iso => (week_begin + Duration::days(3)).iso_week().week(),
+            WeekNumbering::Us => {
+                let date = week_begin + Duration::days(6);
+                let jan1 = NaiveDate::from_ymd_opt(date.year(), 1, 1).expect("January 1 exists");
+                (date.ordinal0() + jan1.weekday().num_days_from_sunday()) / 7 + 1
+            }
+        }
+    }
+}
+
+/// Current date: `current_date` when given (relative to today in `tz`), otherwise today in `tz`
+pub fn resolve_today(
     tz: &str,
-    current_date_override: Option<&str>,
-) -> Result<AgendaOutput, AppError> {
+    current_date: Option<&DateExpr>,
+    calendar: &HolidayCalendar,
+) -> Result<NaiveDate, AppError> {
     let tz: Tz = tz
         .parse()
         .map_err(|_| AppError::InvalidTimezone(tz.to_string()))?;
 
-    let today = if let Some(date_str) = current_date_override {
-        NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
-            .map_err(|e| AppError::InvalidDate(format!("current-date '{date_str}': {e}")))?
-    } else {
-        tz.from_utc_datetime(&chrono::Utc::now().naive_utc()).date_naive()
-    };
+    let today = tz.from_utc_datetime(&chrono::Utc::now().naive_utc()).date_naive();
+    match current_date {
+        Some(expr) => expr.resolve(today, calendar),
+        None => Ok(today),
+    }
+}
+
+/// Compute urgency and drop tasks rejected by the filter
+pub fn prepare_tasks(tasks: Vec<Task>, options: &AgendaOptions, today: NaiveDate) -> Vec<Task> {
+    tasks
+        .into_iter()
+        .map(|mut task| {
+            task.urgency = compute_urgency(&task, today, options.urgency);
+            task
+        })
+        .filter(|task| options.filter.is_none_or(|filter| filter.matches(task, today)))
+        .collect()
+}
 
-    match mode {
+pub fn filter_agenda(tasks: Vec<Task>, options: &AgendaOptions) -> Result<AgendaOutput, AppError> {
+    let AgendaOptions {
+        mode, date, from, to, tz, current_date, sort, calendars, workday_offsets, week_start, week_numbers, ..
+    } = *options;
+
+    let today = resolve_today(tz, current_date, calendars.default_calendar())?;
+    let compact = compacts(options, today)?;
+    let resolve = |expr: &DateExpr| expr.resolve(today, calendars.default_calendar());
+    let tasks = prepare_tasks(tasks, options, today);
+
+    let mut output = match mode {
         "day" => {
-            let target_date = if let Some(date_str) = date {
-                NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
-                    .map_err(|e| AppError::InvalidDate(format!("date '{date_str}': {e}")))?
-            } else {
-                today
+            let target_date = match date {
+                Some(expr) => resolve(expr)?,
+                None => today,
             };
-            Ok(AgendaOutput::Days(vec![build_day_agenda(&tasks, target_date, today)]))
+            AgendaOutput::Days(vec![build_day_agenda(&tasks, target_date, today, calendars, workday_offsets)])
         }
         "week" => {
-            let (start_date, end_date) = if let (Some(from_str), Some(to_str)) = (from, to) {
-                let start = NaiveDate::parse_from_str(from_str, "%Y-%m-%d")
-                    .map_err(|e| AppError::InvalidDate(format!("from '{from_str}': {e}")))?;
-                let end = NaiveDate::parse_from_str(to_str, "%Y-%m-%d")
-                    .map_err(|e| AppError::InvalidDate(format!("to '{to_str}': {e}")))?;
+            let (start_date, end_date) = if let (Some(from), Some(to)) = (from, to) {
+                let start = resolve(from)?;
+                let end = resolve(to)?;
                 
                 if start > end {
-                    return Err(AppError::DateRange(format!("Start date {from_str} is after end date {to_str}")));
+                    return Err(AppError::DateRange(format!("Start date {start} is after end date {end}")));
                 }
                 
                 (start, end)
-            } else if let Some(date_str) = date {
-                let target_date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
-                    .map_err(|e| AppError::InvalidDate(format!("date '{date_str}': {e}")))?;
-                get_week_for_date(target_date)
             } else {
-                get_current_week(&tz)
+                let target_date = match date {
+                    Some(expr) => resolve(expr)?,
+                    None => today,
+                };
+                get_week_for_date(target_date, week_start)
             };
             
-            Ok(AgendaOutput::Days(build_week_agenda(&tasks, start_date, end_date, today)))
+            AgendaOutput::Days(build_week_agenda(&tasks, start_date, end_date, today, calendars, workday_offsets))
         }
         "month" => {
-            let (start_date, end_date) = if let (Some(from_str), Some(to_str)) = (from, to) {
-                let start = NaiveDate::parse_from_str(from_str, "%Y-%m-%d")
-                    .map_err(|e| AppError::InvalidDate(format!("from '{from_str}': {e}")))?;
-                let end = NaiveDate::parse_from_str(to_str, "%Y-%m-%d")
-                    .map_err(|e| AppError::InvalidDate(format!("to '{to_str}': {e}")))?;
+            let (start_date, end_date) = if let (Some(from), Some(to)) = (from, to) {
+                let start = resolve(from)?;
+                let end = resolve(to)?;
                 
                 if start > end {
-                    return Err(AppError::DateRange(format!("Start date {from_str} is after end date {to_str}")));
+                    return Err(AppError::DateRange(format!("Start date {start} is after end date {end}")));
                 }
                 
                 (start, end)
-            } else if let Some(date_str) = date {
-                let target_date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
-                    .map_err(|e| AppError::InvalidDate(format!("date '{date_str}': {e}")))?;
-                get_month_for_date(target_date)
             } else {
-                get_current_month(&tz)
+                let target_date = match date {
+                    Some(expr) => resolve(expr)?,
+                    None => today,
+                };
+                get_month_for_date(target_date)
             };
             
-            Ok(AgendaOutput::Days(build_week_agenda(&tasks, start_date, end_date, today)))
+            AgendaOutput::Days(build_week_agenda(&tasks, start_date, end_date, today, calendars, workday_offsets))
+        }
+        "span" => {
+            let (start_date, end_date) = span_range(options, today)?;
+            AgendaOutput::Days(build_week_agenda(&tasks, start_date, end_date, today, calendars, workday_offsets))
         }
         "tasks" => {
             let mut filtered: Vec<Task> = tasks
                 .into_iter()
                 .filter(|t| matches!(t.task_type, Some(TaskType::Todo)))
                 .collect();
-            filtered.sort_by_key(|t| t.priority.as_ref().map(|p| p.order()).unwrap_or(999));
-            Ok(AgendaOutput::Tasks(filtered))
+            sort_tasks(&mut filtered, sort);
+            AgendaOutput::Tasks(filtered)
         }
-        _ => Err(AppError::InvalidDate(format!("Invalid agenda mode '{mode}'"))),
+        _ => return Err(AppError::InvalidDate(format!("Invalid agenda mode '{mode}'"))),
+    };
+
+    if let AgendaOutput::Days(ref mut days) = output {
+        if mode != "day" {
+            if compact {
+                days.retain(|day| !day.is_empty());
+            }
+            if let Some(numbering) = week_numbers {
+                label_weeks(days, week_start, numbering);
+            }
+        }
+        for day in days {
+            sort_day_agenda(day, sort);
+        }
+    }
+
+    Ok(output)
+}
+
+/// Whether `filter_agenda` lists only days with entries: with `compact` and
+/// for spans of a month or longer
+pub fn is_compact(options: &AgendaOptions) -> Result<bool, AppError> {
+    let today = resolve_today(options.tz, options.current_date, options.calendars.default_calendar())?;
+    compacts(options, today)
+}
+
+fn compacts(options: &AgendaOptions, today: NaiveDate) -> Result<bool, AppError> {
+    if options.compact || options.mode != "span" {
+        return Ok(options.compact);
     }
+    let (start, end) = span_range(options, today)?;
+    Ok((end - start).num_days() >= COMPACT_SPAN_DAYS)
+}
+
+/// First and last day of a `span` agenda starting at `date` or today
+fn span_range(options: &AgendaOptions, today: NaiveDate) -> Result<(NaiveDate, NaiveDate), AppError> {
+    let span = options.span.ok_or_else(|| AppError::DateRange("Agenda mode 'span' needs --span".to_string()))?;
+    let start = match options.date {
+        Some(expr) => expr.resolve(today, options.calendars.default_calendar())?,
+        None => today,
+    };
+    let end = span
+        .end_date(start)
+        .ok_or_else(|| AppError::DateRange(format!("Span {span:?} from {start} is out of range")))?;
+    Ok((start, end))
+}
+
+/// Calendar of a task and the unit its agenda offsets are counted in
+struct Offsets<'a> {
+    calendar: &'a HolidayCalendar,
+    workdays: bool,
+}
+
+impl Offsets<'_> {
+    /// Whether a deadline is close enough to `current_date` to show up as upcoming:
+    /// the timestamp's own warning period (`-3d`, `-3wd`) or the default window
+    fn in_warning_window(&self, warning: Option<&Warning>, deadline: NaiveDate, current_date: NaiveDate) -> bool {
+        let days = (deadline - current_date).num_days();
+        let workdays = || self.calendar.workdays_between(current_date, deadline);
+        let Some(warning) = warning else {
+            return if self.workdays { workdays() <= DEADLINE_WARNING_WORKDAYS } else { days <= DEADLINE_WARNING_DAYS };
+        };
+        let value = warning.value as i64;
+        match warning.unit {
+            RepeaterUnit::Hour => days * 24 <= value,
+            RepeaterUnit::Day => days <= value,
+            RepeaterUnit::Week => days <= value * 7,
+            RepeaterUnit::Month => add_months(deadline, -(value as i32)).is_some_and(|start| start <= current_date),
+            RepeaterUnit::Year => add_months(deadline, -(value as i32) * 12).is_some_and(|start| start <= current_date),
+            RepeaterUnit::Workday => workdays() <= value,
+        }
+    }
+}
+
+fn build_day_agenda(
+    tasks: &[Task],
+    day_date: NaiveDate,
+    current_date: NaiveDate,
+    calendars: &Calendars,
+    workday_offsets: bool,
+) -> DayAgenda {
+    let tasks = agenda_tasks(tasks, day_date, day_date, current_date, calendars);
+    build_range_day_agenda(&tasks, day_date, current_date, calendars, workday_offsets)
 }
 
-fn build_day_agenda(tasks: &[Task], day_date: NaiveDate, current_date: NaiveDate) -> DayAgenda {
+/// Task with a parsed timestamp and, for repeaters, the dates it falls on in an agenda range
+struct AgendaTask<'a> {
+    task: &'a Task,
+    parsed: &'a ParsedTimestamp,
+    /// Last occurrence up to the current date (the base date when that is later)
+    deadline: Option<NaiveDate>,
+    /// Occurrences after the current date within the range, ascending
+    occurrences: Vec<NaiveDate>,
+}
+
+/// Tasks with a valid timestamp, with repeater occurrences of `start..=end` generated once
+fn agenda_tasks<'a>(
+    tasks: &'a [Task],
+    start: NaiveDate,
+    end: NaiveDate,
+    current_date: NaiveDate,
+    calendars: &Calendars,
+) -> Vec<AgendaTask<'a>> {
+    tasks
+        .iter()
+        .filter_map(|task| {
+            let parsed = task.parsed_timestamp.as_ref()?;
+            let (deadline, occurrences) = match parsed.repeater {
+                Some(ref repeater) => {
+                    let calendar = calendars.for_task(task);
+                    let from = start.max(current_date + Duration::days(1));
+                    (
+                        closest_date(parsed.date, current_date, DatePreference::Past, repeater, calendar),
+                        occurrences(parsed.date, repeater, from, end, calendar),
+                    )
+                }
+                None => (None, Vec::new()),
+            };
+            Some(AgendaTask { task, parsed, deadline, occurrences })
+        })
+        .collect()
+}
+
+fn build_range_day_agenda(
+    tasks: &[AgendaTask],
+    day_date: NaiveDate,
+    current_date: NaiveDate,
+    calendars: &Calendars,
+    workday_offsets: bool,
+) -> DayAgenda {
     let mut agenda = DayAgenda::new(day_date);
-    let is_today = day_date == current_date;
+    agenda.is_workday = calendars.default_calendar().is_workday(day_date);
+    agenda.holiday_name = calendars.default_calendar().holiday_name(day_date).map(str::to_string);
     
-    for task in tasks {
-        if let Some(ref ts) = task.timestamp {
-            if let Some(parsed) = parse_org_timestamp(ts, None) {
-                if let Some(ref repeater) = parsed.repeater {
-                    handle_repeating_task(task, &parsed, repeater, day_date, current_date, &mut agenda);
-                } else {
-                    handle_non_repeating_task(task, &parsed, day_date, is_today, &mut agenda);
-                }
-            }
+    for entry in tasks {
+        let offsets = Offsets { calendar: calendars.for_task(entry.task), workdays: workday_offsets };
+        if let Some(ref repeater) = entry.parsed.repeater {
+            handle_repeating_task(entry, repeater, day_date, current_date, &offsets, &mut agenda);
+        } else {
+            handle_non_repeating_task(entry.task, entry.parsed, day_date, current_date, &offsets, &mut agenda);
         }
     }
     
-    agenda.overdue.sort_by_key(|t| t.days_offset);
-    agenda.scheduled_timed.sort_by(|a, b| a.task.timestamp_time.cmp(&b.task.timestamp_time));
-    agenda.upcoming.sort_by_key(|t| t.days_offset);
+    agenda.overdue.sort_by(|a, b| a.days_offset.cmp(&b.days_offset).then_with(|| compare_location(&a.task, &b.task)));
+    agenda.scheduled_timed.sort_by(|a, b| {
+        a.task.timestamp_time.cmp(&b.task.timestamp_time).then_with(|| compare_location(&a.task, &b.task))
+    });
+    agenda.scheduled_no_time.sort_by(|a, b| compare_location(&a.task, &b.task));
+    agenda.upcoming.sort_by(|a, b| a.days_offset.cmp(&b.days_offset).then_with(|| compare_location(&a.task, &b.task)));
+
+    if workday_offsets {
+        let entries = agenda.overdue.iter_mut()
+            .chain(agenda.scheduled_timed.iter_mut())
+            .chain(agenda.scheduled_no_time.iter_mut())
+            .chain(agenda.upcoming.iter_mut());
+        for entry in entries {
+            if let Some(offset) = entry.days_offset {
+                let calendar = calendars.for_task(&entry.task);
+                entry.workdays_offset = Some(calendar.workdays_between(day_date, day_date + Duration::days(offset)));
+            }
+        }
+    }
     
     agenda
 }
@@ -126,9 +403,11 @@ fn handle_non_repeating_task(
     task: &Task,
     parsed: &crate::timestamp::ParsedTimestamp,
     day_date: NaiveDate,
-    is_today: bool,
+    current_date: NaiveDate,
+    offsets: &Offsets,
     agenda: &mut DayAgenda,
 ) {
+    let is_today = day_date == current_date;
     let task_date = parsed.date;
     let days_diff = (task_date - day_date).num_days();
     let is_done = matches!(task.task_type, Some(TaskType::Done));
@@ -137,10 +416,7 @@ fn handle_non_repeating_task(
     
     // Show task on its scheduled date
     if task_date == day_date {
-        let task_with_offset = TaskWithOffset {
-            task: task.clone(),
-            days_offset,
-        };
+        let task_with_offset = TaskWithOffset::new(task.clone(), days_offset);
         if task_with_offset.task.timestamp_time.is_some() {
             agenda.scheduled_timed.push(task_with_offset);
         } else {
@@ -152,7 +428,7 @@ fn handle_non_repeating_task(
     } else if days_diff > 0 && is_today {
         // Upcoming only in today agenda, only for DEADLINE within warning period
         if let Some(ref ts_type) = task.timestamp_type {
-            if ts_type == "DEADLINE" && days_diff <= DEADLINE_WARNING_DAYS {
+            if ts_type == "DEADLINE" && offsets.in_warning_window(parsed.warning.as_ref(), task_date, current_date) {
                 agenda.upcoming.push(create_task_without_time(task, days_offset));
             }
         }
@@ -163,32 +439,27 @@ fn create_task_without_time(task: &Task, days_offset: Option<i64>) -> TaskWithOf
     let mut task_copy = task.clone();
     task_copy.timestamp_time = None;
     task_copy.timestamp_end_time = None;
-    TaskWithOffset {
-        task: task_copy,
-        days_offset,
-    }
+    TaskWithOffset::new(task_copy, days_offset)
 }
 
 fn handle_repeating_task(
-    task: &Task,
-    parsed: &crate::timestamp::ParsedTimestamp,
+    entry: &AgendaTask,
     repeater: &crate::timestamp::Repeater,
     day_date: NaiveDate,
     current_date: NaiveDate,
+    offsets: &Offsets,
     agenda: &mut DayAgenda,
 ) {
-    use crate::timestamp::{closest_date, DatePreference};
-    
-    let base_date = parsed.date;
+    let AgendaTask { task, parsed, deadline, .. } = *entry;
+    let calendar = offsets.calendar;
     let is_today = day_date == current_date;
     
-    // Calculate deadline (last occurrence <= today) and repeat (next occurrence >= day_date)
+    // Deadline (last occurrence <= today) and repeat (day_date itself if it is an occurrence)
     // Following org-mode logic from org-agenda.el
-    let deadline = closest_date(base_date, current_date, DatePreference::Past, repeater);
     let repeat = if day_date <= current_date {
         deadline
     } else {
-        closest_date(base_date, day_date, DatePreference::Future, repeater)
+        entry.occurrences.binary_search(&day_date).ok().map(|_| day_date)
     };
     
     // Show task if:
@@ -223,10 +494,7 @@ fn handle_repeating_task(
                 ));
             }
             
-            let task_with_offset = TaskWithOffset {
-                task: task_copy,
-                days_offset: None,
-            };
+            let task_with_offset = TaskWithOffset::new(task_copy, None);
             
             if task_with_offset.task.timestamp_time.is_some() {
                 agenda.scheduled_timed.push(task_with_offset);
@@ -238,16 +506,8 @@ fn handle_repeating_task(
         // Show as overdue in today agenda if deadline < today
         if is_today && deadline_date < current_date {
             // For workday repeaters, only show as overdue if current_date is a workday
-            let should_show_overdue = if repeater.unit == crate::timestamp::RepeaterUnit::Workday {
-                use crate::holidays::HolidayCalendar;
-                if let Ok(calendar) = HolidayCalendar::load() {
-                    calendar.is_workday(current_date)
-                } else {
-                    true
-                }
-            } else {
-                true
-            };
+            let should_show_overdue =
+                repeater.unit != crate::timestamp::RepeaterUnit::Workday || calendar.is_workday(current_date);
             
             if should_show_overdue {
                 let days_diff = (deadline_date - current_date).num_days();
@@ -273,10 +533,7 @@ fn handle_repeating_task(
                     ));
                 }
                 
-                let task_with_offset = TaskWithOffset {
-                    task: task_copy,
-                    days_offset: Some(days_diff),
-                };
+                let task_with_offset = TaskWithOffset::new(task_copy, Some(days_diff));
                 agenda.overdue.push(task_with_offset);
             }
         }
@@ -286,14 +543,11 @@ fn handle_repeating_task(
             if let Some(ref ts_type) = task.timestamp_type {
                 if ts_type == "DEADLINE" {
                     let days_diff = (repeat_date - current_date).num_days();
-                    if days_diff <= DEADLINE_WARNING_DAYS {
+                    if offsets.in_warning_window(parsed.warning.as_ref(), repeat_date, current_date) {
                         let mut task_copy = task.clone();
                         task_copy.timestamp_time = None;
                         task_copy.timestamp_end_time = None;
-                        let task_with_offset = TaskWithOffset {
-                            task: task_copy,
-                            days_offset: Some(days_diff),
-                        };
+                        let task_with_offset = TaskWithOffset::new(task_copy, Some(days_diff));
                         agenda.upcoming.push(task_with_offset);
                     }
                 }
@@ -303,7 +557,12 @@ fn handle_repeating_task(
 }
 
 #[allow(dead_code)]
-fn find_last_occurrence_before(base_date: NaiveDate, repeater: &crate::timestamp::Repeater, before_date: NaiveDate) -> Option<NaiveDate> {
+fn find_last_occurrence_before(
+    base_date: NaiveDate,
+    repeater: &crate::timestamp::Repeater,
+    before_date: NaiveDate,
+    calendar: &HolidayCalendar,
+) -> Option<NaiveDate> {
     use crate::timestamp::RepeaterUnit;
     
     // For workday repeaters, use next_occurrence to find the next occurrence from before_date - 1
@@ -319,7 +578,7 @@ fn find_last_occurrence_before(base_date: NaiveDate, repeater: &crate::timestamp
         let mut iterations = 0;
         
         while check_date < before_date && iterations < max_iterations {
-            if is_occurrence_day(base_date, repeater, check_date) {
+            if is_occurrence_day(base_date, repeater, check_date, calendar) {
                 last_found = Some(check_date);
             }
             check_date += chrono::Duration::days(1);
@@ -353,7 +612,7 @@ fn find_last_occurrence_before(base_date: NaiveDate, repeater: &crate::timestamp
                 use crate::timestamp::next_occurrence;
                 
                 // Find the next occurrence after before_date, then step back
-                if let Some(next) = next_occurrence(base_date, repeater, before_date) {
+                if let Some(next) = next_occurrence(base_date, repeater, before_date, calendar) {
                     if next > before_date {
                         use crate::timestamp::add_months;
                         let months_to_subtract = if repeater.unit == RepeaterUnit::Year {
@@ -377,7 +636,12 @@ fn find_last_occurrence_before(base_date: NaiveDate, repeater: &crate::timestamp
 }
 
 #[allow(dead_code)]
-fn is_occurrence_day(base_date: NaiveDate, repeater: &crate::timestamp::Repeater, check_date: NaiveDate) -> bool {
+fn is_occurrence_day(
+    base_date: NaiveDate,
+    repeater: &crate::timestamp::Repeater,
+    check_date: NaiveDate,
+    calendar: &HolidayCalendar,
+) -> bool {
     use crate::timestamp::RepeaterUnit;
     
     if check_date < base_date {
@@ -396,7 +660,7 @@ fn is_occurrence_day(base_date: NaiveDate, repeater: &crate::timestamp::Repeater
                 return false;
             }
             let years_diff = check_date.year() - base_date.year();
-            years_diff >= 0 && (years_diff as u32) % repeater.value == 0
+            years_diff >= 0 && (years_diff as u32).is_multiple_of(repeater.value)
         }
         RepeaterUnit::Month => {
             use chrono::Datelike;
@@ -404,63 +668,72 @@ fn is_occurrence_day(base_date: NaiveDate, repeater: &crate::timestamp::Repeater
                 return false;
             }
             let months_diff = (check_date.year() - base_date.year()) * 12 + (check_date.month() as i32 - base_date.month() as i32);
-            months_diff >= 0 && (months_diff as u32) % repeater.value == 0
+            months_diff >= 0 && (months_diff as u32).is_multiple_of(repeater.value)
         }
         RepeaterUnit::Workday => {
-            use crate::holidays::HolidayCalendar;
-            if let Ok(calendar) = HolidayCalendar::load() {
-                if !calendar.is_workday(check_date) {
-                    return false;
-                }
-                if check_date == base_date {
-                    return true;
-                }
-                let mut current = calendar.next_workday(base_date);
-                let mut workday_count = 1u32;
-                while current < check_date {
-                    current = calendar.next_workday(current);
-                    workday_count += 1;
-                }
-                if current == check_date {
-                    workday_count % repeater.value == 0
-                } else {
-                    false
-                }
-            } else {
-                false
+            if !calendar.is_workday(check_date) {
+                return false;
             }
+            if check_date == base_date {
+                return true;
+            }
+            let mut current = calendar.next_workday(base_date);
+            let mut workday_count = 1u32;
+            while current < check_date {
+                current = calendar.next_workday(current);
+                workday_count += 1;
+            }
+            current == check_date && workday_count.is_multiple_of(repeater.value)
         }
     }
 }
 
 /// Build agenda for a week
-fn build_week_agenda(tasks: &[Task], start_date: NaiveDate, end_date: NaiveDate, current_date: NaiveDate) -> Vec<DayAgenda> {
+fn build_week_agenda(
+    tasks: &[Task],
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    current_date: NaiveDate,
+    calendars: &Calendars,
+    workday_offsets: bool,
+) -> Vec<DayAgenda> {
+    let tasks = agenda_tasks(tasks, start_date, end_date, current_date, calendars);
     let mut result = Vec::new();
     let mut current = start_date;
     
     while current <= end_date {
-        result.push(build_day_agenda(tasks, current, current_date));
+        result.push(build_range_day_agenda(&tasks, current, current_date, calendars, workday_offsets));
         current += chrono::Duration::days(1);
     }
     
     result
 }
 
-/// Get week boundaries (Monday to Sunday) for a specific date
-fn get_week_for_date(date: NaiveDate) -> (NaiveDate, NaiveDate) {
-    let weekday = date.weekday();
-    let days_from_monday = weekday.num_days_from_monday();
-    let monday = date - chrono::Duration::days(days_from_monday as i64);
-    let sunday = monday + chrono::Duration::days(6);
-    (monday, sunday)
+/// First day of the week holding `date` when weeks begin on `week_start`
+pub fn week_begin(date: NaiveDate, week_start: Weekday) -> NaiveDate {
+    let days_since_start = (date.weekday().num_days_from_monday() + 7 - week_start.num_days_from_monday()) % 7;
+    date - chrono::Duration::days(days_since_start as i64)
 }
 
-/// Get current week (Monday to Sunday) in the given timezone
-fn get_current_week(tz: &Tz) -> (NaiveDate, NaiveDate) {
-    let today = tz
-        .from_utc_datetime(&chrono::Utc::now().naive_utc())
-        .date_naive();
-    get_week_for_date(today)
+/// Get week boundaries (`week_start` and six days after it) for a specific date
+fn get_week_for_date(date: NaiveDate, week_start: Weekday) -> (NaiveDate, NaiveDate) {
+    let first = week_begin(date, week_start);
+    (first, first + chrono::Duration::days(6))
+}
+
+/// Put the week number on the first listed day of every week
+fn label_weeks(days: &mut [DayAgenda], week_start: Weekday, numbering: WeekNumbering) {
+    let mut labelled = None;
+    for day in days {
+        let Ok(date) = NaiveDate::parse_from_str(&day.date, "%Y-%m-%d") else {
+            continue;
+        };
+        let begin = week_begin(date, week_start);
+        if labelled != Some(begin) {
+            day.week = Some(numbering.week_number(begin));
+            labelled = Some(begin);
+        }
+    }
 }
 
 /// Get month boundaries (first to last day) for a specific date
@@ -474,17 +747,14 @@ fn get_month_for_date(date: NaiveDate) -> (NaiveDate, NaiveDate) {
     (first_day, last_day)
 }
 
-/// Get current month (first to last day) in the given timezone
-fn get_current_month(tz: &Tz) -> (NaiveDate, NaiveDate) {
-    let today = tz
-        .from_utc_datetime(&chrono::Utc::now().naive_utc())
-        .date_naive();
-    get_month_for_date(today)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::timestamp::parse_org_timestamp;
+
+    fn calendars() -> Calendars {
+        Calendars::load("ru", &[], false).unwrap()
+    }
 
     fn create_test_task_with_type(date_str: &str, time: Option<&str>, task_type: TaskType, ts_type: &str) -> Task {
         let timestamp = if let Some(t) = time {
@@ -508,6 +778,11 @@ mod tests {
             timestamp_end_time: None,
             clocks: None,
             total_clock_time: None,
+            tags: None,
+            properties: None,
+            urgency: None,
+            uid: None,
+            parsed_timestamp: parse_org_timestamp(&timestamp, None),
         }
     }
 
@@ -524,7 +799,7 @@ mod tests {
         
         let day_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.upcoming.len(), 0, "SCHEDULED tasks in future should not appear as upcoming");
         assert_eq!(agenda.scheduled_timed.len(), 0);
@@ -540,7 +815,7 @@ mod tests {
         
         let day_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.upcoming.len(), 2, "DEADLINE within 14 days should appear as upcoming");
         assert_eq!(agenda.upcoming[0].days_offset, Some(5));
@@ -556,7 +831,7 @@ mod tests {
         
         let day_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.upcoming.len(), 0, "DEADLINE beyond 14 days should not appear");
     }
@@ -569,7 +844,7 @@ mod tests {
         
         let day_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.upcoming.len(), 1, "DEADLINE exactly 14 days away should appear");
         assert_eq!(agenda.upcoming[0].days_offset, Some(14));
@@ -583,7 +858,7 @@ mod tests {
         
         let day_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.upcoming.len(), 0, "DEADLINE 15 days away should not appear");
     }
@@ -597,7 +872,7 @@ mod tests {
         
         // Check on current date - should show overdue
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
-        let agenda = build_day_agenda(&tasks, current_date, current_date);
+        let agenda = build_day_agenda(&tasks, current_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.overdue.len(), 2, "Overdue tasks should appear on current date");
         assert_eq!(agenda.overdue[0].days_offset, Some(-4));
@@ -605,7 +880,7 @@ mod tests {
         
         // Check on past date - should not show overdue
         let past_date = NaiveDate::from_ymd_opt(2024, 12, 2).unwrap();
-        let agenda_past = build_day_agenda(&tasks, past_date, current_date);
+        let agenda_past = build_day_agenda(&tasks, past_date, current_date, &calendars(), false);
         
         assert_eq!(agenda_past.overdue.len(), 0, "Overdue should not appear on past dates");
     }
@@ -622,7 +897,7 @@ mod tests {
         let end_date = NaiveDate::from_ymd_opt(2024, 12, 8).unwrap(); // Sunday
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap(); // Thursday
         
-        let week = build_week_agenda(&tasks, start_date, end_date, current_date);
+        let week = build_week_agenda(&tasks, start_date, end_date, current_date, &calendars(), false);
         
         assert_eq!(week.len(), 7);
         
@@ -646,7 +921,7 @@ mod tests {
         assert_eq!(week[3].overdue.len(), 2); // Monday and Tuesday tasks are overdue
         
         // Future days should have tasks if scheduled
-        assert!(week[4].scheduled_timed.len() == 0); // Friday
+        assert!(week[4].scheduled_timed.is_empty()); // Friday
     }
 
     #[test]
@@ -659,7 +934,7 @@ mod tests {
         
         let day_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.scheduled_timed.len(), 2);
         assert_eq!(agenda.scheduled_no_time.len(), 1);
@@ -681,7 +956,7 @@ mod tests {
         
         let day_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.upcoming.len(), 1, "Only DEADLINE within 14 days should appear");
         assert_eq!(agenda.upcoming[0].task.timestamp_type, Some("DEADLINE".to_string()));
@@ -709,6 +984,11 @@ mod tests {
             timestamp_end_time: None,
             clocks: None,
             total_clock_time: None,
+            tags: None,
+            properties: None,
+            urgency: None,
+            uid: None,
+            parsed_timestamp: parse_org_timestamp(&timestamp, None),
         }
     }
 
@@ -734,6 +1014,11 @@ mod tests {
             timestamp_end_time: None,
             clocks: None,
             total_clock_time: None,
+            tags: None,
+            properties: None,
+            urgency: None,
+            uid: None,
+            parsed_timestamp: parse_org_timestamp(&timestamp, None),
         }
     }
 
@@ -745,7 +1030,7 @@ mod tests {
         
         let day_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.scheduled_timed.len(), 1);
         assert_eq!(agenda.scheduled_timed[0].task.timestamp_time, Some("10:00".to_string()));
@@ -759,7 +1044,7 @@ mod tests {
         
         let day_date = NaiveDate::from_ymd_opt(2024, 12, 4).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.scheduled_timed.len(), 0);
         assert_eq!(agenda.scheduled_no_time.len(), 0);
@@ -773,12 +1058,12 @@ mod tests {
         
         let day_date = NaiveDate::from_ymd_opt(2024, 12, 8).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 8).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.scheduled_no_time.len(), 1);
         
         let day_date = NaiveDate::from_ymd_opt(2024, 12, 9).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.scheduled_no_time.len(), 0);
     }
@@ -800,7 +1085,7 @@ mod tests {
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
         
         for (date, should_show) in test_dates {
-            let agenda = build_day_agenda(&tasks, date, current_date);
+            let agenda = build_day_agenda(&tasks, date, current_date, &calendars(), false);
             if should_show {
                 assert_eq!(agenda.scheduled_no_time.len(), 1, "Failed for date {date}");
             } else {
@@ -819,14 +1104,14 @@ mod tests {
         // Next occurrence is 12-05, which is in the past, so task is overdue
         let day_date = NaiveDate::from_ymd_opt(2024, 12, 6).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 6).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         eprintln!("overdue: {:?}", agenda.overdue.len());
         eprintln!("scheduled_timed: {:?}", agenda.scheduled_timed.len());
         eprintln!("scheduled_no_time: {:?}", agenda.scheduled_no_time.len());
         
         // Should appear in overdue (next occurrence 12-05 is in the past)
-        assert!(agenda.overdue.len() > 0);
+        assert!(!agenda.overdue.is_empty());
         assert_eq!(agenda.overdue[0].task.timestamp_time, None);
     }
 
@@ -838,7 +1123,7 @@ mod tests {
         
         let day_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.upcoming.len(), 1);
         assert_eq!(agenda.upcoming[0].task.timestamp_time, None);
@@ -853,7 +1138,7 @@ mod tests {
         
         let day_date = NaiveDate::from_ymd_opt(2025, 12, 5).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2025, 12, 5).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.upcoming.len(), 0, "DEADLINE beyond 14 days should not appear in upcoming");
     }
@@ -868,7 +1153,7 @@ mod tests {
         
         let day_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.scheduled_timed.len(), 2);
         assert_eq!(agenda.upcoming.len(), 1); // Only DEADLINE
@@ -884,7 +1169,7 @@ mod tests {
         
         let day_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.scheduled_timed.len(), 3);
         assert_eq!(agenda.scheduled_timed[0].task.timestamp_time, Some("09:00".to_string()));
@@ -901,7 +1186,7 @@ mod tests {
         
         let day_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.overdue.len(), 2);
         assert_eq!(agenda.overdue[0].task.timestamp_time, None);
@@ -917,7 +1202,7 @@ mod tests {
         
         let day_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.upcoming.len(), 2);
         assert_eq!(agenda.upcoming[0].task.timestamp_time, None);
@@ -932,7 +1217,7 @@ mod tests {
         
         let day_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         // Should appear in scheduled (it's an occurrence day)
         assert_eq!(agenda.scheduled_timed.len(), 1);
@@ -951,31 +1236,145 @@ mod tests {
         
         let day_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.scheduled_timed.len(), 1);
         assert_eq!(agenda.overdue.len(), 0);
     }
 
     #[test]
-    fn test_get_current_week() {
-        let tz: Tz = "UTC".parse().unwrap();
-        let (monday, sunday) = get_current_week(&tz);
-        
-        assert_eq!(monday.weekday(), chrono::Weekday::Mon);
-        assert_eq!(sunday.weekday(), chrono::Weekday::Sun);
-        assert_eq!((sunday - monday).num_days(), 6);
+    fn test_week_and_month_default_to_current_date() {
+        let calendars = calendars();
+        let urgency = UrgencyCoefficients::default();
+        let current = DateExpr::Absolute(NaiveDate::from_ymd_opt(2025, 4, 2).unwrap());
+        let agenda = |mode: &str| {
+            let options = AgendaOptions {
+                mode,
+                date: None,
+                from: None,
+                to: None,
+                tz: "UTC",
+                current_date: Some(&current),
+                filter: None,
+                sort: &[],
+                urgency: &urgency,
+                calendars: &calendars,
+                workday_offsets: false,
+                week_start: Weekday::Mon,
+                week_numbers: None,
+                span: None,
+                compact: false,
+            };
+            match filter_agenda(Vec::new(), &options).unwrap() {
+                AgendaOutput::Days(days) => days,
+                AgendaOutput::Tasks(_) => panic!("{mode} agenda should list days"),
+            }
+        };
+
+        let week = agenda("week");
+        assert_eq!(week.len(), 7);
+        assert_eq!(week[0].date, "2025-03-31");
+        assert_eq!(week[6].date, "2025-04-06");
+
+        let month = agenda("month");
+        assert_eq!(month.len(), 30);
+        assert_eq!(month[0].date, "2025-04-01");
+        assert_eq!(month[29].date, "2025-04-30");
     }
 
     #[test]
-    fn test_get_current_month() {
-        let tz: Tz = "UTC".parse().unwrap();
-        let (first, last) = get_current_month(&tz);
-        
-        assert_eq!(first.day(), 1);
-        assert_eq!(first.month(), last.month());
-        assert_eq!(first.year(), last.year());
-        assert!(last.day() >= 28 && last.day() <= 31);
+    fn test_week_start_and_numbers() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        // Среда 2025-04-02 в неделе с воскресенья
+        assert_eq!(get_week_for_date(date(2025, 4, 2), Weekday::Sun), (date(2025, 3, 30), date(2025, 4, 5)));
+        assert_eq!(get_week_for_date(date(2025, 3, 30), Weekday::Sun), (date(2025, 3, 30), date(2025, 4, 5)));
+        assert_eq!(get_week_for_date(date(2025, 4, 2), Weekday::Mon), (date(2025, 3, 31), date(2025, 4, 6)));
+
+        assert_eq!(WeekNumbering::Iso.week_number(date(2025, 3, 31)), 14);
+        assert_eq!(WeekNumbering::Us.week_number(date(2025, 3, 30)), 14);
+        // Неделя с 1 января — первая в обеих нумерациях
+        assert_eq!(WeekNumbering::Iso.week_number(date(2024, 12, 30)), 1);
+        assert_eq!(WeekNumbering::Us.week_number(date(2024, 12, 29)), 1);
+        assert_eq!(WeekNumbering::Iso.week_number(date(2026, 12, 28)), 53);
+
+        let mut days: Vec<DayAgenda> = (0..9).map(|i| DayAgenda::new(date(2025, 4, 2) + Duration::days(i))).collect();
+        label_weeks(&mut days, Weekday::Sun, WeekNumbering::Iso);
+        let labels: Vec<Option<u32>> = days.iter().map(|day| day.week).collect();
+        assert_eq!(labels, vec![Some(14), None, None, None, Some(15), None, None, None, None]);
+
+        // После сжатия номер получает первый оставшийся день недели
+        let mut days: Vec<DayAgenda> = [date(2025, 4, 3), date(2025, 4, 8), date(2025, 4, 9)].map(DayAgenda::new).into();
+        label_weeks(&mut days, Weekday::Sun, WeekNumbering::Iso);
+        let labels: Vec<Option<u32>> = days.iter().map(|day| day.week).collect();
+        assert_eq!(labels, vec![Some(14), Some(15), None]);
+    }
+
+    #[test]
+    fn test_parse_span() {
+        assert_eq!("10d".parse::<Span>(), Ok(Span::Days(10)));
+        assert_eq!("2w".parse::<Span>(), Ok(Span::Days(14)));
+        assert_eq!("Fortnight".parse::<Span>(), Ok(Span::Days(14)));
+        assert_eq!("quarter".parse::<Span>(), Ok(Span::Months(3)));
+        assert_eq!("year".parse::<Span>(), Ok(Span::Months(12)));
+        for s in ["", "0d", "d", "10", "10x", "-3d", "1.5m"] {
+            assert!(s.parse::<Span>().is_err(), "{s}");
+        }
+
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        assert_eq!(Span::Days(10).end_date(start), NaiveDate::from_ymd_opt(2024, 1, 10));
+        assert_eq!(Span::Months(3).end_date(start), NaiveDate::from_ymd_opt(2024, 3, 31));
+        assert_eq!(Span::Months(12).end_date(start), NaiveDate::from_ymd_opt(2024, 12, 31));
+    }
+
+    #[test]
+    fn test_span_agenda_compacts_long_ranges() {
+        let tasks = vec![
+            create_test_task("2025-01-15 Wed", None, TaskType::Todo),
+            create_test_task("2025-06-02 Mon", Some("10:00"), TaskType::Todo),
+            create_test_task_with_repeater("2025-01-20 Mon", None, "+1m", TaskType::Todo),
+        ];
+        let calendars = calendars();
+        let urgency = UrgencyCoefficients::default();
+        let start = DateExpr::Absolute(NaiveDate::from_ymd_opt(2025, 1, 1).unwrap());
+        let options = |span: &str, compact: bool| AgendaOptions {
+            mode: "span",
+            date: Some(&start),
+            from: None,
+            to: None,
+            tz: "UTC",
+            current_date: Some(&start),
+            filter: None,
+            sort: &[],
+            urgency: &urgency,
+            calendars: &calendars,
+            workday_offsets: false,
+            week_start: Weekday::Mon,
+            week_numbers: None,
+            span: Some(span.parse().unwrap()),
+            compact,
+        };
+        let agenda = |span: &str, compact: bool| match filter_agenda(tasks.clone(), &options(span, compact)).unwrap() {
+            AgendaOutput::Days(days) => days,
+            AgendaOutput::Tasks(_) => panic!("span agenda should list days"),
+        };
+
+        let days = agenda("fortnight", false);
+        assert_eq!(days.len(), 14);
+        assert_eq!(days[13].date, "2025-01-14");
+
+        let days = agenda("10d", true);
+        assert!(days.is_empty());
+        assert!(!is_compact(&options("fortnight", false)).unwrap());
+        assert!(is_compact(&options("10d", true)).unwrap());
+        assert!(is_compact(&options("year", false)).unwrap());
+
+        // Год: две разовые задачи и 12 повторов
+        let days = agenda("year", false);
+        assert_eq!(days.len(), 14);
+        assert_eq!(days[0].date, "2025-01-15");
+        assert_eq!(days[1].date, "2025-01-20");
+        assert_eq!(days[13].date, "2025-12-20");
+        assert!(days.iter().any(|day| day.date == "2025-06-02" && day.scheduled_timed.len() == 1));
     }
 
     #[test]
@@ -1021,7 +1420,7 @@ mod tests {
         let end_date = NaiveDate::from_ymd_opt(2024, 12, 31).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
         
-        let month = build_week_agenda(&tasks, start_date, end_date, current_date);
+        let month = build_week_agenda(&tasks, start_date, end_date, current_date, &calendars(), false);
         
         assert_eq!(month.len(), 31, "December should have 31 days");
         assert_eq!(month[0].date, "2024-12-01");
@@ -1040,7 +1439,7 @@ mod tests {
         let end_date = NaiveDate::from_ymd_opt(2024, 12, 31).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
         
-        let month = build_week_agenda(&tasks, start_date, end_date, current_date);
+        let month = build_week_agenda(&tasks, start_date, end_date, current_date, &calendars(), false);
         
         // Day 1 should be empty
         assert_eq!(month[0].scheduled_timed.len(), 0);
@@ -1057,7 +1456,7 @@ mod tests {
         
         // Current day should have overdue tasks
         assert_eq!(month[4].date, "2024-12-05");
-        assert!(month[4].overdue.len() > 0, "Current day should have overdue tasks");
+        assert!(!month[4].overdue.is_empty(), "Current day should have overdue tasks");
         
         // Future days should have scheduled tasks if applicable
         assert_eq!(month[9].scheduled_timed.len(), 1, "Day 10 should have scheduled task");
@@ -1073,7 +1472,7 @@ mod tests {
         let end_date = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap(); // Leap year
         let current_date = NaiveDate::from_ymd_opt(2024, 2, 10).unwrap();
         
-        let month = build_week_agenda(&tasks, start_date, end_date, current_date);
+        let month = build_week_agenda(&tasks, start_date, end_date, current_date, &calendars(), false);
         
         assert_eq!(month.len(), 29, "February 2024 (leap year) should have 29 days");
         assert_eq!(month[0].date, "2024-02-01");
@@ -1091,7 +1490,7 @@ mod tests {
         let end_date = NaiveDate::from_ymd_opt(2024, 12, 20).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 12).unwrap();
         
-        let range = build_week_agenda(&tasks, start_date, end_date, current_date);
+        let range = build_week_agenda(&tasks, start_date, end_date, current_date, &calendars(), false);
         
         assert_eq!(range.len(), 11, "Range should have 11 days (10-20 inclusive)");
         assert_eq!(range[0].date, "2024-12-10");
@@ -1108,7 +1507,7 @@ mod tests {
         
         let day_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.overdue.len(), 1, "Only TODO tasks should appear in overdue");
         assert_eq!(agenda.overdue[0].task.task_type, Some(TaskType::Todo));
@@ -1123,7 +1522,7 @@ mod tests {
         
         let day_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.scheduled_no_time.len(), 1, "DONE task without time should appear on its date");
         assert_eq!(agenda.scheduled_timed.len(), 1, "DONE task with time should appear on its date");
@@ -1139,7 +1538,7 @@ mod tests {
         
         let day_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.overdue.len(), 1, "Only TODO deadline should appear in overdue");
         assert_eq!(agenda.overdue[0].task.task_type, Some(TaskType::Todo));
@@ -1155,7 +1554,7 @@ mod tests {
         // Today is Saturday - next workday is Monday
         let day_date = NaiveDate::from_ymd_opt(2025, 12, 6).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2025, 12, 6).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         // Should NOT appear as overdue because next occurrence is Monday (in the future)
         assert_eq!(agenda.overdue.len(), 0, "Task with +1wd should not be overdue on Saturday");
@@ -1172,11 +1571,97 @@ mod tests {
         // Today is Sunday - next workday is Monday
         let day_date = NaiveDate::from_ymd_opt(2025, 12, 7).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2025, 12, 7).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.overdue.len(), 0, "Task with +1wd should not be overdue on Sunday");
     }
 
+    #[test]
+    fn test_workday_repeater_not_overdue_on_vacation() {
+        let tasks = vec![
+            create_test_task_with_repeater("2025-12-05 Fri", None, "+1wd", TaskType::Todo),
+        ];
+        let mut calendars = calendars();
+        let vacation: Vec<NaiveDate> = (8..=12).map(|d| NaiveDate::from_ymd_opt(2025, 12, d).unwrap()).collect();
+        calendars.add_days_off(&vacation);
+
+        // Wednesday in the middle of the vacation
+        let current_date = NaiveDate::from_ymd_opt(2025, 12, 10).unwrap();
+        let agenda = build_day_agenda(&tasks, current_date, current_date, &calendars, false);
+        assert_eq!(agenda.overdue.len(), 0, "Task with +1wd should not be overdue on vacation");
+        assert_eq!(agenda.scheduled_no_time.len(), 0);
+
+        // First workday after the vacation
+        let monday = NaiveDate::from_ymd_opt(2025, 12, 15).unwrap();
+        let agenda = build_day_agenda(&tasks, monday, current_date, &calendars, false);
+        assert_eq!(agenda.scheduled_no_time.len(), 1);
+    }
+
+    #[test]
+    fn test_day_agenda_marks_holidays() {
+        let date = |m, d| NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+        let holiday = build_day_agenda(&[], date(6, 12), date(6, 12), &calendars(), false);
+        assert!(!holiday.is_workday);
+        assert_eq!(holiday.holiday_name.as_deref(), Some("День России"));
+
+        let saturday = build_day_agenda(&[], date(6, 14), date(6, 12), &calendars(), false);
+        assert!(!saturday.is_workday);
+        assert_eq!(saturday.holiday_name, None);
+
+        let workday = build_day_agenda(&[], date(6, 11), date(6, 12), &calendars(), false);
+        assert!(workday.is_workday);
+        let json = serde_json::to_value(&workday).unwrap();
+        assert_eq!(json["is_workday"], true);
+        assert!(json.get("holiday_name").is_none());
+    }
+
+    #[test]
+    fn test_workday_warning_period_over_new_year() {
+        let tasks = vec![
+            create_test_task_with_type("2026-01-14 Wed -3wd", None, TaskType::Todo, "DEADLINE"),
+            create_test_task_with_type("2026-01-15 Thu -3wd", None, TaskType::Todo, "DEADLINE"),
+            create_test_task_with_type("2026-01-20 Tue", None, TaskType::Todo, "DEADLINE"),
+        ];
+        let current_date = NaiveDate::from_ymd_opt(2025, 12, 30).unwrap();
+
+        // 15 calendar days away, but only 3 workdays after the New Year holidays
+        let agenda = build_day_agenda(&tasks, current_date, current_date, &calendars(), false);
+        assert_eq!(agenda.upcoming.len(), 1);
+        assert_eq!(agenda.upcoming[0].days_offset, Some(15));
+        assert_eq!(agenda.upcoming[0].workdays_offset, None);
+
+        // Default window of 10 workdays also picks up the deadline 21 days away
+        let agenda = build_day_agenda(&tasks, current_date, current_date, &calendars(), true);
+        let offsets: Vec<_> = agenda.upcoming.iter().map(|e| (e.days_offset, e.workdays_offset)).collect();
+        assert_eq!(offsets, [(Some(15), Some(3)), (Some(21), Some(7))]);
+    }
+
+    #[test]
+    fn test_workday_offset_counts_days_off() {
+        let tasks = vec![
+            create_test_task_with_type("2025-12-10 Wed", None, TaskType::Todo, "DEADLINE"),
+            create_test_task_with_type("2025-12-10 Wed", None, TaskType::Todo, "SCHEDULED"),
+        ];
+        let current_date = NaiveDate::from_ymd_opt(2025, 12, 5).unwrap();
+        let agenda = build_day_agenda(&tasks, current_date, current_date, &calendars(), false);
+        assert_eq!(agenda.upcoming.len(), 1);
+        assert_eq!(agenda.upcoming[0].workdays_offset, None);
+
+        let agenda = build_day_agenda(&tasks, current_date, current_date, &calendars(), true);
+        assert_eq!(agenda.upcoming[0].workdays_offset, Some(3));
+
+        let mut calendars = calendars();
+        calendars.add_days_off(&[NaiveDate::from_ymd_opt(2025, 12, 9).unwrap()]);
+        let agenda = build_day_agenda(&tasks, current_date, current_date, &calendars, true);
+        assert_eq!(agenda.upcoming[0].days_offset, Some(5));
+        assert_eq!(agenda.upcoming[0].workdays_offset, Some(2));
+
+        let day_date = NaiveDate::from_ymd_opt(2025, 12, 10).unwrap();
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars, true);
+        assert_eq!(agenda.scheduled_no_time.len(), 2);
+        assert!(agenda.scheduled_no_time.iter().all(|e| e.workdays_offset.is_none()));
+    }
+
     #[test]
     fn test_year_repeater_shows_on_occurrence_day() {
         let tasks = vec![
@@ -1185,7 +1670,7 @@ mod tests {
         
         let day_date = NaiveDate::from_ymd_opt(2025, 12, 11).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2025, 12, 11).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.scheduled_no_time.len(), 1);
         assert_eq!(agenda.overdue.len(), 0);
@@ -1199,7 +1684,7 @@ mod tests {
         
         let day_date = NaiveDate::from_ymd_opt(2025, 12, 6).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2025, 12, 6).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.upcoming.len(), 1);
         assert_eq!(agenda.upcoming[0].days_offset, Some(5));
@@ -1213,7 +1698,7 @@ mod tests {
         
         let day_date = NaiveDate::from_ymd_opt(2025, 11, 21).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2025, 11, 21).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.upcoming.len(), 0);
     }
@@ -1226,7 +1711,7 @@ mod tests {
         
         let day_date = NaiveDate::from_ymd_opt(2025, 1, 5).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2025, 1, 5).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.scheduled_no_time.len(), 1);
     }
@@ -1240,7 +1725,7 @@ mod tests {
         // Today is Monday - this is the next occurrence day
         let day_date = NaiveDate::from_ymd_opt(2025, 12, 8).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2025, 12, 8).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.scheduled_no_time.len(), 1, "Task should be scheduled on Monday");
         assert_eq!(agenda.overdue.len(), 0, "Task should not be overdue on its occurrence day");
@@ -1258,14 +1743,14 @@ mod tests {
         // По логике org-mode показывается, даже если это прошлая дата
         let day_date = NaiveDate::from_ymd_opt(2025, 12, 5).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2025, 12, 7).unwrap(); // Сегодня воскресенье
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.scheduled_no_time.len(), 1, "Task should be shown on deadline day (org-mode logic)");
         assert_eq!(agenda.overdue.len(), 0);
         
         // Проверим будущий occurrence day (2026-12-05)
         let future_day = NaiveDate::from_ymd_opt(2026, 12, 5).unwrap();
-        let agenda_future = build_day_agenda(&tasks, future_day, current_date);
+        let agenda_future = build_day_agenda(&tasks, future_day, current_date, &calendars(), false);
         
         assert_eq!(agenda_future.scheduled_no_time.len(), 1, "Future occurrence day should show task");
         assert_eq!(agenda_future.scheduled_no_time[0].task.timestamp_date, Some("2026-12-05".to_string()));
@@ -1283,7 +1768,7 @@ mod tests {
         // Воскресенье 2025-12-07 - через 2 дня после дедлайна
         let day_date = NaiveDate::from_ymd_opt(2025, 12, 7).unwrap();
         let current_date = NaiveDate::from_ymd_opt(2025, 12, 7).unwrap();
-        let agenda = build_day_agenda(&tasks, day_date, current_date);
+        let agenda = build_day_agenda(&tasks, day_date, current_date, &calendars(), false);
         
         assert_eq!(agenda.overdue.len(), 1, "Task should be overdue on Sunday");
         assert_eq!(agenda.overdue[0].days_offset, Some(-2), "Task should be 2 days overdue");
@@ -1292,4 +1777,67 @@ mod tests {
         assert_eq!(agenda.overdue[0].task.timestamp_date, Some("2025-12-05".to_string()));
         assert!(agenda.overdue[0].task.timestamp.as_ref().unwrap().contains("2025-12-05"));
     }
+
+    /// The per-day algorithm the range agenda replaced: every day re-parses each
+    /// timestamp and searches its repeater from the base date with `closest_date`
+    fn per_day_agenda(tasks: &[Task], day_date: NaiveDate, current_date: NaiveDate, calendars: &Calendars) -> DayAgenda {
+        let parsed: Vec<(&Task, ParsedTimestamp)> = tasks
+            .iter()
+            .filter_map(|task| Some((task, parse_org_timestamp(task.timestamp.as_deref()?, None)?)))
+            .collect();
+        let entries: Vec<AgendaTask> = parsed
+            .iter()
+            .map(|(task, parsed)| {
+                let (deadline, occurrences) = match parsed.repeater {
+                    Some(ref repeater) => {
+                        let calendar = calendars.for_task(task);
+                        let next = closest_date(parsed.date, day_date, DatePreference::Future, repeater, calendar);
+                        (
+                            closest_date(parsed.date, current_date, DatePreference::Past, repeater, calendar),
+                            next.into_iter().filter(|date| *date > current_date).collect(),
+                        )
+                    }
+                    None => (None, Vec::new()),
+                };
+                AgendaTask { task, parsed, deadline, occurrences }
+            })
+            .collect();
+        build_range_day_agenda(&entries, day_date, current_date, calendars, false)
+    }
+
+    /// `cargo test --release -- --ignored bench_year_agenda --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_year_agenda_50k_tasks() {
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2025, 12, 31).unwrap();
+        let repeaters = ["+1w", "+2w", "+1m", "+3m", "+1y", "+5wd"];
+        let tasks: Vec<Task> = (0..50_000)
+            .map(|i| {
+                let date = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap() + Duration::days(i % 2500);
+                let date_str = date.format("%Y-%m-%d %a").to_string();
+                match i % 4 {
+                    0 => create_test_task_with_repeater(&date_str, None, repeaters[(i / 4) as usize % repeaters.len()], TaskType::Todo),
+                    1 => create_test_task_with_type(&date_str, None, TaskType::Todo, "DEADLINE"),
+                    _ => create_test_task(&date_str, Some("10:00"), TaskType::Todo),
+                }
+            })
+            .collect();
+        let calendars = calendars();
+
+        let timer = std::time::Instant::now();
+        let range = build_week_agenda(&tasks, start, end, start, &calendars, false);
+        let range_time = timer.elapsed();
+
+        let timer = std::time::Instant::now();
+        let by_day: Vec<DayAgenda> = start
+            .iter_days()
+            .take_while(|day| *day <= end)
+            .map(|day| per_day_agenda(&tasks, day, start, &calendars))
+            .collect();
+        let by_day_time = timer.elapsed();
+
+        eprintln!("year agenda over 50k tasks: {range_time:?} in one pass, {by_day_time:?} parsing and searching day by day");
+        assert_eq!(serde_json::to_value(&range).unwrap(), serde_json::to_value(&by_day).unwrap());
+    }
 }
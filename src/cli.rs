@@ -1,58 +1,178 @@
-use chrono::NaiveDate;
-use clap::Parser;
+use chrono::Weekday;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand};
 use std::path::PathBuf;
 
+use crate::agenda::{Span, WeekNumbering};
+use crate::date_expr::{parse_date_expr, parse_weekday, DateExpr};
 use crate::format::OutputFormat;
+use crate::holidays::{HolidayFile, DEFAULT_CALENDAR};
+use crate::locale::{validate_weekday_locales, OutputLocale};
+use crate::sort::SortKey;
+use crate::urgency::UrgencyCoefficients;
 
 #[derive(Parser)]
 #[command(name = "markdown-org-extract")]
 #[command(about = "Extract tasks from markdown files with org-mode timestamps")]
 #[command(version)]
 pub struct Cli {
-    #[arg(long, default_value = ".")]
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[arg(long, default_value = ".", global = true)]
     pub dir: PathBuf,
 
-    #[arg(long, default_value = "*.md")]
-    pub glob: String,
+    /// File patterns, comma-separated: `*.ext` or an exact file name
+    /// (default: *.md,*.org,*.ics for tasks, *.md for lint and fmt)
+    #[arg(long, global = true)]
+    pub glob: Option<String>,
 
-    #[arg(long, default_value = "json", value_parser = parse_format)]
+    #[arg(long, default_value = "json", value_parser = parse_format, global = true)]
     pub format: OutputFormat,
 
-    #[arg(long)]
+    #[arg(long, global = true)]
     pub output: Option<PathBuf>,
 
-    #[arg(long, default_value = "ru,en")]
+    /// Languages of weekday names in input timestamps, comma-separated
+    #[arg(long, default_value = "ru,en", value_parser = parse_weekday_locales, global = true)]
     pub locale: String,
 
+    /// Language of output labels and dates: en, ru
+    #[arg(long, default_value = "en", value_parser = parse_output_locale, global = true)]
+    pub output_locale: OutputLocale,
+
     #[arg(long, default_value = "day", value_parser = ["day", "week", "month"], conflicts_with = "tasks")]
     pub agenda: String,
 
     #[arg(long)]
     pub tasks: bool,
 
-    #[arg(long, value_parser = validate_date)]
-    pub date: Option<String>,
+    #[arg(long, value_parser = parse_date_expr)]
+    pub date: Option<DateExpr>,
+
+    #[arg(long, value_parser = parse_date_expr)]
+    pub from: Option<DateExpr>,
+
+    #[arg(long, value_parser = parse_date_expr)]
+    pub to: Option<DateExpr>,
+
+    /// Agenda of a span starting at --date: 10d, 2w, 3m, fortnight, quarter or year
+    #[arg(long, value_parser = parse_span, conflicts_with_all = ["agenda", "tasks", "from", "to"])]
+    pub span: Option<Span>,
+
+    /// List only days with entries in week, month and span agendas (spans over a month always are)
+    #[arg(long)]
+    pub compact: bool,
 
-    #[arg(long, value_parser = validate_date)]
-    pub from: Option<String>,
+    /// First day of the week for --agenda week, e.g. mon, sun or вс
+    #[arg(long, default_value = "mon", value_parser = parse_week_start)]
+    pub week_start: Weekday,
 
-    #[arg(long, value_parser = validate_date)]
-    pub to: Option<String>,
+    /// Label weeks of week and month agendas with ISO or US week numbers
+    #[arg(long, value_name = "iso|us", value_parser = parse_week_numbering)]
+    pub week_numbers: Option<WeekNumbering>,
 
     #[arg(long, default_value = "Europe/Moscow", value_parser = validate_timezone)]
     pub tz: String,
 
-    #[arg(long, value_parser = validate_date)]
-    pub current_date: Option<String>,
+    #[arg(long, value_parser = parse_date_expr)]
+    pub current_date: Option<DateExpr>,
 
     #[arg(long, value_parser = validate_year)]
     pub holidays: Option<i32>,
+
+    /// With --holidays, list holiday names and transferred working days as objects
+    #[arg(long, requires = "holidays")]
+    pub holiday_details: bool,
+
+    /// Holiday calendar for workday repeaters and --holidays: ru, de, rs
+    /// or a code loaded with --holiday-file. A HOLIDAY_CALENDAR property
+    /// overrides it per file or task
+    #[arg(long, default_value = DEFAULT_CALENDAR, global = true)]
+    pub holiday_calendar: String,
+
+    /// Extra holidays from a JSON or .ics file as [CODE=]PATH, repeatable.
+    /// Without CODE the file applies to --holiday-calendar
+    #[arg(long = "holiday-file", value_name = "[CODE=]PATH", value_parser = parse_holiday_file, global = true)]
+    pub holiday_files: Vec<HolidayFile>,
+
+    /// Holiday files replace built-in calendars of the same code instead of extending them
+    #[arg(long, global = true)]
+    pub replace_holidays: bool,
+
+    /// File with personal days off as `VACATION: <2025-07-01>--<2025-07-14>` entries, repeatable
+    #[arg(long = "days-off", value_name = "PATH", global = true)]
+    pub days_off: Vec<PathBuf>,
+
+    /// Count agenda offsets and the default deadline warning window (10 workdays) in workdays
+    #[arg(long)]
+    pub workday_offsets: bool,
+
+    /// Filter expression, e.g. "priority<=B and tag:work and deadline<+7d"
+    #[arg(long)]
+    pub filter: Option<String>,
+
+    /// Comma-separated sort keys, "-" for descending, e.g. "deadline,-priority"
+    #[arg(long, value_delimiter = ',', value_parser = parse_sort_key)]
+    pub sort: Vec<SortKey>,
+
+    /// Urgency weights as NAME=VALUE pairs, e.g. "deadline=15,tags=0"
+    #[arg(long, value_parser = parse_urgency_coefficients)]
+    pub urgency_coefficients: Option<UrgencyCoefficients>,
+
+    /// Comma-separated columns for csv/tsv output, named after the JSON fields
+    #[arg(long, value_delimiter = ',', value_parser = crate::csv::parse_column)]
+    pub columns: Vec<String>,
+
+    /// Render output through a template file instead of the --format layout
+    #[arg(long)]
+    pub template: Option<PathBuf>,
+
+    /// Output one csv/tsv row per CLOCK entry, limited to --from/--to when given (needs --format csv or tsv)
+    #[arg(long)]
+    pub clocks: bool,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Move a heading subtree (with timestamps, clocks and children) under another heading
+    Refile {
+        /// Source heading as FILE:LINE
+        source: String,
+        /// Destination as FILE or FILE:HEADING
+        dest: String,
+    },
+    /// Report malformed or suspicious timestamps, CLOCK entries and planning lines
+    Lint,
+    /// Rewrite timestamps into canonical form (correct weekday, spacing, repeaters)
+    Fmt {
+        /// Only report files that need formatting, do not modify them
+        #[arg(long)]
+        check: bool,
+
+        /// Language of weekday names in rewritten timestamps
+        #[arg(long, default_value = "auto", value_parser = ["auto", "ru", "en"])]
+        weekday_locale: String,
+    },
 }
 
 impl Cli {
+    /// Parse arguments and check combinations clap can't express, exiting on errors
+    pub fn parse_checked() -> Self {
+        let cli = Self::parse();
+        if cli.clocks && !matches!(cli.format, OutputFormat::Csv | OutputFormat::Tsv) {
+            Self::command()
+                .error(ErrorKind::ArgumentConflict, "--clocks needs --format csv or tsv")
+                .exit();
+        }
+        cli
+    }
+
     pub fn get_agenda_mode(&self) -> &str {
         if self.tasks {
             "tasks"
+        } else if self.span.is_some() {
+            "span"
         } else {
             &self.agenda
         }
@@ -63,10 +183,32 @@ fn parse_format(s: &str) -> Result<OutputFormat, String> {
     s.parse()
 }
 
-fn validate_date(s: &str) -> Result<String, String> {
-    NaiveDate::parse_from_str(s, "%Y-%m-%d")
-        .map(|_| s.to_string())
-        .map_err(|e| format!("Invalid date '{s}': {e}. Use YYYY-MM-DD format"))
+fn parse_weekday_locales(s: &str) -> Result<String, String> {
+    validate_weekday_locales(s).map(|()| s.to_string())
+}
+
+fn parse_output_locale(s: &str) -> Result<OutputLocale, String> {
+    s.parse()
+}
+
+fn parse_sort_key(s: &str) -> Result<SortKey, String> {
+    s.parse()
+}
+
+fn parse_urgency_coefficients(s: &str) -> Result<UrgencyCoefficients, String> {
+    s.parse()
+}
+
+fn parse_week_start(s: &str) -> Result<Weekday, String> {
+    parse_weekday(s.trim()).ok_or_else(|| format!("Invalid weekday '{s}'. Use mon, tue, ..., sun"))
+}
+
+fn parse_span(s: &str) -> Result<Span, String> {
+    s.parse()
+}
+
+fn parse_week_numbering(s: &str) -> Result<WeekNumbering, String> {
+    s.parse()
 }
 
 fn validate_year(s: &str) -> Result<i32, String> {
@@ -80,59 +222,12 @@ fn validate_year(s: &str) -> Result<i32, String> {
     Ok(year)
 }
 
+fn parse_holiday_file(s: &str) -> Result<HolidayFile, String> {
+    s.parse()
+}
+
 fn validate_timezone(s: &str) -> Result<String, String> {
     s.parse::<chrono_tz::Tz>()
         .map(|_| s.to_string())
         .map_err(|_| format!("Invalid timezone '{s}'. Use IANA timezone names (e.g., 'Europe/Moscow', 'UTC')"))
 }
-
-pub fn get_weekday_mappings(locale: &str) -> Vec<(&'static str, &'static str)> {
-    let locales: Vec<&str> = locale.split(',').map(|s| s.trim()).collect();
-    let mut mappings = Vec::new();
-
-    for loc in locales {
-        if loc == "ru" {
-            mappings.extend_from_slice(&[
-                ("Понедельник", "Monday"),
-                ("Вторник", "Tuesday"),
-                ("Среда", "Wednesday"),
-                ("Четверг", "Thursday"),
-                ("Пятница", "Friday"),
-                ("Суббота", "Saturday"),
-                ("Воскресенье", "Sunday"),
-                ("Пн", "Mon"),
-                ("Вт", "Tue"),
-                ("Ср", "Wed"),
-                ("Чт", "Thu"),
-                ("Пт", "Fri"),
-                ("Сб", "Sat"),
-                ("Вс", "Sun"),
-            ]);
-        }
-    }
-    mappings
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_get_weekday_mappings_ru() {
-        let mappings = get_weekday_mappings("ru");
-        assert!(mappings.contains(&("Понедельник", "Monday")));
-        assert!(mappings.contains(&("Пн", "Mon")));
-    }
-
-    #[test]
-    fn test_get_weekday_mappings_multiple() {
-        let mappings = get_weekday_mappings("ru,en");
-        assert!(mappings.contains(&("Понедельник", "Monday")));
-    }
-
-    #[test]
-    fn test_get_weekday_mappings_empty() {
-        let mappings = get_weekday_mappings("en");
-        assert!(mappings.is_empty());
-    }
-}
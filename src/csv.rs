@@ -0,0 +1,344 @@
+use chrono::NaiveDate;
+
+use crate::types::{ClockEntry, DayAgenda, Task, TaskType};
+
+/// Task columns, named after the JSON fields of `Task`
+pub const TASK_COLUMNS: &[&str] = &[
+    "file",
+    "line",
+    "heading",
+    "content",
+    "task_type",
+    "priority",
+    "created",
+    "timestamp",
+    "timestamp_type",
+    "timestamp_date",
+    "timestamp_time",
+    "timestamp_end_time",
+    "total_clock_time",
+    "tags",
+    "properties",
+    "urgency",
+    "uid",
+];
+
+/// Columns describing the position of an entry in a day agenda
+pub const AGENDA_COLUMNS: &[&str] = &["date", "is_workday", "holiday_name", "section", "days_offset", "workdays_offset"];
+
+/// Columns of a single CLOCK entry
+pub const CLOCK_COLUMNS: &[&str] = &["clock_start", "clock_end", "clock_duration"];
+
+/// Field separator of the table
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Delimiter {
+    Comma,
+    Tab,
+}
+
+/// One output row: a task plus its agenda or clock context
+struct Row<'a> {
+    task: &'a Task,
+    day: Option<&'a DayAgenda>,
+    section: Option<&'a str>,
+    days_offset: Option<i64>,
+    workdays_offset: Option<i64>,
+    clock: Option<&'a ClockEntry>,
+}
+
+impl<'a> Row<'a> {
+    fn task(task: &'a Task) -> Self {
+        Self { task, day: None, section: None, days_offset: None, workdays_offset: None, clock: None }
+    }
+}
+
+/// Validate a column name for `--columns`
+pub fn parse_column(s: &str) -> Result<String, String> {
+    let name = s.trim();
+    if TASK_COLUMNS.contains(&name) || AGENDA_COLUMNS.contains(&name) || CLOCK_COLUMNS.contains(&name) {
+        Ok(name.to_string())
+    } else {
+        Err(format!(
+            "Invalid column: {name}. Valid columns: {}, {}, {}",
+            AGENDA_COLUMNS.join(", "),
+            TASK_COLUMNS.join(", "),
+            CLOCK_COLUMNS.join(", ")
+        ))
+    }
+}
+
+/// Render a flat task list, one row per task
+pub fn render_tasks_table(tasks: &[Task], delimiter: Delimiter, columns: &[String]) -> String {
+    let columns = columns_or(columns, &[TASK_COLUMNS]);
+    let rows: Vec<Row> = tasks.iter().map(Row::task).collect();
+    render_table(&rows, delimiter, &columns)
+}
+
+/// Render day agendas, one row per agenda entry with its section
+pub fn render_days_table(days: &[DayAgenda], delimiter: Delimiter, columns: &[String]) -> String {
+    let columns = columns_or(columns, &[AGENDA_COLUMNS, TASK_COLUMNS]);
+    let mut rows = Vec::new();
+    for day in days {
+        let sections = [
+            ("overdue", &day.overdue),
+            ("scheduled_timed", &day.scheduled_timed),
+            ("scheduled_no_time", &day.scheduled_no_time),
+            ("upcoming", &day.upcoming),
+        ];
+        for (section, entries) in sections {
+            for entry in entries {
+                rows.push(Row {
+                    task: &entry.task,
+                    day: Some(day),
+                    section: Some(section),
+                    days_offset: entry.days_offset,
+                    workdays_offset: entry.workdays_offset,
+                    clock: None,
+                });
+            }
+        }
+    }
+    render_table(&rows, delimiter, &columns)
+}
+
+/// Render CLOCK entries, one row per entry; `from` and `to` each bound the start date
+pub fn render_clocks_table(
+    tasks: &[Task],
+    delimiter: Delimiter,
+    columns: &[String],
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> String {
+    let columns = columns_or(columns, &[&["file", "line", "heading", "task_type", "tags"], CLOCK_COLUMNS]);
+    let mut rows = Vec::new();
+    for task in tasks {
+        for clock in task.clocks.iter().flatten() {
+            let in_range = match clock_date(clock) {
+                Some(date) => from.is_none_or(|from| from <= date) && to.is_none_or(|to| date <= to),
+                None => from.is_none() && to.is_none(),
+            };
+            if in_range {
+                rows.push(Row { clock: Some(clock), ..Row::task(task) });
+            }
+        }
+    }
+    render_table(&rows, delimiter, &columns)
+}
+
+fn columns_or<'a>(columns: &'a [String], defaults: &[&[&'a str]]) -> Vec<&'a str> {
+    if columns.is_empty() {
+        defaults.iter().flat_map(|group| group.iter().copied()).collect()
+    } else {
+        columns.iter().map(String::as_str).collect()
+    }
+}
+
+fn render_table(rows: &[Row], delimiter: Delimiter, columns: &[&str]) -> String {
+    let mut output = String::new();
+    write_record(&mut output, delimiter, columns.iter().map(|c| c.to_string()));
+    for row in rows {
+        write_record(&mut output, delimiter, columns.iter().map(|c| cell(row, c)));
+    }
+    output
+}
+
+fn write_record(output: &mut String, delimiter: Delimiter, fields: impl Iterator<Item = String>) {
+    let separator = match delimiter {
+        Delimiter::Comma => ",",
+        Delimiter::Tab => "\t",
+    };
+    let fields: Vec<String> = fields.map(|f| escape_field(&f, delimiter)).collect();
+    output.push_str(&fields.join(separator));
+    // RFC 4180 uses CRLF; TSV is conventionally LF-terminated
+    output.push_str(match delimiter {
+        Delimiter::Comma => "\r\n",
+        Delimiter::Tab => "\n",
+    });
+}
+
+/// Quote CSV fields when needed; TSV cannot quote, so tabs and newlines become spaces
+///
+/// Text starting with `=`, `+`, `-` or `@` gets a `'` prefix, so spreadsheets
+/// don't run headings from notes as formulas; numbers like `-3` stay as they are.
+fn escape_field(field: &str, delimiter: Delimiter) -> String {
+    let guarded;
+    let field = if field.starts_with(['=', '+', '-', '@']) && field.parse::<f64>().is_err() {
+        guarded = format!("'{field}");
+        guarded.as_str()
+    } else {
+        field
+    };
+    match delimiter {
+        Delimiter::Comma => {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        }
+        Delimiter::Tab => field.replace(['\t', '\n'], " ").replace('\r', ""),
+    }
+}
+
+fn cell(row: &Row, column: &str) -> String {
+    let task = row.task;
+    let opt = |value: &Option<String>| value.clone().unwrap_or_default();
+    match column {
+        "date" => row.day.map(|day| day.date.clone()).unwrap_or_default(),
+        "is_workday" => row.day.map(|day| day.is_workday.to_string()).unwrap_or_default(),
+        "holiday_name" => row.day.and_then(|day| day.holiday_name.clone()).unwrap_or_default(),
+        "section" => row.section.unwrap_or_default().to_string(),
+        "days_offset" => row.days_offset.map(|o| o.to_string()).unwrap_or_default(),
+        "workdays_offset" => row.workdays_offset.map(|o| o.to_string()).unwrap_or_default(),
+        "file" => task.file.clone(),
+        "line" => task.line.to_string(),
+        "heading" => task.heading.clone(),
+        "content" => task.content.clone(),
+        "task_type" => match task.task_type {
+            Some(TaskType::Todo) => "TODO".to_string(),
+            Some(TaskType::Done) => "DONE".to_string(),
+            None => String::new(),
+        },
+        "priority" => task.priority.as_ref().map(|p| p.as_char().to_string()).unwrap_or_default(),
+        "created" => opt(&task.created),
+        "timestamp" => opt(&task.timestamp),
+        "timestamp_type" => opt(&task.timestamp_type),
+        "timestamp_date" => opt(&task.timestamp_date),
+        "timestamp_time" => opt(&task.timestamp_time),
+        "timestamp_end_time" => opt(&task.timestamp_end_time),
+        "total_clock_time" => opt(&task.total_clock_time),
+        "tags" => task.tags.as_ref().map(|t| t.join(" ")).unwrap_or_default(),
+        "properties" => task
+            .properties
+            .as_ref()
+            .map(|props| {
+                props
+                    .iter()
+                    .map(|(k, v)| format!("{k}={v}"))
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            })
+            .unwrap_or_default(),
+        "urgency" => task.urgency.as_ref().map(|u| u.score.to_string()).unwrap_or_default(),
+        "uid" => opt(&task.uid),
+        "clock_start" => row.clock.map(|c| c.start.clone()).unwrap_or_default(),
+        "clock_end" => row.clock.and_then(|c| c.end.clone()).unwrap_or_default(),
+        "clock_duration" => row.clock.and_then(|c| c.duration.clone()).unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+fn clock_date(clock: &ClockEntry) -> Option<NaiveDate> {
+    clock
+        .start
+        .get(..10)
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Priority, TaskWithOffset};
+
+    fn task() -> Task {
+        Task {
+            file: "notes/work.md".to_string(),
+            line: 7,
+            heading: "Review \"Q1\", budget".to_string(),
+            content: "Line one\nLine two".to_string(),
+            task_type: Some(TaskType::Todo),
+            priority: Some(Priority::A),
+            created: None,
+            timestamp: Some("DEADLINE: <2025-01-15 Wed>".to_string()),
+            timestamp_type: Some("DEADLINE".to_string()),
+            timestamp_date: Some("2025-01-15".to_string()),
+            timestamp_time: None,
+            timestamp_end_time: None,
+            clocks: Some(vec![
+                ClockEntry {
+                    start: "2025-01-09 Thu 10:00".to_string(),
+                    end: Some("2025-01-09 Thu 11:30".to_string()),
+                    duration: Some("1:30".to_string()),
+                },
+                ClockEntry {
+                    start: "2024-12-30 Mon 09:00".to_string(),
+                    end: Some("2024-12-30 Mon 10:00".to_string()),
+                    duration: Some("1:00".to_string()),
+                },
+            ]),
+            total_clock_time: Some("2:30".to_string()),
+            tags: Some(vec!["work".to_string(), "finance".to_string()]),
+            properties: None,
+            urgency: None,
+            uid: None,
+            parsed_timestamp: None,
+        }
+    }
+
+    fn columns(spec: &str) -> Vec<String> {
+        spec.split(',').map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_csv_quoting_and_header() {
+        let csv = render_tasks_table(&[task()], Delimiter::Comma, &columns("line,heading,content,priority,tags"));
+        assert_eq!(
+            csv,
+            "line,heading,content,priority,tags\r\n7,\"Review \"\"Q1\"\", budget\",\"Line one\nLine two\",A,work finance\r\n"
+        );
+    }
+
+    #[test]
+    fn test_formula_cells_guarded() {
+        assert_eq!(escape_field("=HYPERLINK(\"x\")", Delimiter::Comma), "\"'=HYPERLINK(\"\"x\"\")\"");
+        assert_eq!(escape_field("@SUM(A1)", Delimiter::Tab), "'@SUM(A1)");
+        assert_eq!(escape_field("- item", Delimiter::Tab), "'- item");
+        assert_eq!(escape_field("-3", Delimiter::Comma), "-3");
+        assert_eq!(escape_field("+1.5", Delimiter::Comma), "+1.5");
+    }
+
+    #[test]
+    fn test_default_columns_match_json_fields() {
+        let csv = render_tasks_table(&[task()], Delimiter::Comma, &[]);
+        let header = csv.lines().next().unwrap();
+        assert_eq!(header.trim_end(), TASK_COLUMNS.join(","));
+
+        let json = serde_json::to_value(task()).unwrap();
+        for column in ["file", "line", "heading", "task_type", "timestamp_date", "tags"] {
+            assert!(json.get(column).is_some(), "{column} is not a JSON field");
+        }
+    }
+
+    #[test]
+    fn test_tsv_days_with_sections() {
+        let mut day = DayAgenda::new(NaiveDate::from_ymd_opt(2025, 1, 10).unwrap());
+        let mut entry = TaskWithOffset::new(task(), Some(5));
+        entry.workdays_offset = Some(3);
+        day.upcoming.push(entry);
+        let tsv = render_days_table(&[day], Delimiter::Tab, &columns("date,section,days_offset,workdays_offset,content"));
+        assert_eq!(
+            tsv,
+            "date\tsection\tdays_offset\tworkdays_offset\tcontent\n2025-01-10\tupcoming\t5\t3\tLine one Line two\n"
+        );
+    }
+
+    #[test]
+    fn test_clock_rows_in_range() {
+        let from = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let to = NaiveDate::from_ymd_opt(2025, 1, 31).unwrap();
+        let clocks = |from, to| render_clocks_table(&[task()], Delimiter::Comma, &columns("line,clock_start"), from, to);
+        assert_eq!(clocks(Some(from), Some(to)), "line,clock_start\r\n7,2025-01-09 Thu 10:00\r\n");
+        assert_eq!(clocks(Some(from), None), clocks(Some(from), Some(to)));
+        assert_eq!(clocks(None, Some(from)), "line,clock_start\r\n7,2024-12-30 Mon 09:00\r\n");
+
+        let all = render_clocks_table(&[task()], Delimiter::Comma, &[], None, None);
+        assert_eq!(all.lines().count(), 3);
+    }
+
+    #[test]
+    fn test_parse_column() {
+        assert_eq!(parse_column("heading"), Ok("heading".to_string()));
+        assert!(parse_column("section").is_ok());
+        assert!(parse_column("size").is_err());
+    }
+}
@@ -0,0 +1,232 @@
+use chrono::{Datelike, Duration, NaiveDate, TimeDelta, Weekday};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::str::FromStr;
+
+use crate::error::AppError;
+use crate::holidays::HolidayCalendar;
+use crate::locale::{find_weekday, get_weekday_mappings, WEEKDAY_LOCALES};
+use crate::timestamp::add_months;
+
+static OFFSET_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^([+-])(\d+)(wd|[dwmy])$").expect("Invalid OFFSET_RE regex")
+});
+
+/// Largest `wd` offset, so stepping through the calendar day by day stays quick
+const MAX_WORKDAYS: i64 = 100_000;
+
+static ISO_WEEK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(\d{4})-w(\d{1,2})$").expect("Invalid ISO_WEEK_RE regex")
+});
+
+/// Weekday names of every supported language, so `пн` works regardless of `--locale`
+static WEEKDAY_MAPPINGS: Lazy<Vec<(&'static str, &'static str)>> = Lazy::new(|| {
+    let codes: Vec<&str> = WEEKDAY_LOCALES.iter().map(|names| names.code).collect();
+    get_weekday_mappings(&codes.join(","))
+});
+
+/// Date given on the command line, absolute or relative to the current date
+///
+/// Accepts `YYYY-MM-DD`, `today`/`tomorrow`/`yesterday`, `+3d`, `-1w`, `+1m`,
+/// `+1y`, `+2wd` (workdays of the holiday calendar), a weekday (`fri`, `пн`)
+/// for the nearest such day from today on, `next fri` for the first one after
+/// today, `eom` for the end of the month and ISO weeks (`2025-W14`, Monday).
+#[derive(Debug, Clone, PartialEq)]
+pub enum DateExpr {
+    Absolute(NaiveDate),
+    Days(i64),
+    Months(i32),
+    Workdays(i64),
+    Weekday { weekday: Weekday, next: bool },
+    EndOfMonth,
+}
+
+impl FromStr for DateExpr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.trim().to_lowercase();
+        let invalid = || {
+            format!("Invalid date '{s}'. Use YYYY-MM-DD, today, tomorrow, +3d, -1w, +2wd, fri, next fri, eom or 2025-W14")
+        };
+
+        match value.as_str() {
+            "today" | "сегодня" => return Ok(DateExpr::Days(0)),
+            "tomorrow" | "завтра" => return Ok(DateExpr::Days(1)),
+            "yesterday" | "вчера" => return Ok(DateExpr::Days(-1)),
+            "eom" => return Ok(DateExpr::EndOfMonth),
+            _ => {}
+        }
+
+        if let Ok(date) = NaiveDate::parse_from_str(&value, "%Y-%m-%d") {
+            return Ok(DateExpr::Absolute(date));
+        }
+
+        if let Some(caps) = ISO_WEEK_RE.captures(&value) {
+            let year: i32 = caps[1].parse().map_err(|_| invalid())?;
+            let week: u32 = caps[2].parse().map_err(|_| invalid())?;
+            let monday = NaiveDate::from_isoywd_opt(year, week, Weekday::Mon)
+                .ok_or_else(|| format!("Invalid date '{s}': {year} has no ISO week {week}"))?;
+            return Ok(DateExpr::Absolute(monday));
+        }
+
+        if let Some(caps) = OFFSET_RE.captures(&value) {
+            let amount: i64 = caps[2].parse().map_err(|_| invalid())?;
+            let amount = if &caps[1] == "-" { -amount } else { amount };
+            let months = |factor: i32| {
+                i32::try_from(amount).ok().and_then(|months| months.checked_mul(factor)).map(DateExpr::Months)
+            };
+            let expr = match &caps[3] {
+                "d" => Some(DateExpr::Days(amount)),
+                "w" => amount.checked_mul(7).map(DateExpr::Days),
+                "m" => months(1),
+                "y" => months(12),
+                _ => Some(DateExpr::Workdays(amount)),
+            };
+            return expr.filter(DateExpr::in_range).ok_or_else(|| format!("Date offset '{s}' is out of range"));
+        }
+
+        let (next, name) = match value.split_once(char::is_whitespace) {
+            Some(("next", name)) => (true, name.trim()),
+            Some(_) => return Err(invalid()),
+            None => (false, value.as_str()),
+        };
+        parse_weekday(name)
+            .map(|weekday| DateExpr::Weekday { weekday, next })
+            .ok_or_else(invalid)
+    }
+}
+
+impl DateExpr {
+    /// Date relative to `today`; workday offsets skip non-working days of `calendar`
+    pub fn resolve(&self, today: NaiveDate, calendar: &HolidayCalendar) -> Result<NaiveDate, AppError> {
+        let date = match *self {
+            DateExpr::Absolute(date) => Some(date),
+            DateExpr::Days(days) => add_days(today, days),
+            DateExpr::Months(months) => add_months(today, months),
+            DateExpr::Workdays(workdays) => add_workdays(today, workdays, calendar),
+            DateExpr::Weekday { weekday, next } => {
+                let ahead = (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+                let ahead = if next && ahead == 0 { 7 } else { ahead };
+                add_days(today, ahead as i64)
+            }
+            DateExpr::EndOfMonth => today
+                .with_day(1)
+                .and_then(|first| add_months(first, 1))
+                .and_then(|next_month| next_month.pred_opt()),
+        };
+        date.ok_or_else(|| AppError::InvalidDate(format!("{self:?} from {today} is out of range")))
+    }
+
+    /// Whether an offset fits between the first and the last representable date
+    fn in_range(&self) -> bool {
+        match *self {
+            DateExpr::Days(days) => add_days(NaiveDate::MIN, days.abs()).is_some(),
+            DateExpr::Months(months) => months.checked_abs().and_then(|months| add_months(NaiveDate::MIN, months)).is_some(),
+            DateExpr::Workdays(workdays) => workdays.abs() <= MAX_WORKDAYS,
+            _ => true,
+        }
+    }
+}
+
+/// clap value parser for date options
+pub fn parse_date_expr(s: &str) -> Result<DateExpr, String> {
+    s.parse()
+}
+
+/// English or localized weekday name, full or abbreviated
+pub fn parse_weekday(name: &str) -> Option<Weekday> {
+    if let Ok(weekday) = name.parse::<Weekday>() {
+        return Some(weekday);
+    }
+    find_weekday(name, &WEEKDAY_MAPPINGS).and_then(|english| english.parse().ok())
+}
+
+fn add_days(date: NaiveDate, days: i64) -> Option<NaiveDate> {
+    TimeDelta::try_days(days).and_then(|delta| date.checked_add_signed(delta))
+}
+
+/// Step `workdays` working days forward (or back when negative); `None` past
+/// the last representable date
+fn add_workdays(date: NaiveDate, workdays: i64, calendar: &HolidayCalendar) -> Option<NaiveDate> {
+    let step = Duration::days(workdays.signum());
+    let mut current = date;
+    for _ in 0..workdays.unsigned_abs() {
+        current = current.checked_add_signed(step)?;
+        while !calendar.is_workday(current) {
+            current = current.checked_add_signed(step)?;
+        }
+    }
+    Some(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(s: &str, today: NaiveDate) -> NaiveDate {
+        let calendar = HolidayCalendar::builtin("ru").unwrap();
+        s.parse::<DateExpr>().unwrap().resolve(today, &calendar).unwrap()
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_relative_dates() {
+        // Wednesday
+        let today = date(2025, 12, 10);
+        assert_eq!(resolve("2025-01-02", today), date(2025, 1, 2));
+        assert_eq!(resolve("Today", today), today);
+        assert_eq!(resolve("tomorrow", today), date(2025, 12, 11));
+        assert_eq!(resolve("+3d", today), date(2025, 12, 13));
+        assert_eq!(resolve("-1w", today), date(2025, 12, 3));
+        assert_eq!(resolve("+1m", today), date(2026, 1, 10));
+        assert_eq!(resolve("eom", today), date(2025, 12, 31));
+        assert_eq!(resolve("eom", date(2024, 2, 10)), date(2024, 2, 29));
+        assert_eq!(resolve("2025-W14", today), date(2025, 3, 31));
+        assert_eq!(resolve("2026-w01", today), date(2025, 12, 29));
+    }
+
+    #[test]
+    fn test_weekdays() {
+        // Wednesday
+        let today = date(2025, 12, 10);
+        assert_eq!(resolve("wed", today), today);
+        assert_eq!(resolve("next wed", today), date(2025, 12, 17));
+        assert_eq!(resolve("fri", today), date(2025, 12, 12));
+        assert_eq!(resolve("next Friday", today), date(2025, 12, 12));
+        assert_eq!(resolve("пн", today), date(2025, 12, 15));
+        assert_eq!(resolve("next mon", today), date(2025, 12, 15));
+    }
+
+    #[test]
+    fn test_workdays_skip_holidays() {
+        assert_eq!(resolve("+2wd", date(2025, 12, 30)), date(2026, 1, 13));
+        assert_eq!(resolve("-1wd", date(2025, 12, 15)), date(2025, 12, 12));
+        assert_eq!(resolve("+0wd", date(2025, 12, 13)), date(2025, 12, 13));
+    }
+
+    #[test]
+    fn test_invalid() {
+        for s in ["", "2025-13-01", "+3x", "next", "someday", "next someday", "last fri", "2025-W54"] {
+            assert!(s.parse::<DateExpr>().is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn test_offsets_out_of_range() {
+        for s in ["+99999999999999999d", "+999999999d", "-9999999999999999w", "+3000000000m", "+300000000y", "+999999999wd"] {
+            let err = s.parse::<DateExpr>().unwrap_err();
+            assert!(err.contains("out of range"), "{s}: {err}");
+        }
+        assert_eq!(resolve("+100000wd", date(2025, 1, 1)).year(), 2408);
+
+        let calendar = HolidayCalendar::builtin("ru").unwrap();
+        let near_end = NaiveDate::MAX - Duration::days(3);
+        assert!("+30d".parse::<DateExpr>().unwrap().resolve(near_end, &calendar).is_err());
+        assert!("+30wd".parse::<DateExpr>().unwrap().resolve(near_end, &calendar).is_err());
+        assert!("next mon".parse::<DateExpr>().unwrap().resolve(NaiveDate::MAX, &calendar).is_err());
+    }
+}
@@ -12,6 +12,12 @@ pub enum AppError {
     Serialization(String),
     Regex(String),
     Walk(String),
+    Refile(String),
+    Lint(String),
+    Fmt(String),
+    Query(String),
+    Template(String),
+    Holidays(String),
 }
 
 impl fmt::Display for AppError {
@@ -26,6 +32,12 @@ impl fmt::Display for AppError {
             AppError::Serialization(msg) => write!(f, "Serialization error: {msg}"),
             AppError::Regex(msg) => write!(f, "Regex error: {msg}"),
             AppError::Walk(msg) => write!(f, "Walk error: {msg}"),
+            AppError::Refile(msg) => write!(f, "Refile error: {msg}"),
+            AppError::Lint(msg) => write!(f, "Lint failed: {msg}"),
+            AppError::Fmt(msg) => write!(f, "Format check failed: {msg}"),
+            AppError::Query(msg) => write!(f, "Invalid filter expression: {msg}"),
+            AppError::Template(msg) => write!(f, "Template error: {msg}"),
+            AppError::Holidays(msg) => write!(f, "Holiday calendar error: {msg}"),
         }
     }
 }
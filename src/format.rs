@@ -5,6 +5,11 @@ pub enum OutputFormat {
     Json,
     Markdown,
     Html,
+    Ics,
+    Csv,
+    Tsv,
+    Org,
+    Term,
 }
 
 impl FromStr for OutputFormat {
@@ -15,7 +20,12 @@ impl FromStr for OutputFormat {
             "json" => Ok(OutputFormat::Json),
             "md" | "markdown" => Ok(OutputFormat::Markdown),
             "html" => Ok(OutputFormat::Html),
-            _ => Err(format!("Invalid format: {s}. Valid formats: json, md, html")),
+            "ics" | "ical" => Ok(OutputFormat::Ics),
+            "csv" => Ok(OutputFormat::Csv),
+            "tsv" => Ok(OutputFormat::Tsv),
+            "org" => Ok(OutputFormat::Org),
+            "term" => Ok(OutputFormat::Term),
+            _ => Err(format!("Invalid format: {s}. Valid formats: json, md, html, ics, csv, tsv, org, term")),
         }
     }
 }
@@ -1,34 +1,151 @@
 use chrono::{Datelike, NaiveDate, Weekday};
-use std::collections::HashSet;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::error::AppError;
+use crate::ics;
+use crate::types::Task;
 
 include!(concat!(env!("OUT_DIR"), "/holidays_data.rs"));
 
-#[derive(Debug)]
+/// Calendar used when `--holiday-calendar` is not given
+pub const DEFAULT_CALENDAR: &str = "ru";
+
+/// Task or file property selecting a calendar, e.g. `:HOLIDAY_CALENDAR: de`
+pub const CALENDAR_PROPERTY: &str = "HOLIDAY_CALENDAR";
+
+/// Personal day off: `VACATION: <2025-07-01>` or `VACATION: <2025-07-01>--<2025-07-14>`
+static VACATION_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"VACATION:\s*<(\d{4}-\d{2}-\d{2})[^>]*>(?:--<(\d{4}-\d{2}-\d{2})[^>]*>)?")
+        .expect("Invalid VACATION_RE regex")
+});
+
+/// One year of a holiday file, laid out like `holidays_ru.json`
+#[derive(Deserialize)]
+struct YearData {
+    #[serde(default)]
+    holidays: Vec<String>,
+    #[serde(default)]
+    workdays: Vec<String>,
+    /// Holiday names by date, e.g. `"2025-06-12": "День России"`
+    #[serde(default)]
+    names: BTreeMap<String, String>,
+}
+
+/// `--holiday-file [CODE=]PATH`; without a code the file applies to the
+/// default calendar
+#[derive(Debug, Clone)]
+pub struct HolidayFile {
+    pub code: Option<String>,
+    pub path: PathBuf,
+}
+
+impl FromStr for HolidayFile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (code, path) = match s.split_once('=') {
+            Some((code, path)) if !code.is_empty() && code.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') => {
+                (Some(code.to_lowercase()), path)
+            }
+            _ => (None, s),
+        };
+        if path.is_empty() {
+            return Err(format!("Missing path in holiday file '{s}'"));
+        }
+        Ok(Self { code, path: PathBuf::from(path) })
+    }
+}
+
+/// Entry of `--holidays --holiday-details`
+#[derive(Debug, Serialize)]
+pub struct CalendarDay {
+    pub date: String,
+    /// `holiday` or `workday` (a weekend day worked by transfer)
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Default)]
 pub struct HolidayCalendar {
     holidays: HashSet<NaiveDate>,
     workdays: HashSet<NaiveDate>,
+    names: HashMap<NaiveDate, String>,
+    /// Personal days off, never workdays even if transferred
+    days_off: HashSet<NaiveDate>,
 }
 
 impl HolidayCalendar {
-    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
-        let mut holidays = HashSet::new();
-        for &(year, month, day) in HOLIDAYS {
-            if let Some(date) = NaiveDate::from_ymd_opt(year, month, day) {
-                holidays.insert(date);
+    /// Built-in calendar of a country code (`ru`, `de`, `rs`)
+    pub fn builtin(code: &str) -> Option<Self> {
+        let (_, holidays, workdays, names) = CALENDARS.iter().find(|(c, _, _, _)| c.eq_ignore_ascii_case(code))?;
+        Some(Self {
+            holidays: to_dates(holidays),
+            workdays: to_dates(workdays),
+            names: names.iter().filter_map(|(date, name)| Some((to_date(*date)?, name.to_string()))).collect(),
+            days_off: HashSet::new(),
+        })
+    }
+
+    /// Read a JSON file in the `holidays_ru.json` layout, or an `.ics` file
+    /// whose events are holidays named by their SUMMARY
+    pub fn load(path: &Path) -> Result<Self, AppError> {
+        let error = |msg: String| AppError::Holidays(format!("{}: {msg}", path.display()));
+        let content = fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+
+        if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("ics")) {
+            let dates = ics::holiday_dates(&content);
+            if dates.is_empty() {
+                return Err(error("no events with a start date".to_string()));
+            }
+            let mut calendar = Self::default();
+            for (date, name) in dates {
+                calendar.holidays.insert(date);
+                if let Some(name) = name {
+                    calendar.names.insert(date, name);
+                }
             }
+            return Ok(calendar);
         }
-        
-        let mut workdays = HashSet::new();
-        for &(year, month, day) in WORKDAYS {
-            if let Some(date) = NaiveDate::from_ymd_opt(year, month, day) {
-                workdays.insert(date);
+
+        let years: BTreeMap<String, YearData> = serde_json::from_str(&content).map_err(|e| error(e.to_string()))?;
+        let parse_date = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").map_err(|_| error(format!("invalid date '{d}'")));
+        let parse = |dates: &[String]| -> Result<Vec<NaiveDate>, AppError> { dates.iter().map(|d| parse_date(d)).collect() };
+        let mut calendar = Self::default();
+        for data in years.values() {
+            calendar.holidays.extend(parse(&data.holidays)?);
+            calendar.workdays.extend(parse(&data.workdays)?);
+            for (date, name) in &data.names {
+                calendar.names.insert(parse_date(date)?, name.clone());
             }
         }
-        
-        Ok(Self { holidays, workdays })
+        Ok(calendar)
     }
-    
+
+    /// Add the dates of `other`; where the two disagree, `other` wins
+    fn merge(&mut self, other: HolidayCalendar) {
+        for date in other.holidays {
+            self.workdays.remove(&date);
+            self.holidays.insert(date);
+        }
+        for date in other.workdays {
+            self.holidays.remove(&date);
+            self.workdays.insert(date);
+        }
+        self.names.extend(other.names);
+    }
+
     pub fn is_workday(&self, date: NaiveDate) -> bool {
+        if self.days_off.contains(&date) {
+            return false;
+        }
         if self.workdays.contains(&date) {
             return true;
         }
@@ -46,13 +163,161 @@ impl HolidayCalendar {
         current
     }
 
+    /// Workdays after `from` up to and including `to`; negative when `to` is earlier
+    pub fn workdays_between(&self, from: NaiveDate, to: NaiveDate) -> i64 {
+        let (start, end, sign) = if to >= from { (from, to, 1) } else { (to, from, -1) };
+        let count = start
+            .iter_days()
+            .skip(1)
+            .take_while(|date| *date <= end)
+            .filter(|date| self.is_workday(*date))
+            .count();
+        sign * count as i64
+    }
+
+    /// Name of the holiday on `date`, if it is a holiday and has one
+    pub fn holiday_name(&self, date: NaiveDate) -> Option<&str> {
+        if !self.holidays.contains(&date) {
+            return None;
+        }
+        self.names.get(&date).map(String::as_str)
+    }
+
     pub fn get_holidays_for_year(&self, year: i32) -> Vec<NaiveDate> {
-        let mut result: Vec<_> = self.holidays.iter()
-            .filter(|d| d.year() == year)
-            .copied()
+        sorted_in_year(&self.holidays, year)
+    }
+
+    /// Holidays with their names and transferred working days of `year`, by date
+    pub fn get_days_for_year(&self, year: i32) -> Vec<CalendarDay> {
+        let holidays = sorted_in_year(&self.holidays, year).into_iter().map(|date| CalendarDay {
+            date: date.format("%Y-%m-%d").to_string(),
+            kind: "holiday",
+            name: self.holiday_name(date).map(str::to_string),
+        });
+        let workdays = sorted_in_year(&self.workdays, year).into_iter().map(|date| CalendarDay {
+            date: date.format("%Y-%m-%d").to_string(),
+            kind: "workday",
+            name: None,
+        });
+        let mut days: Vec<CalendarDay> = holidays.chain(workdays).collect();
+        days.sort_by(|a, b| a.date.cmp(&b.date));
+        days
+    }
+}
+
+fn sorted_in_year(dates: &HashSet<NaiveDate>, year: i32) -> Vec<NaiveDate> {
+    let mut result: Vec<_> = dates.iter()
+        .filter(|d| d.year() == year)
+        .copied()
+        .collect();
+    result.sort();
+    result
+}
+
+fn to_dates(dates: Dates) -> HashSet<NaiveDate> {
+    dates.iter().filter_map(|date| to_date(*date)).collect()
+}
+
+fn to_date((year, month, day): (i32, u32, u32)) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(year, month, day)
+}
+
+/// Read `VACATION:` entries of a days-off file, e.g. a markdown note
+pub fn load_days_off(path: &Path) -> Result<Vec<NaiveDate>, AppError> {
+    let content = fs::read_to_string(path).map_err(|e| AppError::Holidays(format!("{}: {e}", path.display())))?;
+    let dates = parse_days_off(&content).map_err(|msg| AppError::Holidays(format!("{}: {msg}", path.display())))?;
+    if dates.is_empty() {
+        return Err(AppError::Holidays(format!("{}: no VACATION entries found", path.display())));
+    }
+    Ok(dates)
+}
+
+fn parse_days_off(content: &str) -> Result<Vec<NaiveDate>, String> {
+    let parse = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|_| format!("invalid date '{s}'"));
+    let mut dates = Vec::new();
+    for caps in VACATION_RE.captures_iter(content) {
+        let start = parse(&caps[1])?;
+        let end = caps.get(2).map(|m| parse(m.as_str())).transpose()?.unwrap_or(start);
+        if end < start {
+            return Err(format!("vacation ends before it starts: {}", &caps[0]));
+        }
+        dates.extend(start.iter_days().take_while(|date| *date <= end));
+    }
+    Ok(dates)
+}
+
+/// Give tasks without their own `HOLIDAY_CALENDAR` the one set for the whole file
+pub fn inherit_file_calendar(tasks: &mut [Task], file_properties: &BTreeMap<String, String>) {
+    let Some(code) = file_properties.get(CALENDAR_PROPERTY) else {
+        return;
+    };
+    for task in tasks {
+        task.properties
+            .get_or_insert_with(BTreeMap::new)
+            .entry(CALENDAR_PROPERTY.to_string())
+            .or_insert_with(|| code.clone());
+    }
+}
+
+/// Calendars available to tasks and the one used when a task names none
+#[derive(Debug)]
+pub struct Calendars {
+    calendars: BTreeMap<String, HolidayCalendar>,
+    default: String,
+}
+
+impl Calendars {
+    /// Built-in calendars extended by user `files`, with `default` as the fallback
+    ///
+    /// A file is merged into the calendar of its code, or replaces the
+    /// built-in one when `replace` is set; unknown codes add new calendars.
+    pub fn load(default: &str, files: &[HolidayFile], replace: bool) -> Result<Self, AppError> {
+        let mut calendars: BTreeMap<String, HolidayCalendar> = CALENDARS
+            .iter()
+            .filter_map(|(code, _, _, _)| HolidayCalendar::builtin(code).map(|c| (code.to_string(), c)))
             .collect();
-        result.sort();
-        result
+        let default = default.to_lowercase();
+        let mut replaced = HashSet::new();
+        for file in files {
+            let code = file.code.clone().unwrap_or_else(|| default.clone());
+            let calendar = HolidayCalendar::load(&file.path)?;
+            if replace && replaced.insert(code.clone()) {
+                calendars.insert(code, calendar);
+            } else if let Some(existing) = calendars.get_mut(&code) {
+                existing.merge(calendar);
+            } else {
+                calendars.insert(code, calendar);
+            }
+        }
+        if !calendars.contains_key(&default) {
+            let known: Vec<&str> = calendars.keys().map(String::as_str).collect();
+            return Err(AppError::Holidays(format!(
+                "unknown calendar '{default}', available: {}",
+                known.join(", ")
+            )));
+        }
+        Ok(Self { calendars, default })
+    }
+
+    /// Mark personal days off in every calendar
+    pub fn add_days_off(&mut self, dates: &[NaiveDate]) {
+        for calendar in self.calendars.values_mut() {
+            calendar.days_off.extend(dates.iter().copied());
+        }
+    }
+
+    pub fn default_calendar(&self) -> &HolidayCalendar {
+        &self.calendars[&self.default]
+    }
+
+    /// Calendar named by the task's `HOLIDAY_CALENDAR` property; unknown or
+    /// missing names fall back to the default
+    pub fn for_task(&self, task: &Task) -> &HolidayCalendar {
+        task.properties
+            .as_ref()
+            .and_then(|props| props.get(CALENDAR_PROPERTY))
+            .and_then(|code| self.calendars.get(&code.trim().to_lowercase()))
+            .unwrap_or_else(|| self.default_calendar())
     }
 }
 
@@ -62,13 +327,132 @@ mod tests {
 
     #[test]
     fn test_load_calendar() {
-        let calendar = HolidayCalendar::load().unwrap();
-        assert!(calendar.holidays.len() > 0);
+        let calendar = HolidayCalendar::builtin("ru").unwrap();
+        assert!(!calendar.holidays.is_empty());
+    }
+
+    #[test]
+    fn test_country_calendars() {
+        let de = HolidayCalendar::builtin("de").unwrap();
+        let rs = HolidayCalendar::builtin("rs").unwrap();
+        let ru = HolidayCalendar::builtin("ru").unwrap();
+        let unity_day = NaiveDate::from_ymd_opt(2025, 10, 3).unwrap();
+        assert!(!de.is_workday(unity_day));
+        assert!(ru.is_workday(unity_day));
+        // Statehood Day on Sunday moves to Tuesday in Serbia
+        let feb_17 = NaiveDate::from_ymd_opt(2026, 2, 17).unwrap();
+        assert!(!rs.is_workday(feb_17));
+        assert!(de.is_workday(feb_17));
+        assert!(HolidayCalendar::builtin("xx").is_none());
+    }
+
+    #[test]
+    fn test_calendar_for_task() {
+        let calendars = Calendars::load("ru", &[], false).unwrap();
+        let mut task: Task = serde_json::from_value(serde_json::json!({
+            "file": "a.md", "line": 1, "heading": "Task", "content": ""
+        }))
+        .unwrap();
+        let unity_day = NaiveDate::from_ymd_opt(2025, 10, 3).unwrap();
+        assert!(calendars.for_task(&task).is_workday(unity_day));
+        task.properties = Some(BTreeMap::from([(CALENDAR_PROPERTY.to_string(), "DE".to_string())]));
+        assert!(!calendars.for_task(&task).is_workday(unity_day));
+        task.properties = Some(BTreeMap::from([(CALENDAR_PROPERTY.to_string(), "xx".to_string())]));
+        assert!(calendars.for_task(&task).is_workday(unity_day));
+        assert!(Calendars::load("xx", &[], false).is_err());
+    }
+
+    #[test]
+    fn test_load_user_files() {
+        let dir = std::env::temp_dir().join(format!("holidays-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let json = dir.join("extra.json");
+        fs::write(
+            &json,
+            r#"{"2025": {"holidays": ["2025-12-29"], "workdays": ["2025-01-08"], "names": {"2025-12-29": "Company day"}}}"#,
+        )
+        .unwrap();
+        let ics = dir.join("fr.ics");
+        fs::write(&ics, "BEGIN:VEVENT\nSUMMARY:Fête nationale\nDTSTART;VALUE=DATE:20250714\nEND:VEVENT\n").unwrap();
+        let bad = dir.join("bad.json");
+        fs::write(&bad, r#"{"2025": {"holidays": ["2025-13-01"]}}"#).unwrap();
+        let file = |s: &str| s.parse::<HolidayFile>().unwrap();
+        let date = |m, d| NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+
+        let merged = Calendars::load("ru", &[file(json.to_str().unwrap())], false).unwrap();
+        let ru = merged.default_calendar();
+        assert!(!ru.is_workday(date(12, 29)));
+        assert!(ru.is_workday(date(1, 8)));
+        assert!(!ru.is_workday(date(1, 7)));
+        assert_eq!(ru.holiday_name(date(12, 29)), Some("Company day"));
+        assert_eq!(ru.holiday_name(date(1, 8)), None);
+        let days = ru.get_days_for_year(2025);
+        let workday = days.iter().find(|day| day.date == "2025-01-08").unwrap();
+        assert_eq!((workday.kind, workday.name.as_deref()), ("workday", None));
+
+        let replaced = Calendars::load("ru", &[file(json.to_str().unwrap())], true).unwrap();
+        assert!(replaced.default_calendar().is_workday(date(1, 7)));
+
+        let with_fr = Calendars::load("fr", &[file(&format!("fr={}", ics.display()))], false).unwrap();
+        assert!(!with_fr.default_calendar().is_workday(date(7, 14)));
+        assert_eq!(with_fr.default_calendar().holiday_name(date(7, 14)), Some("Fête nationale"));
+
+        let err = Calendars::load("ru", &[file(bad.to_str().unwrap())], false).unwrap_err().to_string();
+        assert!(err.contains("bad.json") && err.contains("2025-13-01"), "{err}");
+        assert!(Calendars::load("ru", &[file("/nonexistent/holidays.json")], false).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_builtin_holiday_names() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let ru = HolidayCalendar::builtin("ru").unwrap();
+        assert_eq!(ru.holiday_name(date(2025, 6, 12)), Some("День России"));
+        assert_eq!(ru.holiday_name(date(2026, 1, 1)), Some("Новый год"));
+        assert_eq!(ru.holiday_name(date(2025, 6, 14)), None);
+        let de = HolidayCalendar::builtin("de").unwrap();
+        assert_eq!(de.holiday_name(date(2025, 10, 3)), Some("Tag der Deutschen Einheit"));
+
+        // Every built-in holiday has a name
+        for (code, holidays, _, _) in CALENDARS {
+            let calendar = HolidayCalendar::builtin(code).unwrap();
+            for date in to_dates(holidays) {
+                assert!(calendar.holiday_name(date).is_some(), "{code}: {date} has no name");
+            }
+        }
+    }
+
+    #[test]
+    fn test_days_off() {
+        let content = "## Отпуск\n`VACATION: <2025-07-01 Tue>--<2025-07-14 Mon>`\n`VACATION: <2025-08-15>`\n";
+        let dates = parse_days_off(content).unwrap();
+        assert_eq!(dates.len(), 15);
+        assert!(parse_days_off("VACATION: <2025-07-14>--<2025-07-01>").is_err());
+
+        let mut calendars = Calendars::load("ru", &[], false).unwrap();
+        calendars.add_days_off(&dates);
+        let ru = calendars.default_calendar();
+        let date = |m, d| NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+        assert!(!ru.is_workday(date(7, 1)));
+        assert_eq!(ru.next_workday(date(6, 30)), date(7, 15));
+        assert!(ru.get_holidays_for_year(2025).iter().all(|d| d.month() != 7));
+    }
+
+    #[test]
+    fn test_workdays_between() {
+        let calendar = HolidayCalendar::builtin("ru").unwrap();
+        let date = |m, d| NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+        // Friday to next Wednesday: Mon, Tue, Wed
+        assert_eq!(calendar.workdays_between(date(12, 5), date(12, 10)), 3);
+        assert_eq!(calendar.workdays_between(date(12, 10), date(12, 5)), -3);
+        assert_eq!(calendar.workdays_between(date(12, 5), date(12, 5)), 0);
+        // New Year holidays
+        assert_eq!(calendar.workdays_between(date(12, 30), date(1, 9).with_year(2026).unwrap()), 0);
     }
 
     #[test]
     fn test_regular_weekend() {
-        let calendar = HolidayCalendar::load().unwrap();
+        let calendar = HolidayCalendar::builtin("ru").unwrap();
         let saturday = NaiveDate::from_ymd_opt(2025, 12, 6).unwrap();
         let sunday = NaiveDate::from_ymd_opt(2025, 12, 7).unwrap();
         assert!(!calendar.is_workday(saturday));
@@ -77,14 +461,14 @@ mod tests {
 
     #[test]
     fn test_regular_weekday() {
-        let calendar = HolidayCalendar::load().unwrap();
+        let calendar = HolidayCalendar::builtin("ru").unwrap();
         let friday = NaiveDate::from_ymd_opt(2025, 12, 5).unwrap();
         assert!(calendar.is_workday(friday));
     }
 
     #[test]
     fn test_new_year_holidays_2025() {
-        let calendar = HolidayCalendar::load().unwrap();
+        let calendar = HolidayCalendar::builtin("ru").unwrap();
         for day in 1..=8 {
             let date = NaiveDate::from_ymd_opt(2025, 1, day).unwrap();
             assert!(!calendar.is_workday(date), "2025-01-{:02} should be holiday", day);
@@ -93,7 +477,7 @@ mod tests {
 
     #[test]
     fn test_new_year_holidays_2026() {
-        let calendar = HolidayCalendar::load().unwrap();
+        let calendar = HolidayCalendar::builtin("ru").unwrap();
         for day in 1..=9 {
             let date = NaiveDate::from_ymd_opt(2026, 1, day).unwrap();
             assert!(!calendar.is_workday(date), "2026-01-{:02} should be holiday", day);
@@ -104,21 +488,21 @@ mod tests {
 
     #[test]
     fn test_march_8_transfer_2026() {
-        let calendar = HolidayCalendar::load().unwrap();
+        let calendar = HolidayCalendar::builtin("ru").unwrap();
         let march_9 = NaiveDate::from_ymd_opt(2026, 3, 9).unwrap();
         assert!(!calendar.is_workday(march_9), "2026-03-09 should be holiday (transfer)");
     }
 
     #[test]
     fn test_may_9_transfer_2026() {
-        let calendar = HolidayCalendar::load().unwrap();
+        let calendar = HolidayCalendar::builtin("ru").unwrap();
         let may_11 = NaiveDate::from_ymd_opt(2026, 5, 11).unwrap();
         assert!(!calendar.is_workday(may_11), "2026-05-11 should be holiday (transfer)");
     }
 
     #[test]
     fn test_next_workday_skip_weekend() {
-        let calendar = HolidayCalendar::load().unwrap();
+        let calendar = HolidayCalendar::builtin("ru").unwrap();
         let friday = NaiveDate::from_ymd_opt(2025, 12, 5).unwrap();
         let next = calendar.next_workday(friday);
         let monday = NaiveDate::from_ymd_opt(2025, 12, 8).unwrap();
@@ -127,7 +511,7 @@ mod tests {
 
     #[test]
     fn test_next_workday_skip_holidays() {
-        let calendar = HolidayCalendar::load().unwrap();
+        let calendar = HolidayCalendar::builtin("ru").unwrap();
         let jan_4 = NaiveDate::from_ymd_opt(2026, 1, 4).unwrap();
         let next = calendar.next_workday(jan_4);
         let jan_12 = NaiveDate::from_ymd_opt(2026, 1, 12).unwrap();
@@ -0,0 +1,5 @@
+mod export;
+mod import;
+
+pub use export::render_ics;
+pub use import::{holiday_dates, parse_ics};
@@ -0,0 +1,443 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::holidays::{Calendars, HolidayCalendar};
+use crate::timestamp::{parse_timestamp_parts, Repeater, RepeaterUnit, TimestampParts};
+use crate::types::{Priority, Task, TaskType};
+
+/// Days ahead of the current date for which workday repeaters are expanded
+const WORKDAY_EXPANSION_DAYS: i64 = 366;
+
+/// Maximum length of a content line in octets (RFC 5545, section 3.1)
+const MAX_LINE_OCTETS: usize = 75;
+
+static SPAN_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"<([^<>]+)>").expect("Invalid SPAN_RE regex"));
+
+/// Date or local date-time of a calendar property
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum When {
+    Date(NaiveDate),
+    DateTime(NaiveDate, NaiveTime),
+}
+
+/// Render tasks with timestamps as an iCalendar document
+///
+/// DEADLINEs become VTODOs with DUE; SCHEDULED and plain timestamps become
+/// VEVENTs. Local times are interpreted in `tz` and written in UTC, except
+/// for repeating entries: their times stay floating, so recurrences keep the
+/// wall-clock time across DST changes. CLOSED timestamps and tasks without
+/// timestamps are skipped. Workday repeaters are expanded with the calendar
+/// of each task.
+pub fn render_ics(tasks: &[Task], tz: Tz, today: NaiveDate, calendars: &Calendars) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//markdown-org-extract//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+    let mut uids: HashMap<String, usize> = HashMap::new();
+
+    for task in tasks {
+        let Some((start, end)) = task.timestamp.as_deref().and_then(parse_spans) else {
+            continue;
+        };
+        let is_deadline = match task.timestamp_type.as_deref() {
+            Some("CLOSED") => continue,
+            Some("DEADLINE") => true,
+            _ => false,
+        };
+
+        // Imported entries keep their UID; tasks with the same file and
+        // heading get a numeric suffix
+        let uid = match task.uid {
+            Some(ref uid) => uid.clone(),
+            None => {
+                let hash = task_hash(task);
+                let count = uids.entry(hash.clone()).or_insert(0);
+                *count += 1;
+                if *count == 1 {
+                    format!("{hash}@markdown-org-extract")
+                } else {
+                    format!("{hash}-{count}@markdown-org-extract")
+                }
+            }
+        };
+
+        let component = if is_deadline { "VTODO" } else { "VEVENT" };
+        lines.push(format!("BEGIN:{component}"));
+        lines.push(format!("UID:{uid}"));
+        lines.push(format!("DTSTAMP:{}", today.format("%Y%m%dT000000Z")));
+        lines.push(format!("SUMMARY:{}", escape_text(&task.heading)));
+
+        // An hourly rule needs a DATE-TIME start, so date-only `+2h` timestamps don't repeat
+        let repeater = start.repeater.as_ref().filter(|r| r.unit != RepeaterUnit::Hour || start.time.is_some());
+        let zone = if repeater.is_some() { None } else { Some(tz) };
+        let start_when = when(&start);
+        if is_deadline {
+            lines.push(format_when("DUE", start_when, zone));
+            // Recurrences are built from DTSTART, which must come before DUE,
+            // so a repeating deadline starts the day before it is due
+            if repeater.is_some() {
+                lines.push(format_when("DTSTART", start_when.day_before(), zone));
+            }
+            let status = match task.task_type {
+                Some(TaskType::Done) => "COMPLETED",
+                _ => "NEEDS-ACTION",
+            };
+            lines.push(format!("STATUS:{status}"));
+        } else {
+            lines.push(format_when("DTSTART", start_when, zone));
+            if let Some(end_when) = event_end(&start, end.as_ref()) {
+                lines.push(format_when("DTEND", end_when, zone));
+            }
+        }
+
+        if let Some(repeater) = repeater {
+            if repeater.unit == RepeaterUnit::Workday {
+                let until = today.max(start.date) + Duration::days(WORKDAY_EXPANSION_DAYS);
+                let mut dates = workday_occurrences(calendars.for_task(task), start.date, repeater.value, until);
+                if is_deadline {
+                    dates.iter_mut().for_each(|date| *date -= Duration::days(1));
+                }
+                if !dates.is_empty() {
+                    lines.push(format_rdate(&dates, start.time));
+                }
+            } else {
+                lines.push(format!("RRULE:{}", rrule(repeater)));
+            }
+        }
+
+        if let Some(ref priority) = task.priority {
+            lines.push(format!("PRIORITY:{}", ics_priority(priority)));
+        }
+        if let Some(ref tags) = task.tags {
+            if !tags.is_empty() {
+                let escaped: Vec<String> = tags.iter().map(|t| escape_text(t)).collect();
+                lines.push(format!("CATEGORIES:{}", escaped.join(",")));
+            }
+        }
+        if !task.content.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", escape_text(&task.content)));
+        }
+        lines.push(format!("END:{component}"));
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut output = String::new();
+    for line in &lines {
+        output.push_str(&fold_line(line));
+        output.push_str("\r\n");
+    }
+    output
+}
+
+/// Parse the first timestamp and, for `<A>--<B>` ranges, the second one
+fn parse_spans(timestamp: &str) -> Option<(TimestampParts, Option<TimestampParts>)> {
+    let mut spans = SPAN_RE.captures_iter(timestamp);
+    let start = parse_timestamp_parts(spans.next()?.get(1)?.as_str(), &[]).ok()?;
+    let end = if timestamp.contains(">--<") {
+        spans
+            .next()
+            .and_then(|caps| parse_timestamp_parts(&caps[1], &[]).ok())
+    } else {
+        None
+    };
+    Some((start, end))
+}
+
+impl When {
+    fn day_before(self) -> Self {
+        match self {
+            When::Date(date) => When::Date(date - Duration::days(1)),
+            When::DateTime(date, time) => When::DateTime(date - Duration::days(1), time),
+        }
+    }
+}
+
+fn when(parts: &TimestampParts) -> When {
+    match parts.time {
+        Some(time) => When::DateTime(parts.date, time),
+        None => When::Date(parts.date),
+    }
+}
+
+/// DTEND of an event: range end, time range end, or the next day for all-day events
+fn event_end(start: &TimestampParts, end: Option<&TimestampParts>) -> Option<When> {
+    match (start.time, end) {
+        (Some(_), Some(end)) => end
+            .time
+            .or(end.end_time)
+            .or(start.end_time)
+            .or(start.time)
+            .map(|time| When::DateTime(end.date, time)),
+        (None, Some(end)) => Some(When::Date(end.date + Duration::days(1))),
+        (Some(_), None) => start.end_time.map(|time| When::DateTime(start.date, time)),
+        (None, None) => Some(When::Date(start.date + Duration::days(1))),
+    }
+}
+
+/// Date-times in UTC when `zone` is given, floating local times otherwise
+fn format_when(name: &str, when: When, zone: Option<Tz>) -> String {
+    match (when, zone) {
+        (When::Date(date), _) => format!("{name};VALUE=DATE:{}", date.format("%Y%m%d")),
+        (When::DateTime(date, time), Some(tz)) => format!("{name}:{}", utc_stamp(date, time, tz)),
+        (When::DateTime(date, time), None) => format!("{name}:{}", date.and_time(time).format("%Y%m%dT%H%M%S")),
+    }
+}
+
+/// Workday occurrences of a repeating entry, so times are floating
+fn format_rdate(dates: &[NaiveDate], time: Option<NaiveTime>) -> String {
+    match time {
+        Some(time) => {
+            let values: Vec<String> =
+                dates.iter().map(|d| d.and_time(time).format("%Y%m%dT%H%M%S").to_string()).collect();
+            format!("RDATE:{}", values.join(","))
+        }
+        None => {
+            let values: Vec<String> = dates.iter().map(|d| d.format("%Y%m%d").to_string()).collect();
+            format!("RDATE;VALUE=DATE:{}", values.join(","))
+        }
+    }
+}
+
+/// Local date and time in `tz` as a UTC `YYYYMMDDTHHMMSSZ` value
+fn utc_stamp(date: NaiveDate, time: NaiveTime, tz: Tz) -> String {
+    let local = date.and_time(time);
+    // A local time inside a DST gap does not exist; use the first hour after it
+    let utc = tz
+        .from_local_datetime(&local)
+        .earliest()
+        .or_else(|| tz.from_local_datetime(&(local + Duration::hours(1))).earliest())
+        .map(|dt| dt.with_timezone(&Utc).naive_utc())
+        .unwrap_or(local);
+    utc.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Occurrences after `start` every `step` workdays, up to and including `until`
+fn workday_occurrences(calendar: &HolidayCalendar, start: NaiveDate, step: u32, until: NaiveDate) -> Vec<NaiveDate> {
+    let mut dates = Vec::new();
+    let mut current = start;
+    loop {
+        for _ in 0..step.max(1) {
+            current = calendar.next_workday(current);
+        }
+        if current > until {
+            break;
+        }
+        dates.push(current);
+    }
+    dates
+}
+
+fn rrule(repeater: &Repeater) -> String {
+    let freq = match repeater.unit {
+        RepeaterUnit::Hour => "HOURLY",
+        RepeaterUnit::Day | RepeaterUnit::Workday => "DAILY",
+        RepeaterUnit::Week => "WEEKLY",
+        RepeaterUnit::Month => "MONTHLY",
+        RepeaterUnit::Year => "YEARLY",
+    };
+    if repeater.value > 1 {
+        format!("FREQ={freq};INTERVAL={}", repeater.value)
+    } else {
+        format!("FREQ={freq}")
+    }
+}
+
+/// Map org priorities onto the iCalendar 1-9 scale (1 is highest)
+fn ics_priority(priority: &Priority) -> u32 {
+    match priority {
+        Priority::A => 1,
+        Priority::B => 5,
+        Priority::C => 9,
+        Priority::Other(_) => 9,
+    }
+}
+
+/// Stable hash of file and heading (64-bit FNV-1a) used for UIDs
+fn task_hash(task: &Task) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in task.file.bytes().chain([0u8]).chain(task.heading.bytes()) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    format!("{hash:016x}")
+}
+
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Fold a content line into chunks of at most 75 octets, never splitting a character
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / MAX_LINE_OCTETS * 3);
+    let mut width = 0;
+    for c in line.chars() {
+        let len = c.len_utf8();
+        if width + len > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += len;
+    }
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(heading: &str, task_type: Option<TaskType>, timestamp: &str) -> Task {
+        let ts_type = ["SCHEDULED", "DEADLINE", "CLOSED"]
+            .into_iter()
+            .find(|t| timestamp.starts_with(t))
+            .unwrap_or("PLAIN");
+        Task {
+            file: "notes/work.md".to_string(),
+            line: 3,
+            heading: heading.to_string(),
+            content: String::new(),
+            task_type,
+            priority: None,
+            created: None,
+            timestamp: Some(timestamp.to_string()),
+            timestamp_type: Some(ts_type.to_string()),
+            timestamp_date: None,
+            timestamp_time: None,
+            timestamp_end_time: None,
+            clocks: None,
+            total_clock_time: None,
+            tags: None,
+            properties: None,
+            urgency: None,
+            uid: None,
+            parsed_timestamp: None,
+        }
+    }
+
+    fn render(tasks: &[Task]) -> String {
+        render_ics(
+            tasks,
+            "Europe/Moscow".parse().unwrap(),
+            NaiveDate::from_ymd_opt(2025, 1, 10).unwrap(),
+            &Calendars::load("ru", &[], false).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_timed_event_in_utc() {
+        let ics = render(&[task("Sync", None, "SCHEDULED: <2025-01-10 Fri 10:00-11:30>")]);
+        assert!(ics.contains("BEGIN:VEVENT\r\n"));
+        assert!(ics.contains("DTSTART:20250110T070000Z\r\n"));
+        assert!(ics.contains("DTEND:20250110T083000Z\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    }
+
+    #[test]
+    fn test_repeating_event_keeps_local_time() {
+        let ics = render(&[task("Sync", None, "SCHEDULED: <2025-01-10 Fri 10:00-11:30 +1w>")]);
+        assert!(ics.contains("DTSTART:20250110T100000\r\n"));
+        assert!(ics.contains("DTEND:20250110T113000\r\n"));
+        assert!(ics.contains("RRULE:FREQ=WEEKLY\r\n"));
+    }
+
+    #[test]
+    fn test_deadline_todo() {
+        let mut t = task("Report", Some(TaskType::Done), "DEADLINE: <2025-01-15 Wed>");
+        t.priority = Some(Priority::A);
+        let ics = render(&[t]);
+        assert!(ics.contains("BEGIN:VTODO\r\n"));
+        assert!(ics.contains("DUE;VALUE=DATE:20250115\r\n"));
+        assert!(ics.contains("STATUS:COMPLETED\r\n"));
+        assert!(ics.contains("PRIORITY:1\r\n"));
+        assert!(!ics.contains("DTSTART"));
+    }
+
+    #[test]
+    fn test_repeating_deadline_starts_before_due() {
+        let ics = render(&[task("Rent", Some(TaskType::Todo), "DEADLINE: <2024-12-05 Thu +1m>")]);
+        assert!(ics.contains("DUE;VALUE=DATE:20241205\r\nDTSTART;VALUE=DATE:20241204\r\n"));
+        assert!(ics.contains("RRULE:FREQ=MONTHLY\r\n"));
+
+        let ics = render(&[task("Standup notes", Some(TaskType::Todo), "DEADLINE: <2025-01-09 Thu 18:00 +1wd>")]);
+        assert!(ics.contains("DUE:20250109T180000\r\nDTSTART:20250108T180000\r\n"));
+        let rdate = ics.replace("\r\n ", "");
+        let rdate = rdate.lines().find(|l| l.starts_with("RDATE:")).unwrap();
+        // Due 2025-01-10 and 2025-01-13, each starting the day before
+        assert!(rdate.starts_with("RDATE:20250109T180000,20250112T180000,"), "{rdate}");
+    }
+
+    #[test]
+    fn test_hourly_repeater_needs_time() {
+        let ics = render(&[task("Water", None, "SCHEDULED: <2024-12-05 Thu +2h>")]);
+        assert!(ics.contains("DTSTART;VALUE=DATE:20241205\r\n"));
+        assert!(!ics.contains("RRULE"));
+
+        let ics = render(&[task("Water", None, "SCHEDULED: <2024-12-05 Thu 09:00 +2h>")]);
+        assert!(ics.contains("DTSTART:20241205T090000\r\n"));
+        assert!(ics.contains("RRULE:FREQ=HOURLY;INTERVAL=2\r\n"));
+    }
+
+    #[test]
+    fn test_all_day_range() {
+        let ics = render(&[task("Conference", None, "<2024-12-20 Fri>--<2024-12-22 Sun>")]);
+        assert!(ics.contains("DTSTART;VALUE=DATE:20241220\r\n"));
+        assert!(ics.contains("DTEND;VALUE=DATE:20241223\r\n"));
+    }
+
+    #[test]
+    fn test_workday_repeater_expanded() {
+        let ics = render(&[task("Standup", None, "SCHEDULED: <2025-01-09 Thu +1wd>")]);
+        assert!(!ics.contains("RRULE"));
+        let rdate = ics.replace("\r\n ", "");
+        let rdate = rdate.lines().find(|l| l.starts_with("RDATE;VALUE=DATE:")).unwrap();
+        // 2025-01-11/12 are weekend days
+        assert!(rdate.starts_with("RDATE;VALUE=DATE:20250110,20250113,"));
+    }
+
+    #[test]
+    fn test_closed_and_missing_timestamps_skipped() {
+        let mut no_ts = task("Idea", None, "");
+        no_ts.timestamp = None;
+        let ics = render(&[task("Old", None, "CLOSED: <2025-01-01 Wed>"), no_ts]);
+        assert!(!ics.contains("BEGIN:VEVENT"));
+        assert!(!ics.contains("BEGIN:VTODO"));
+    }
+
+    #[test]
+    fn test_stable_unique_uids() {
+        let a = task("Sync", None, "<2025-01-10 Fri>");
+        let ics = render(&[a.clone(), a.clone()]);
+        let uids: Vec<&str> = ics.lines().filter(|l| l.starts_with("UID:")).collect();
+        assert_eq!(uids.len(), 2);
+        assert_ne!(uids[0], uids[1]);
+        assert_eq!(uids[0], format!("UID:{}@markdown-org-extract", task_hash(&a)));
+        assert_eq!(render(&[a]), render(&[task("Sync", None, "<2025-01-10 Fri>")]));
+    }
+
+    #[test]
+    fn test_escape_and_fold() {
+        assert_eq!(escape_text("a, b; c\\d\ne"), "a\\, b\\; c\\\\d\\ne");
+        let line = format!("SUMMARY:{}", "ж".repeat(60));
+        let folded = fold_line(&line);
+        assert!(folded.split("\r\n").all(|l| l.len() <= MAX_LINE_OCTETS));
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+}
@@ -0,0 +1,729 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+
+use crate::timestamp::{add_months, parse_org_timestamp, parse_timestamp_fields};
+use crate::types::{Priority, Task, TaskType};
+
+/// Content line with its property name, parameters and raw value
+#[derive(Debug)]
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Calendar component being collected
+#[derive(Debug)]
+struct Component {
+    kind: String,
+    line: u32,
+    properties: Vec<Property>,
+}
+
+impl Component {
+    fn get(&self, name: &str) -> Option<&Property> {
+        self.properties.iter().find(|p| p.name == name)
+    }
+}
+
+/// `VTIMEZONE` definitions by TZID, for ids that are not IANA names
+type TimeZones = HashMap<String, Vec<Observance>>;
+
+/// `STANDARD` or `DAYLIGHT` part of a `VTIMEZONE`
+#[derive(Debug)]
+struct Observance {
+    /// First onset, in the local time before it
+    start: NaiveDateTime,
+    /// `TZOFFSETTO` in seconds
+    offset: i32,
+    rule: Option<YearlyRule>,
+}
+
+/// `FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU`: the onset repeats on the nth weekday of a month
+#[derive(Debug)]
+struct YearlyRule {
+    month: u32,
+    week: i32,
+    weekday: Weekday,
+    until: Option<NaiveDateTime>,
+}
+
+impl Observance {
+    /// Latest onset at or before `local`
+    fn last_onset(&self, local: NaiveDateTime) -> Option<NaiveDateTime> {
+        let Some(ref rule) = self.rule else {
+            return Some(self.start).filter(|start| *start <= local);
+        };
+        let limit = rule.until.map_or(local, |until| until.min(local));
+        (self.start.year()..=limit.year())
+            .rev()
+            .take(2)
+            .filter_map(|year| nth_weekday(year, rule.month, rule.week, rule.weekday))
+            .map(|date| date.and_time(self.start.time()))
+            .find(|onset| *onset <= limit && *onset >= self.start)
+    }
+}
+
+/// UTC offset in seconds of a local time in a `VTIMEZONE`: the observance
+/// with the latest onset, or the earliest one before any onset
+fn utc_offset(observances: &[Observance], local: NaiveDateTime) -> Option<i32> {
+    observances
+        .iter()
+        .filter_map(|o| o.last_onset(local).map(|onset| (onset, o.offset)))
+        .max_by_key(|(onset, _)| *onset)
+        .map(|(_, offset)| offset)
+        .or_else(|| observances.iter().min_by_key(|o| o.start).map(|o| o.offset))
+}
+
+/// Date or date-time value converted to local time
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum When {
+    Date(NaiveDate),
+    DateTime(NaiveDateTime),
+}
+
+impl When {
+    fn date(self) -> NaiveDate {
+        match self {
+            When::Date(date) => date,
+            When::DateTime(dt) => dt.date(),
+        }
+    }
+}
+
+/// Convert VEVENT and VTODO entries of an iCalendar file into tasks
+///
+/// Events become plain timestamps, to-dos become TODO/DONE tasks with a
+/// DEADLINE (from DUE) or SCHEDULED (from DTSTART) timestamp. UTC and TZID
+/// times are converted to `tz`; a TZID is an IANA name or a VTIMEZONE of the
+/// file. RRULEs are kept only when they map onto a repeater exactly (FREQ and
+/// INTERVAL). Whatever cannot be imported faithfully is reported on stderr.
+pub fn parse_ics(path: &Path, content: &str, tz: Tz) -> Vec<Task> {
+    let (tasks, warnings) = import(path, content, tz);
+    for warning in warnings {
+        eprintln!("Warning: {warning}");
+    }
+    tasks
+}
+
+/// Tasks of an iCalendar file and warnings about what was left out
+///
+/// Changed occurrences of a series (RECURRENCE-ID) are skipped: a repeater
+/// cannot carry exceptions, so they would show up next to the series.
+fn import(path: &Path, content: &str, tz: Tz) -> (Vec<Task>, Vec<String>) {
+    let zones = time_zones(content);
+    let components = components(content);
+    let series: HashSet<&str> = components
+        .iter()
+        .filter(|c| c.get("RECURRENCE-ID").is_none())
+        .filter_map(|c| c.get("UID"))
+        .map(|p| p.value.as_str())
+        .collect();
+
+    let mut tasks = Vec::new();
+    let mut warnings = Vec::new();
+    for component in &components {
+        if let Some(id) = component.get("RECURRENCE-ID") {
+            if component.get("UID").is_some_and(|uid| series.contains(uid.value.as_str())) {
+                warnings.push(format!(
+                    "{}:{}: changed occurrence {} of a recurring entry is not imported",
+                    path.display(),
+                    component.line,
+                    id.value
+                ));
+                continue;
+            }
+        }
+        tasks.extend(component_to_task(path, component, tz, &zones, &mut warnings));
+    }
+    (tasks, warnings)
+}
+
+/// Dates covered by the VEVENTs of a holiday calendar with their SUMMARY
+///
+/// DTEND is exclusive; an event without it covers its start day. Recurrence
+/// rules are not expanded.
+pub fn holiday_dates(content: &str) -> Vec<(NaiveDate, Option<String>)> {
+    let mut dates = Vec::new();
+    for component in components(content).iter().filter(|c| c.kind == "VEVENT") {
+        let Some(start) = component.get("DTSTART").and_then(|p| parse_when(p, Tz::UTC, &TimeZones::new())) else {
+            continue;
+        };
+        let start = start.date();
+        let end = component
+            .get("DTEND")
+            .and_then(|p| parse_when(p, Tz::UTC, &TimeZones::new()))
+            .map(When::date)
+            .filter(|end| *end > start)
+            .unwrap_or(start + Duration::days(1));
+        let name = component.get("SUMMARY").map(|p| unescape_text(&p.value)).filter(|name| !name.is_empty());
+        dates.extend(start.iter_days().take_while(|date| *date < end).map(|date| (date, name.clone())));
+    }
+    dates
+}
+
+/// Top-level VEVENT and VTODO components with their properties
+fn components(content: &str) -> Vec<Component> {
+    let mut components = Vec::new();
+    let mut current: Option<Component> = None;
+    let mut depth = 0usize;
+
+    for (line_no, line) in unfold_lines(content) {
+        let Some(property) = parse_property(&line) else {
+            continue;
+        };
+        match property.name.as_str() {
+            "BEGIN" => {
+                let kind = property.value.to_uppercase();
+                if current.is_some() {
+                    // Nested component such as VALARM
+                    depth += 1;
+                } else if kind == "VEVENT" || kind == "VTODO" {
+                    current = Some(Component { kind, line: line_no, properties: Vec::new() });
+                }
+            }
+            "END" => {
+                if depth > 0 {
+                    depth -= 1;
+                } else if let Some(component) = current.take() {
+                    components.push(component);
+                }
+            }
+            _ => {
+                if depth == 0 {
+                    if let Some(ref mut component) = current {
+                        component.properties.push(property);
+                    }
+                }
+            }
+        }
+    }
+
+    components
+}
+
+/// `VTIMEZONE` components with their `STANDARD` and `DAYLIGHT` observances
+fn time_zones(content: &str) -> TimeZones {
+    let mut zones = TimeZones::new();
+    let mut tzid = None;
+    let mut observances = Vec::new();
+    let mut observance: Option<Vec<Property>> = None;
+    let mut in_zone = false;
+
+    for (_, line) in unfold_lines(content) {
+        let Some(property) = parse_property(&line) else {
+            continue;
+        };
+        let value = property.value.to_uppercase();
+        match (property.name.as_str(), value.as_str()) {
+            ("BEGIN", "VTIMEZONE") => in_zone = true,
+            ("BEGIN", "STANDARD" | "DAYLIGHT") if in_zone => observance = Some(Vec::new()),
+            ("END", "STANDARD" | "DAYLIGHT") => {
+                if let Some(properties) = observance.take() {
+                    observances.extend(parse_observance(&properties));
+                }
+            }
+            ("END", "VTIMEZONE") => {
+                if let Some(id) = tzid.take() {
+                    zones.insert(id, std::mem::take(&mut observances));
+                }
+                observances.clear();
+                in_zone = false;
+            }
+            ("TZID", _) if in_zone && observance.is_none() => tzid = Some(property.value.trim().to_string()),
+            _ => {
+                if let Some(ref mut properties) = observance {
+                    properties.push(property);
+                }
+            }
+        }
+    }
+    zones
+}
+
+fn parse_observance(properties: &[Property]) -> Option<Observance> {
+    let get = |name: &str| properties.iter().find(|p| p.name == name).map(|p| p.value.trim());
+    let start = NaiveDateTime::parse_from_str(get("DTSTART")?, "%Y%m%dT%H%M%S").ok()?;
+    let offset = parse_utc_offset(get("TZOFFSETTO")?)?;
+    let rule = get("RRULE").and_then(yearly_rule);
+    Some(Observance { start, offset, rule })
+}
+
+/// `+0100`, `-0530` or `+013045` in seconds
+fn parse_utc_offset(value: &str) -> Option<i32> {
+    let (sign, digits) = match value.split_at_checked(1)? {
+        ("+", digits) => (1, digits),
+        ("-", digits) => (-1, digits),
+        _ => return None,
+    };
+    if !matches!(digits.len(), 4 | 6) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let field = |range: std::ops::Range<usize>| digits.get(range).map_or(0, |d| d.parse::<i32>().unwrap_or(0));
+    Some(sign * (field(0..2) * 3600 + field(2..4) * 60 + field(4..6)))
+}
+
+fn yearly_rule(rule: &str) -> Option<YearlyRule> {
+    let (mut month, mut byday, mut until, mut yearly) = (None, None, None, false);
+    for part in rule.split(';') {
+        let (key, value) = part.split_once('=')?;
+        match key.to_uppercase().as_str() {
+            "FREQ" => yearly = value.eq_ignore_ascii_case("YEARLY"),
+            "BYMONTH" => month = value.parse().ok(),
+            "BYDAY" => byday = Some(value.to_uppercase()),
+            "UNTIL" => until = NaiveDateTime::parse_from_str(value.trim_end_matches('Z'), "%Y%m%dT%H%M%S").ok(),
+            _ => {}
+        }
+    }
+    let byday = byday.filter(|_| yearly)?;
+    let (week, day) = byday.split_at(byday.len().checked_sub(2)?);
+    let week = if week.is_empty() { 1 } else { week.trim_start_matches('+').parse().ok()? };
+    Some(YearlyRule { month: month?, week, weekday: ical_weekday(day)?, until })
+}
+
+fn ical_weekday(day: &str) -> Option<Weekday> {
+    Some(match day {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+/// `week`-th `weekday` of a month; negative weeks count from the end
+fn nth_weekday(year: i32, month: u32, week: i32, weekday: Weekday) -> Option<NaiveDate> {
+    if week > 0 {
+        return NaiveDate::from_weekday_of_month_opt(year, month, weekday, u8::try_from(week).ok()?);
+    }
+    let last = add_months(NaiveDate::from_ymd_opt(year, month, 1)?, 1)?.pred_opt()?;
+    let back = (last.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday()) % 7;
+    let date = last - Duration::days(back as i64) - Duration::weeks((-week - 1) as i64);
+    Some(date).filter(|date| date.month() == month)
+}
+
+fn component_to_task(
+    path: &Path,
+    component: &Component,
+    tz: Tz,
+    zones: &TimeZones,
+    warnings: &mut Vec<String>,
+) -> Option<Task> {
+    let at = format!("{}:{}", path.display(), component.line);
+    let is_todo = component.kind == "VTODO";
+    for name in ["DTSTART", "DTEND", "DUE"] {
+        let tzid = component.get(name).and_then(|p| p.param("TZID")).map(|id| id.trim_matches('"'));
+        if let Some(id) = tzid.filter(|id| id.parse::<Tz>().is_err() && !zones.contains_key(*id)) {
+            warnings.push(format!("{at}: unknown TZID '{id}' in {name}, its time is read as --tz local time"));
+        }
+    }
+    let start = component.get("DTSTART").and_then(|p| parse_when(p, tz, zones));
+    let due = component.get("DUE").and_then(|p| parse_when(p, tz, zones));
+
+    let (keyword, start) = match (is_todo, due, start) {
+        (true, Some(due), _) => ("DEADLINE: ", due),
+        (true, None, Some(start)) => ("SCHEDULED: ", start),
+        (false, _, Some(start)) => ("", start),
+        _ => return None,
+    };
+    let end = if is_todo {
+        None
+    } else {
+        component.get("DTEND").and_then(|p| parse_when(p, tz, zones))
+    };
+    let repeater = component.get("RRULE").and_then(|p| match rrule_to_repeater(&p.value, start.date().weekday()) {
+        Ok(repeater) => Some(repeater),
+        Err(reason) => {
+            warnings.push(format!("{at}: RRULE '{}' is not imported ({reason}), keeping the first occurrence", p.value));
+            None
+        }
+    });
+    if repeater.is_some() && component.get("EXDATE").is_some() {
+        warnings.push(format!("{at}: EXDATE is not imported, the excluded occurrences still repeat"));
+    }
+    let timestamp = format!("{keyword}{}", format_timestamp(start, end, repeater.as_deref()));
+    let (ts_type, ts_date, ts_time, ts_end_time) = parse_timestamp_fields(&timestamp, &[]);
+
+    let task_type = if is_todo {
+        match component.get("STATUS").map(|p| p.value.to_uppercase()) {
+            Some(ref status) if status == "COMPLETED" => Some(TaskType::Done),
+            _ => Some(TaskType::Todo),
+        }
+    } else {
+        None
+    };
+    let priority = component
+        .get("PRIORITY")
+        .and_then(|p| p.value.trim().parse::<u32>().ok())
+        .and_then(|p| match p {
+            1..=4 => Some(Priority::A),
+            5 => Some(Priority::B),
+            6..=9 => Some(Priority::C),
+            _ => None,
+        });
+    let tags: Vec<String> = component
+        .properties
+        .iter()
+        .filter(|p| p.name == "CATEGORIES")
+        .flat_map(|p| split_list(&p.value))
+        .map(|t| t.replace(' ', "_"))
+        .collect();
+    let created = component
+        .get("CREATED")
+        .and_then(|p| parse_when(p, tz, zones))
+        .map(|w| format!("CREATED: <{}>", w.date().format("%Y-%m-%d %a")));
+
+    Some(Task {
+        file: path.display().to_string(),
+        line: component.line,
+        heading: component.get("SUMMARY").map(|p| unescape_text(&p.value)).unwrap_or_default(),
+        content: component.get("DESCRIPTION").map(|p| unescape_text(&p.value)).unwrap_or_default(),
+        task_type,
+        priority,
+        created,
+        timestamp_type: ts_type,
+        timestamp_date: ts_date,
+        timestamp_time: ts_time,
+        timestamp_end_time: ts_end_time,
+        clocks: None,
+        total_clock_time: None,
+        tags: if tags.is_empty() { None } else { Some(tags) },
+        properties: None,
+        urgency: None,
+        uid: component.get("UID").map(|p| p.value.clone()),
+        parsed_timestamp: parse_org_timestamp(&timestamp, None),
+        timestamp: Some(timestamp),
+    })
+}
+
+/// Org timestamp for a start and optional exclusive end
+fn format_timestamp(start: When, end: Option<When>, repeater: Option<&str>) -> String {
+    let suffix = repeater.map(|r| format!(" {r}")).unwrap_or_default();
+    match (start, end) {
+        (When::DateTime(s), Some(When::DateTime(e))) if s.date() == e.date() => format!(
+            "<{} {}-{}{suffix}>",
+            s.format("%Y-%m-%d %a"),
+            s.format("%H:%M"),
+            e.format("%H:%M")
+        ),
+        (When::DateTime(s), Some(When::DateTime(e))) if e > s => format!(
+            "<{}{suffix}>--<{}>",
+            s.format("%Y-%m-%d %a %H:%M"),
+            e.format("%Y-%m-%d %a %H:%M")
+        ),
+        (When::DateTime(s), _) => format!("<{}{suffix}>", s.format("%Y-%m-%d %a %H:%M")),
+        (When::Date(s), Some(When::Date(e))) if e - s > Duration::days(1) => format!(
+            "<{}{suffix}>--<{}>",
+            s.format("%Y-%m-%d %a"),
+            (e - Duration::days(1)).format("%Y-%m-%d %a")
+        ),
+        (When::Date(s), _) => format!("<{}{suffix}>", s.format("%Y-%m-%d %a")),
+    }
+}
+
+/// Map `FREQ=...;INTERVAL=n` onto an org repeater; a weekly `BYDAY` naming
+/// the start's own weekday adds nothing. Other rule parts are not representable.
+fn rrule_to_repeater(rule: &str, weekday: Weekday) -> Result<String, String> {
+    let mut unit = None;
+    let mut interval = 1u32;
+    let mut byday = None;
+    for part in rule.split(';') {
+        let (key, value) = part.split_once('=').ok_or_else(|| format!("malformed part '{part}'"))?;
+        match key.to_uppercase().as_str() {
+            "FREQ" => {
+                unit = Some(match value.to_uppercase().as_str() {
+                    "HOURLY" => "h",
+                    "DAILY" => "d",
+                    "WEEKLY" => "w",
+                    "MONTHLY" => "m",
+                    "YEARLY" => "y",
+                    other => return Err(format!("FREQ={other}")),
+                })
+            }
+            "INTERVAL" => {
+                interval = value.parse().ok().filter(|&i| i > 0).ok_or_else(|| format!("INTERVAL={value}"))?
+            }
+            "BYDAY" => byday = Some(value.to_uppercase()),
+            "WKST" => {}
+            "COUNT" | "UNTIL" => return Err("a repeater has no end".to_string()),
+            other => return Err(other.to_string()),
+        }
+    }
+    let unit = unit.ok_or("no FREQ")?;
+    if let Some(byday) = byday {
+        if unit != "w" || ical_weekday(&byday) != Some(weekday) {
+            return Err(format!("BYDAY={byday}"));
+        }
+    }
+    Ok(format!("+{interval}{unit}"))
+}
+
+/// Parse DATE, UTC DATE-TIME, TZID DATE-TIME or floating DATE-TIME into local time
+///
+/// A TZID is looked up as an IANA name, then among the file's `zones`; an
+/// unknown one is read as floating time.
+fn parse_when(property: &Property, tz: Tz, zones: &TimeZones) -> Option<When> {
+    let value = property.value.trim();
+    if property.param("VALUE").is_some_and(|v| v.eq_ignore_ascii_case("DATE")) || value.len() == 8 {
+        return NaiveDate::parse_from_str(value, "%Y%m%d").ok().map(When::Date);
+    }
+
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(When::DateTime(Utc.from_utc_datetime(&naive).with_timezone(&tz).naive_local()));
+    }
+
+    let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    let Some(id) = property.param("TZID").map(|id| id.trim_matches('"')) else {
+        return Some(When::DateTime(naive));
+    };
+    let utc = if let Ok(source) = id.parse::<Tz>() {
+        // A local time inside a DST gap does not exist; use the first hour after it
+        source
+            .from_local_datetime(&naive)
+            .earliest()
+            .or_else(|| source.from_local_datetime(&(naive + Duration::hours(1))).earliest())?
+            .naive_utc()
+    } else if let Some(offset) = zones.get(id).and_then(|observances| utc_offset(observances, naive)) {
+        naive - Duration::seconds(offset as i64)
+    } else {
+        return Some(When::DateTime(naive));
+    };
+    Some(When::DateTime(Utc.from_utc_datetime(&utc).with_timezone(&tz).naive_local()))
+}
+
+/// Undo line folding; returns lines with the 1-based number of their first physical line
+fn unfold_lines(content: &str) -> Vec<(u32, String)> {
+    let mut lines: Vec<(u32, String)> = Vec::new();
+    for (idx, raw) in content.lines().enumerate() {
+        let raw = raw.trim_end_matches('\r');
+        if let Some(continuation) = raw.strip_prefix([' ', '\t']) {
+            if let Some((_, last)) = lines.last_mut() {
+                last.push_str(continuation);
+                continue;
+            }
+        }
+        if !raw.is_empty() {
+            lines.push((idx as u32 + 1, raw.to_string()));
+        }
+    }
+    lines
+}
+
+/// Split `NAME;PARAM=VALUE:VALUE` respecting quoted parameter values
+fn parse_property(line: &str) -> Option<Property> {
+    let mut in_quotes = false;
+    let colon = line.char_indices().find_map(|(idx, c)| match c {
+        '"' => {
+            in_quotes = !in_quotes;
+            None
+        }
+        ':' if !in_quotes => Some(idx),
+        _ => None,
+    })?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+    let mut parts = head.split(';');
+    let name = parts.next()?.trim().to_uppercase();
+    let params = parts
+        .filter_map(|p| p.split_once('='))
+        .map(|(k, v)| (k.trim().to_uppercase(), v.to_string()))
+        .collect();
+    Some(Property { name, params, value: value.to_string() })
+}
+
+/// Split a comma-separated text list, honouring `\,` escapes
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                current.push(c);
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            ',' => items.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    items.push(current);
+    items
+        .into_iter()
+        .map(|item| unescape_text(item.trim()))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn unescape_text(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => result.push('\n'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> Vec<Task> {
+        parse_ics(Path::new("team.ics"), content, "Europe/Moscow".parse().unwrap())
+    }
+
+    const CALENDAR: &str = "BEGIN:VCALENDAR\r\n\
+VERSION:2.0\r\n\
+BEGIN:VEVENT\r\n\
+UID:sync-1@example.com\r\n\
+SUMMARY:Weekly sync\\, team\r\n\
+DTSTART:20250110T070000Z\r\n\
+DTEND:20250110T080000Z\r\n\
+RRULE:FREQ=WEEKLY\r\n\
+CATEGORIES:work,meetings\r\n\
+BEGIN:VALARM\r\n\
+TRIGGER:-PT15M\r\n\
+SUMMARY:Reminder\r\n\
+END:VALARM\r\n\
+END:VEVENT\r\n\
+BEGIN:VTODO\r\n\
+UID:report-2@example.com\r\n\
+SUMMARY:Quarterly\r\n  report\r\n\
+DUE;VALUE=DATE:20250115\r\n\
+PRIORITY:1\r\n\
+STATUS:NEEDS-ACTION\r\n\
+END:VTODO\r\n\
+END:VCALENDAR\r\n";
+
+    #[test]
+    fn test_parse_event_with_rrule() {
+        let tasks = parse(CALENDAR);
+        assert_eq!(tasks.len(), 2);
+        let event = &tasks[0];
+        assert_eq!(event.heading, "Weekly sync, team");
+        assert_eq!(event.file, "team.ics");
+        assert_eq!(event.line, 3);
+        assert_eq!(event.uid.as_deref(), Some("sync-1@example.com"));
+        assert_eq!(event.timestamp.as_deref(), Some("<2025-01-10 Fri 10:00-11:00 +1w>"));
+        assert_eq!(event.timestamp_type.as_deref(), Some("PLAIN"));
+        assert_eq!(event.timestamp_time.as_deref(), Some("10:00"));
+        assert_eq!(event.tags, Some(vec!["work".to_string(), "meetings".to_string()]));
+        assert!(event.task_type.is_none());
+    }
+
+    #[test]
+    fn test_parse_todo_with_due() {
+        let tasks = parse(CALENDAR);
+        let todo = &tasks[1];
+        assert_eq!(todo.heading, "Quarterly report");
+        assert_eq!(todo.task_type, Some(TaskType::Todo));
+        assert_eq!(todo.priority, Some(Priority::A));
+        assert_eq!(todo.timestamp.as_deref(), Some("DEADLINE: <2025-01-15 Wed>"));
+        assert_eq!(todo.timestamp_date.as_deref(), Some("2025-01-15"));
+    }
+
+    #[test]
+    fn test_all_day_range_and_tzid() {
+        let content = "BEGIN:VEVENT\nSUMMARY:Trip\nDTSTART;VALUE=DATE:20241220\nDTEND;VALUE=DATE:20241223\nEND:VEVENT\n\
+BEGIN:VEVENT\nSUMMARY:Call\nDTSTART;TZID=\"Europe/Berlin\":20250110T090000\nEND:VEVENT\n";
+        let tasks = parse(content);
+        assert_eq!(tasks[0].timestamp.as_deref(), Some("<2024-12-20 Fri>--<2024-12-22 Sun>"));
+        assert_eq!(tasks[1].timestamp.as_deref(), Some("<2025-01-10 Fri 11:00>"));
+    }
+
+    #[test]
+    fn test_holiday_dates() {
+        let content = "BEGIN:VCALENDAR\nBEGIN:VEVENT\nSUMMARY:Christmas\nDTSTART;VALUE=DATE:20251225\nDTEND;VALUE=DATE:20251227\nEND:VEVENT\n\
+BEGIN:VEVENT\nSUMMARY:New Year\nDTSTART;VALUE=DATE:20260101\nEND:VEVENT\nBEGIN:VTODO\nDUE:20260105\nEND:VTODO\nEND:VCALENDAR\n";
+        let dates: Vec<String> = holiday_dates(content).iter().map(|(d, _)| d.to_string()).collect();
+        assert_eq!(dates, ["2025-12-25", "2025-12-26", "2026-01-01"]);
+        assert_eq!(holiday_dates(content)[1].1.as_deref(), Some("Christmas"));
+    }
+
+    #[test]
+    fn test_unmappable_rrule_dropped() {
+        assert_eq!(rrule_to_repeater("FREQ=DAILY;INTERVAL=2", Weekday::Mon), Ok("+2d".to_string()));
+        assert_eq!(rrule_to_repeater("FREQ=WEEKLY;BYDAY=FR", Weekday::Fri), Ok("+1w".to_string()));
+        assert!(rrule_to_repeater("FREQ=WEEKLY;BYDAY=MO,WE", Weekday::Mon).is_err());
+        assert!(rrule_to_repeater("FREQ=WEEKLY;BYDAY=MO", Weekday::Fri).is_err());
+        assert!(rrule_to_repeater("FREQ=SECONDLY", Weekday::Mon).is_err());
+        assert!(rrule_to_repeater("FREQ=DAILY;COUNT=5", Weekday::Mon).is_err());
+    }
+
+    #[test]
+    fn test_vtimezone_and_dst_gap() {
+        let content = "BEGIN:VCALENDAR\n\
+BEGIN:VTIMEZONE\nTZID:W. Europe Standard Time\n\
+BEGIN:STANDARD\nDTSTART:16010101T030000\nTZOFFSETFROM:+0200\nTZOFFSETTO:+0100\nRRULE:FREQ=YEARLY;BYDAY=-1SU;BYMONTH=10\nEND:STANDARD\n\
+BEGIN:DAYLIGHT\nDTSTART:16010101T020000\nTZOFFSETFROM:+0100\nTZOFFSETTO:+0200\nRRULE:FREQ=YEARLY;BYDAY=-1SU;BYMONTH=3\nEND:DAYLIGHT\n\
+END:VTIMEZONE\n\
+BEGIN:VEVENT\nSUMMARY:Winter\nDTSTART;TZID=W. Europe Standard Time:20250110T090000\nEND:VEVENT\n\
+BEGIN:VEVENT\nSUMMARY:Summer\nDTSTART;TZID=\"W. Europe Standard Time\":20250710T090000\nEND:VEVENT\n\
+BEGIN:VEVENT\nSUMMARY:Gap\nDTSTART;TZID=Europe/Berlin:20250330T023000\nEND:VEVENT\n\
+BEGIN:VEVENT\nSUMMARY:Unknown\nDTSTART;TZID=Mars Standard Time:20250110T090000\nEND:VEVENT\n\
+END:VCALENDAR\n";
+        let (tasks, warnings) = import(Path::new("team.ics"), content, "Europe/Moscow".parse().unwrap());
+        let stamps: Vec<_> = tasks.iter().map(|t| t.timestamp.as_deref().unwrap()).collect();
+        assert_eq!(
+            stamps,
+            ["<2025-01-10 Fri 11:00>", "<2025-07-10 Thu 10:00>", "<2025-03-30 Sun 04:30>", "<2025-01-10 Fri 09:00>"]
+        );
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("Mars Standard Time"), "{}", warnings[0]);
+    }
+
+    #[test]
+    fn test_bounded_and_overridden_series() {
+        let content = "BEGIN:VEVENT\nUID:standup\nSUMMARY:Standup\nDTSTART:20250106T090000\nRRULE:FREQ=DAILY\n\
+EXDATE:20250108T090000\nEND:VEVENT\n\
+BEGIN:VEVENT\nUID:standup\nRECURRENCE-ID:20250107T090000\nSUMMARY:Standup\nDTSTART:20250107T110000\nEND:VEVENT\n\
+BEGIN:VEVENT\nUID:course\nSUMMARY:Course\nDTSTART:20250106T180000\nRRULE:FREQ=WEEKLY;COUNT=4\nEND:VEVENT\n";
+        let (tasks, warnings) = import(Path::new("team.ics"), content, "Europe/Moscow".parse().unwrap());
+        let stamps: Vec<_> = tasks.iter().map(|t| t.timestamp.as_deref().unwrap()).collect();
+        assert_eq!(stamps, ["<2025-01-06 Mon 09:00 +1d>", "<2025-01-06 Mon 18:00>"]);
+        assert_eq!(warnings.len(), 3, "{warnings:?}");
+        assert!(warnings[0].contains("EXDATE"));
+        assert!(warnings[1].contains("team.ics:8") && warnings[1].contains("changed occurrence"));
+        assert!(warnings[2].contains("COUNT=4"));
+    }
+
+    #[test]
+    fn test_roundtrip_with_export() {
+        let tasks = parse(CALENDAR);
+        let exported = super::super::render_ics(
+            &tasks,
+            "Europe/Moscow".parse().unwrap(),
+            NaiveDate::from_ymd_opt(2025, 1, 10).unwrap(),
+            &crate::holidays::Calendars::load("ru", &[], false).unwrap(),
+        );
+        let reimported = parse(&exported);
+        assert_eq!(reimported.len(), 2);
+        assert_eq!(reimported[0].timestamp, tasks[0].timestamp);
+        assert_eq!(reimported[0].uid, tasks[0].uid);
+        assert_eq!(reimported[1].timestamp, tasks[1].timestamp);
+    }
+}
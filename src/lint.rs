@@ -0,0 +1,434 @@
+use chrono::{Datelike, NaiveDate};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use std::path::Path;
+
+use crate::parser::{heading_level, is_fence, parse_heading};
+use crate::timestamp::{parse_org_timestamp, parse_timestamp_parts, TimestampIssue, TimestampParts};
+use crate::types::TaskType;
+
+/// Keywords that introduce a timestamp
+const KEYWORDS: &[&str] = &["SCHEDULED", "DEADLINE", "CLOSED", "CREATED", "CLOCK"];
+
+/// Word followed by a colon and something timestamp-like: `SHEDULED: <2025`
+static KEYWORD_LIKE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b([A-Za-z]{4,})\s*:\s*[<\[]\d").expect("Invalid KEYWORD_LIKE_RE regex")
+});
+
+/// Known planning keyword and the text following it
+static PLANNING_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b(SCHEDULED|DEADLINE|CLOSED|CREATED):(\s*)(\S?)").expect("Invalid PLANNING_RE regex")
+});
+
+/// Active timestamp span starting with a year
+static ACTIVE_SPAN_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"<(\d{4}-[^<>]*)>").expect("Invalid ACTIVE_SPAN_RE regex")
+});
+
+/// Inactive timestamp span starting with a year
+static INACTIVE_SPAN_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\[(\d{4}-\d{1,2}-\d{1,2}[^\[\]]*)\]").expect("Invalid INACTIVE_SPAN_RE regex")
+});
+
+/// Well-formed CLOCK entry
+static CLOCK_ENTRY_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^CLOCK:\s*[\[<]([^\]>]+)[\]>](?:--[\[<]([^\]>]+)[\]>])?(?:\s*=>\s*(\d+):(\d{2}))?")
+        .expect("Invalid CLOCK_ENTRY_RE regex")
+});
+
+/// Category of a lint issue
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LintKind {
+    UnknownKeyword,
+    InvalidTimestamp,
+    WeekdayMismatch,
+    InvalidRepeater,
+    InvalidClock,
+    DeadlineBeforeScheduled,
+    DoneWithoutClosed,
+}
+
+/// Single problem found in a file
+#[derive(Debug, Clone, Serialize)]
+pub struct LintIssue {
+    pub file: String,
+    pub line: u32,
+    pub kind: LintKind,
+    pub message: String,
+}
+
+/// Planning data collected for the heading being scanned
+struct HeadingState {
+    line: u32,
+    task_type: Option<TaskType>,
+    scheduled: Option<NaiveDate>,
+    deadline: Option<(NaiveDate, u32)>,
+    closed: bool,
+}
+
+/// Collects issues for a single file
+struct Linter<'a> {
+    file: String,
+    mappings: &'a [(&'a str, &'a str)],
+    issues: Vec<LintIssue>,
+}
+
+impl<'a> Linter<'a> {
+    fn report(&mut self, line: u32, kind: LintKind, message: String) {
+        self.issues.push(LintIssue {
+            file: self.file.clone(),
+            line,
+            kind,
+            message,
+        });
+    }
+
+    /// Validate a timestamp span, returning its parts if it is usable
+    fn check_span(&mut self, line: u32, span: &str, inner: &str) -> Option<TimestampParts> {
+        let parts = match parse_timestamp_parts(inner, self.mappings) {
+            Ok(parts) => parts,
+            Err(issue) => {
+                let kind = match issue {
+                    TimestampIssue::InvalidRepeater(_) => LintKind::InvalidRepeater,
+                    _ => LintKind::InvalidTimestamp,
+                };
+                self.report(line, kind, format!("{issue} in '{span}'"));
+                return None;
+            }
+        };
+
+        if parts.weekday_mismatch() {
+            if let Some((ref written, _)) = parts.weekday {
+                self.report(
+                    line,
+                    LintKind::WeekdayMismatch,
+                    format!("weekday '{written}' does not match {} ({})", parts.date, parts.date.weekday()),
+                );
+            }
+        } else if span.starts_with('<') && parse_org_timestamp(span, Some(self.mappings)).is_none() {
+            self.report(
+                line,
+                LintKind::InvalidTimestamp,
+                format!("timestamp '{span}' is not recognized by the agenda parser"),
+            );
+        }
+
+        Some(parts)
+    }
+
+    fn check_keywords(&mut self, line: u32, text: &str) {
+        for caps in KEYWORD_LIKE_RE.captures_iter(text) {
+            let word = &caps[1];
+            let upper = word.to_uppercase();
+            if KEYWORDS.contains(&word) {
+                continue;
+            }
+            if KEYWORDS.contains(&upper.as_str()) {
+                self.report(
+                    line,
+                    LintKind::UnknownKeyword,
+                    format!("keyword '{word}:' must be upper-case '{upper}:'"),
+                );
+                continue;
+            }
+            if let Some(suggestion) = KEYWORDS
+                .iter()
+                .find(|kw| upper.len() >= 5 && edit_distance(&upper, kw) <= 2)
+            {
+                self.report(
+                    line,
+                    LintKind::UnknownKeyword,
+                    format!("unknown keyword '{word}:' (did you mean '{suggestion}:'?)"),
+                );
+            }
+        }
+    }
+
+    fn check_clock(&mut self, line: u32, text: &str) {
+        let Some(start) = text.find("CLOCK:") else {
+            return;
+        };
+        let entry = text[start..].trim_end().trim_end_matches('`');
+        let Some(caps) = CLOCK_ENTRY_RE.captures(entry) else {
+            self.report(line, LintKind::InvalidClock, format!("malformed CLOCK entry '{entry}'"));
+            return;
+        };
+
+        let clock_start = self.check_clock_time(line, &caps[1]);
+        let clock_end = caps.get(2).and_then(|m| self.check_clock_time(line, m.as_str()));
+
+        if let (Some(s), Some(e)) = (clock_start, clock_end) {
+            let minutes = (e - s).num_minutes();
+            if minutes < 0 {
+                self.report(line, LintKind::InvalidClock, "CLOCK ends before it starts".to_string());
+            } else if let (Some(h), Some(m)) = (caps.get(3), caps.get(4)) {
+                let written = h.as_str().parse::<i64>().unwrap_or(0) * 60 + m.as_str().parse::<i64>().unwrap_or(0);
+                if written != minutes {
+                    self.report(
+                        line,
+                        LintKind::InvalidClock,
+                        format!(
+                            "CLOCK duration {}:{} does not match interval {}:{:02}",
+                            h.as_str(),
+                            m.as_str(),
+                            minutes / 60,
+                            minutes % 60
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_clock_time(&mut self, line: u32, inner: &str) -> Option<chrono::NaiveDateTime> {
+        let span = format!("[{inner}]");
+        let parts = self.check_span(line, &span, inner)?;
+        match parts.time {
+            Some(time) => Some(parts.date.and_time(time)),
+            None => {
+                self.report(line, LintKind::InvalidClock, format!("CLOCK timestamp '{span}' has no time"));
+                None
+            }
+        }
+    }
+
+    /// Check planning keywords and plain timestamps, updating heading state
+    fn check_timestamps(&mut self, line: u32, text: &str, heading: Option<&mut HeadingState>) {
+        let mut planning: Vec<(&str, usize)> = Vec::new();
+        for caps in PLANNING_RE.captures_iter(text) {
+            let keyword = caps.get(1).map_or("", |m| m.as_str());
+            let next = &caps[3];
+            if next != "<" && !(keyword == "CLOSED" && next == "[") {
+                self.report(
+                    line,
+                    LintKind::InvalidTimestamp,
+                    format!("'{keyword}:' is not followed by a timestamp"),
+                );
+                continue;
+            }
+            planning.push((keyword, caps.get(3).map_or(0, |m| m.start())));
+        }
+
+        for &(keyword, position) in &planning {
+            let has_span = ACTIVE_SPAN_RE
+                .find_iter(text)
+                .chain(INACTIVE_SPAN_RE.find_iter(text))
+                .any(|m| m.start() == position);
+            if !has_span {
+                self.report(
+                    line,
+                    LintKind::InvalidTimestamp,
+                    format!("'{keyword}:' is followed by a malformed timestamp"),
+                );
+            }
+        }
+
+        let mut heading = heading;
+        for caps in ACTIVE_SPAN_RE.captures_iter(text) {
+            let span = caps.get(0).map_or("", |m| m.as_str());
+            let position = caps.get(0).map_or(0, |m| m.start());
+            let parts = self.check_span(line, span, &caps[1]);
+            let keyword = planning.iter().find(|&&(_, p)| p == position).map(|&(k, _)| k);
+            if let (Some(parts), Some(keyword), Some(state)) = (parts, keyword, heading.as_deref_mut()) {
+                match keyword {
+                    "SCHEDULED" => state.scheduled = Some(parts.date),
+                    "DEADLINE" => state.deadline = Some((parts.date, line)),
+                    "CLOSED" => state.closed = true,
+                    _ => {}
+                }
+            }
+        }
+
+        for caps in INACTIVE_SPAN_RE.captures_iter(text) {
+            let span = caps.get(0).map_or("", |m| m.as_str());
+            let position = caps.get(0).map_or(0, |m| m.start());
+            let is_closed = planning.iter().any(|&(k, p)| k == "CLOSED" && p == position);
+            if self.check_span(line, span, &caps[1]).is_some() && is_closed {
+                if let Some(state) = heading.as_deref_mut() {
+                    state.closed = true;
+                }
+            }
+        }
+    }
+
+    fn finish_heading(&mut self, state: HeadingState) {
+        if state.task_type == Some(TaskType::Done) && !state.closed {
+            self.report(state.line, LintKind::DoneWithoutClosed, "DONE task has no CLOSED timestamp".to_string());
+        }
+        if let (Some(scheduled), Some((deadline, line))) = (state.scheduled, state.deadline) {
+            if deadline < scheduled {
+                self.report(
+                    line,
+                    LintKind::DeadlineBeforeScheduled,
+                    format!("DEADLINE {deadline} is before SCHEDULED {scheduled}"),
+                );
+            }
+        }
+    }
+}
+
+/// Lint markdown content and return all issues sorted by line
+pub fn lint_content(path: &Path, content: &str, mappings: &[(&str, &str)]) -> Vec<LintIssue> {
+    let mut linter = Linter {
+        file: path.display().to_string(),
+        mappings,
+        issues: Vec::new(),
+    };
+    let mut current: Option<HeadingState> = None;
+    let mut in_fence = false;
+
+    for (idx, text) in content.lines().enumerate() {
+        let line = idx as u32 + 1;
+
+        if is_fence(text) {
+            in_fence = !in_fence;
+            continue;
+        }
+        if !in_fence {
+            if let Some(level) = heading_level(text) {
+                if let Some(state) = current.take() {
+                    linter.finish_heading(state);
+                }
+                let (task_type, _, _) = parse_heading(text[level..].trim());
+                current = Some(HeadingState {
+                    line,
+                    task_type,
+                    scheduled: None,
+                    deadline: None,
+                    closed: false,
+                });
+                continue;
+            }
+        }
+
+        linter.check_keywords(line, text);
+        if text.contains("CLOCK:") {
+            linter.check_clock(line, text);
+        } else {
+            linter.check_timestamps(line, text, current.as_mut());
+        }
+    }
+
+    if let Some(state) = current.take() {
+        linter.finish_heading(state);
+    }
+
+    linter.issues.sort_by_key(|issue| issue.line);
+    linter.issues
+}
+
+/// Render issues as `file:line: kind: message` lines
+pub fn render_issues_text(issues: &[LintIssue]) -> String {
+    let mut output = String::new();
+    for issue in issues {
+        let kind = serde_json::to_value(issue.kind)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default();
+        output.push_str(&format!("{}:{}: {}: {}\n", issue.file, issue.line, kind, issue.message));
+    }
+    output
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint(content: &str) -> Vec<LintIssue> {
+        lint_content(Path::new("test.md"), content, &[("Пт", "Fri"), ("Сб", "Sat")])
+    }
+
+    fn kinds(issues: &[LintIssue]) -> Vec<LintKind> {
+        issues.iter().map(|i| i.kind).collect()
+    }
+
+    #[test]
+    fn test_clean_file() {
+        let issues = lint("### TODO Task\n`SCHEDULED: <2025-01-10 Fri>`\n`DEADLINE: <2025-01-11 Сб>`\n");
+        assert!(issues.is_empty(), "{issues:?}");
+    }
+
+    #[test]
+    fn test_keyword_typo() {
+        let issues = lint("### TODO Task\n`SHEDULED: <2025-01-10 Fri>`\n");
+        assert_eq!(kinds(&issues), vec![LintKind::UnknownKeyword]);
+        assert_eq!(issues[0].line, 2);
+        assert!(issues[0].message.contains("SCHEDULED"));
+    }
+
+    #[test]
+    fn test_invalid_month() {
+        let issues = lint("### Event\n`<2025-13-01>`\n");
+        assert_eq!(kinds(&issues), vec![LintKind::InvalidTimestamp]);
+    }
+
+    #[test]
+    fn test_missing_separator() {
+        let issues = lint("### Event\n`<2025-01-10 Fri10:00>`\n");
+        assert_eq!(kinds(&issues), vec![LintKind::InvalidTimestamp]);
+    }
+
+    #[test]
+    fn test_weekday_mismatch_localized() {
+        let issues = lint("### Event\n`<2025-01-10 Сб>`\n");
+        assert_eq!(kinds(&issues), vec![LintKind::WeekdayMismatch]);
+    }
+
+    #[test]
+    fn test_invalid_repeater() {
+        let issues = lint("### TODO Task\n`SCHEDULED: <2025-01-10 Fri +1x>`\n");
+        assert_eq!(kinds(&issues), vec![LintKind::InvalidRepeater]);
+    }
+
+    #[test]
+    fn test_malformed_planning_timestamp() {
+        let issues = lint("### TODO Task\n`SCHEDULED: <2025/01/10>`\n`DEADLINE: 2025-01-10`\n");
+        assert_eq!(kinds(&issues), vec![LintKind::InvalidTimestamp, LintKind::InvalidTimestamp]);
+    }
+
+    #[test]
+    fn test_deadline_before_scheduled() {
+        let issues = lint("### TODO Task\n`SCHEDULED: <2025-01-10 Fri>`\n`DEADLINE: <2025-01-09 Thu>`\n");
+        assert_eq!(kinds(&issues), vec![LintKind::DeadlineBeforeScheduled]);
+        assert_eq!(issues[0].line, 3);
+    }
+
+    #[test]
+    fn test_done_without_closed() {
+        let issues = lint("### DONE Task\ntext\n### DONE Other\n`CLOSED: <2025-01-10 Fri>`\n");
+        assert_eq!(kinds(&issues), vec![LintKind::DoneWithoutClosed]);
+        assert_eq!(issues[0].line, 1);
+    }
+
+    #[test]
+    fn test_clock_in_code_block() {
+        let content = "### TODO Task\n```\nCLOCK: [2025-01-10 Fri 10:00]--[2025-01-10 Fri 11:00] =>  1:00\nCLOCK: [2025-01-10 Fri 12:00]--[2025-01-10 Fri 13:00] =>  2:00\nCLOCK: 2025-01-10\n```\n";
+        let issues = lint(content);
+        assert_eq!(kinds(&issues), vec![LintKind::InvalidClock, LintKind::InvalidClock]);
+        assert_eq!(issues[0].line, 4);
+        assert_eq!(issues[1].line, 5);
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("SHEDULED", "SCHEDULED"), 1);
+        assert_eq!(edit_distance("DEADLINE", "DEADLINE"), 0);
+    }
+}
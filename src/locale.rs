@@ -0,0 +1,539 @@
+use std::borrow::Cow;
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::Serialize;
+
+/// Language of renderer labels and formatted dates
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OutputLocale {
+    #[default]
+    En,
+    Ru,
+}
+
+impl FromStr for OutputLocale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "en" => Ok(OutputLocale::En),
+            "ru" => Ok(OutputLocale::Ru),
+            _ => Err(format!("Invalid output locale: {s}. Valid locales: en, ru")),
+        }
+    }
+}
+
+/// Fixed strings used by the renderers
+#[derive(Debug, Serialize)]
+pub struct Labels {
+    /// BCP 47 language tag
+    pub lang: &'static str,
+    pub agenda: &'static str,
+    pub tasks: &'static str,
+    pub overdue: &'static str,
+    pub scheduled: &'static str,
+    pub upcoming: &'static str,
+    pub deadline: &'static str,
+    pub closed: &'static str,
+    pub file: &'static str,
+    pub task_type: &'static str,
+    pub priority: &'static str,
+    pub time: &'static str,
+    pub created: &'static str,
+    pub total_time: &'static str,
+    pub clock: &'static str,
+    pub active: &'static str,
+    /// Marks a non-working day without a holiday name
+    pub day_off: &'static str,
+    /// Precedes the week number of week and month agendas
+    pub week: &'static str,
+    /// Monday first
+    pub weekdays_short: [&'static str; 7],
+    /// Day headings of markdown and org output
+    pub date_format: &'static str,
+    /// Day headings of html and terminal output
+    pub long_date_format: &'static str,
+}
+
+const EN_LABELS: Labels = Labels {
+    lang: "en",
+    agenda: "Agenda",
+    tasks: "Tasks",
+    overdue: "Overdue",
+    scheduled: "Scheduled",
+    upcoming: "Upcoming",
+    deadline: "Deadline",
+    closed: "Closed",
+    file: "File",
+    task_type: "Type",
+    priority: "Priority",
+    time: "Time",
+    created: "Created",
+    total_time: "Total Time",
+    clock: "Clock",
+    active: "active",
+    day_off: "day off",
+    week: "Week",
+    weekdays_short: ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"],
+    date_format: "%Y-%m-%d",
+    long_date_format: "%A, %-d %B %Y",
+};
+
+const RU_LABELS: Labels = Labels {
+    lang: "ru",
+    agenda: "План",
+    tasks: "Задачи",
+    overdue: "Просрочено",
+    scheduled: "Запланировано",
+    upcoming: "Предстоящие",
+    deadline: "Дедлайн",
+    closed: "Закрыто",
+    file: "Файл",
+    task_type: "Тип",
+    priority: "Приоритет",
+    time: "Время",
+    created: "Создано",
+    total_time: "Всего",
+    clock: "Учет времени",
+    active: "идет",
+    day_off: "выходной",
+    week: "Неделя",
+    weekdays_short: ["Пн", "Вт", "Ср", "Чт", "Пт", "Сб", "Вс"],
+    date_format: "%-d %B %Y, %A",
+    long_date_format: "%A, %-d %B %Y",
+};
+
+const EN_WEEKDAYS: [&str; 7] = ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"];
+const RU_WEEKDAYS: [&str; 7] = ["понедельник", "вторник", "среда", "четверг", "пятница", "суббота", "воскресенье"];
+
+const EN_MONTHS: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June", "July", "August", "September", "October", "November",
+    "December",
+];
+/// Genitive case, as in "5 декабря"
+const RU_MONTHS: [&str; 12] = [
+    "января", "февраля", "марта", "апреля", "мая", "июня", "июля", "августа", "сентября", "октября", "ноября",
+    "декабря",
+];
+const RU_MONTHS_SHORT: [&str; 12] = [
+    "янв", "фев", "мар", "апр", "мая", "июн", "июл", "авг", "сен", "окт", "ноя", "дек",
+];
+
+/// Weekday names of one input language, Monday first
+pub struct WeekdayNames {
+    pub code: &'static str,
+    pub full: [&'static str; 7],
+    pub short: [&'static str; 7],
+    /// Other spellings: grammatical cases and forms without diacritics
+    pub variants: &'static [(&'static str, Weekday)],
+}
+
+/// Languages accepted by `--locale`
+pub const WEEKDAY_LOCALES: &[WeekdayNames] = &[
+    WeekdayNames {
+        code: "en",
+        full: EN_WEEKDAYS,
+        short: ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"],
+        variants: &[],
+    },
+    WeekdayNames {
+        code: "ru",
+        full: ["Понедельник", "Вторник", "Среда", "Четверг", "Пятница", "Суббота", "Воскресенье"],
+        short: ["Пн", "Вт", "Ср", "Чт", "Пт", "Сб", "Вс"],
+        variants: &[("Среду", Weekday::Wed), ("Пятницу", Weekday::Fri), ("Субботу", Weekday::Sat)],
+    },
+    WeekdayNames {
+        code: "uk",
+        full: ["Понеділок", "Вівторок", "Середа", "Четвер", "П'ятниця", "Субота", "Неділя"],
+        short: ["Пн", "Вт", "Ср", "Чт", "Пт", "Сб", "Нд"],
+        variants: &[
+            ("П’ятниця", Weekday::Fri),
+            ("Середу", Weekday::Wed),
+            ("П'ятницю", Weekday::Fri),
+            ("П’ятницю", Weekday::Fri),
+            ("Суботу", Weekday::Sat),
+            ("Неділю", Weekday::Sun),
+        ],
+    },
+    WeekdayNames {
+        code: "de",
+        full: ["Montag", "Dienstag", "Mittwoch", "Donnerstag", "Freitag", "Samstag", "Sonntag"],
+        short: ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"],
+        variants: &[("Sonnabend", Weekday::Sat)],
+    },
+    WeekdayNames {
+        code: "fr",
+        full: ["lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche"],
+        short: ["lun", "mar", "mer", "jeu", "ven", "sam", "dim"],
+        variants: &[],
+    },
+    WeekdayNames {
+        code: "es",
+        full: ["lunes", "martes", "miércoles", "jueves", "viernes", "sábado", "domingo"],
+        short: ["lun", "mar", "mié", "jue", "vie", "sáb", "dom"],
+        variants: &[("miercoles", Weekday::Wed), ("sabado", Weekday::Sat), ("mie", Weekday::Wed)],
+    },
+    WeekdayNames {
+        code: "it",
+        full: ["lunedì", "martedì", "mercoledì", "giovedì", "venerdì", "sabato", "domenica"],
+        short: ["lun", "mar", "mer", "gio", "ven", "sab", "dom"],
+        variants: &[
+            ("lunedi", Weekday::Mon),
+            ("martedi", Weekday::Tue),
+            ("mercoledi", Weekday::Wed),
+            ("giovedi", Weekday::Thu),
+            ("venerdi", Weekday::Fri),
+        ],
+    },
+    WeekdayNames {
+        code: "pl",
+        full: ["poniedziałek", "wtorek", "środa", "czwartek", "piątek", "sobota", "niedziela"],
+        short: ["pon", "wt", "śr", "czw", "pt", "sob", "niedz"],
+        variants: &[
+            ("środę", Weekday::Wed),
+            ("sobotę", Weekday::Sat),
+            ("niedzielę", Weekday::Sun),
+            ("poniedzialek", Weekday::Mon),
+            ("sroda", Weekday::Wed),
+            ("piatek", Weekday::Fri),
+            ("ndz", Weekday::Sun),
+        ],
+    },
+    WeekdayNames {
+        code: "pt",
+        full: ["segunda-feira", "terça-feira", "quarta-feira", "quinta-feira", "sexta-feira", "sábado", "domingo"],
+        short: ["seg", "ter", "qua", "qui", "sex", "sáb", "dom"],
+        variants: &[
+            ("segunda", Weekday::Mon),
+            ("terça", Weekday::Tue),
+            ("terca", Weekday::Tue),
+            ("terca-feira", Weekday::Tue),
+            ("quarta", Weekday::Wed),
+            ("quinta", Weekday::Thu),
+            ("sexta", Weekday::Fri),
+            ("sabado", Weekday::Sat),
+        ],
+    },
+];
+
+/// Localized to English weekday names for a comma-separated list of codes
+///
+/// Full names and variants map to full English names, abbreviations to
+/// three-letter ones. Canonical names of a language come before its variants.
+pub fn get_weekday_mappings(locale: &str) -> Vec<(&'static str, &'static str)> {
+    let en = &WEEKDAY_LOCALES[0];
+    let mut mappings = Vec::new();
+    for code in locale.split(',').map(str::trim) {
+        let Some(names) = WEEKDAY_LOCALES.iter().find(|n| n.code.eq_ignore_ascii_case(code)) else {
+            continue;
+        };
+        mappings.extend(names.full.iter().zip(en.full).map(|(l, e)| (*l, e)));
+        mappings.extend(names.short.iter().zip(en.short).map(|(l, e)| (*l, e)));
+        mappings.extend(
+            names
+                .variants
+                .iter()
+                .map(|(l, wd)| (*l, en.full[wd.num_days_from_monday() as usize])),
+        );
+    }
+    mappings
+}
+
+/// Check a `--locale` value against the known weekday languages
+pub fn validate_weekday_locales(locale: &str) -> Result<(), String> {
+    for code in locale.split(',').map(str::trim) {
+        if !WEEKDAY_LOCALES.iter().any(|n| n.code.eq_ignore_ascii_case(code)) {
+            let valid: Vec<&str> = WEEKDAY_LOCALES.iter().map(|n| n.code).collect();
+            return Err(format!("Unknown locale '{code}'. Valid locales: {}", valid.join(", ")));
+        }
+    }
+    Ok(())
+}
+
+/// English name for a localized weekday, compared case-insensitively
+pub fn find_weekday<'a>(word: &str, mappings: &[(&str, &'a str)]) -> Option<&'a str> {
+    mappings
+        .iter()
+        .find(|(localized, _)| eq_ignore_case(localized, word))
+        .map(|(_, english)| *english)
+}
+
+/// Replace localized weekday names in `text` with English ones
+///
+/// Only whole words are replaced, so an abbreviation such as "Ср" never
+/// rewrites the start of a longer word such as "Среду".
+pub fn normalize_weekdays<'a>(text: &'a str, mappings: &[(&str, &str)]) -> Cow<'a, str> {
+    if mappings.is_empty() {
+        return Cow::Borrowed(text);
+    }
+    let mut result = String::new();
+    let mut last = 0;
+    for (start, end) in words(text) {
+        let word = &text[start..end];
+        match find_weekday(word, mappings) {
+            Some(english) if english != word => {
+                result.push_str(&text[last..start]);
+                result.push_str(english);
+                last = end;
+            }
+            _ => {}
+        }
+    }
+    if last == 0 {
+        return Cow::Borrowed(text);
+    }
+    result.push_str(&text[last..]);
+    Cow::Owned(result)
+}
+
+/// True if `token` is a single word in the sense of `normalize_weekdays`
+pub fn is_word(token: &str) -> bool {
+    words(token) == [(0, token.len())]
+}
+
+/// Byte ranges of letter runs; an apostrophe or hyphen between letters
+/// stays inside the word ("П'ятниця", "segunda-feira")
+fn words(text: &str) -> Vec<(usize, usize)> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let letter = |i: usize| chars.get(i).is_some_and(|(_, c)| c.is_alphabetic());
+    let mut words = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if !letter(i) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while letter(i) || (matches!(chars.get(i), Some((_, '\'' | '’' | '-'))) && letter(i + 1)) {
+            i += 1;
+        }
+        let end = chars.get(i).map_or(text.len(), |(pos, _)| *pos);
+        words.push((chars[start].0, end));
+    }
+    words
+}
+
+fn eq_ignore_case(a: &str, b: &str) -> bool {
+    a.chars().flat_map(char::to_lowercase).eq(b.chars().flat_map(char::to_lowercase))
+}
+
+/// Plural category of a count
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Plural {
+    One,
+    Few,
+    Many,
+}
+
+impl OutputLocale {
+    pub fn labels(self) -> &'static Labels {
+        match self {
+            OutputLocale::En => &EN_LABELS,
+            OutputLocale::Ru => &RU_LABELS,
+        }
+    }
+
+    /// CLDR plural category; English has only one/other (Many)
+    pub fn plural(self, n: i64) -> Plural {
+        let n = n.unsigned_abs();
+        match self {
+            OutputLocale::En if n == 1 => Plural::One,
+            OutputLocale::En => Plural::Many,
+            OutputLocale::Ru => match (n % 10, n % 100) {
+                (1, m) if m != 11 => Plural::One,
+                (2..=4, m) if !(12..=14).contains(&m) => Plural::Few,
+                _ => Plural::Many,
+            },
+        }
+    }
+
+    /// Pick the form matching `n`
+    pub fn plural_form<'a>(self, n: i64, one: &'a str, few: &'a str, many: &'a str) -> &'a str {
+        match self.plural(n) {
+            Plural::One => one,
+            Plural::Few => few,
+            Plural::Many => many,
+        }
+    }
+
+    /// "in 3 days" / "3 days ago" for an agenda day offset
+    pub fn days_offset(self, offset: i64) -> String {
+        let n = offset.abs();
+        match self {
+            OutputLocale::En => {
+                let days = self.plural_form(n, "day", "days", "days");
+                if offset > 0 {
+                    format!("in {n} {days}")
+                } else {
+                    format!("{n} {days} ago")
+                }
+            }
+            OutputLocale::Ru => {
+                let days = self.plural_form(n, "день", "дня", "дней");
+                if offset > 0 {
+                    format!("через {n} {days}")
+                } else {
+                    format!("{n} {days} назад")
+                }
+            }
+        }
+    }
+
+    /// "in 3 workdays" / "3 workdays ago"; no direction for 0
+    pub fn workdays_offset(self, offset: i64) -> String {
+        let n = offset.abs();
+        match self {
+            OutputLocale::En => {
+                let workdays = self.plural_form(n, "workday", "workdays", "workdays");
+                match offset {
+                    1.. => format!("in {n} {workdays}"),
+                    0 => format!("{n} {workdays}"),
+                    _ => format!("{n} {workdays} ago"),
+                }
+            }
+            OutputLocale::Ru => {
+                let workdays = self.plural_form(n, "рабочий день", "рабочих дня", "рабочих дней");
+                match offset {
+                    1.. => format!("через {n} {workdays}"),
+                    0 => format!("{n} {workdays}"),
+                    _ => format!("{n} {workdays} назад"),
+                }
+            }
+        }
+    }
+
+    /// strftime-style formatting with localized `%A`, `%a`, `%B` and `%b`;
+    /// fails on specifiers a date cannot fill, like `%H`
+    pub fn format_date(self, date: NaiveDate, format: &str) -> Result<String, fmt::Error> {
+        let weekday = date.weekday().num_days_from_monday() as usize;
+        let month = date.month0() as usize;
+        let mut pattern = String::new();
+        let mut chars = format.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                pattern.push(c);
+                continue;
+            }
+            let name = match chars.next() {
+                Some('A') => self.weekday_name(date.weekday()).to_string(),
+                Some('a') => self.labels().weekdays_short[weekday].to_string(),
+                Some('B') => match self {
+                    OutputLocale::En => EN_MONTHS[month].to_string(),
+                    OutputLocale::Ru => RU_MONTHS[month].to_string(),
+                },
+                Some('b') => match self {
+                    OutputLocale::En => EN_MONTHS[month][..3].to_string(),
+                    OutputLocale::Ru => RU_MONTHS_SHORT[month].to_string(),
+                },
+                Some(spec) => {
+                    pattern.push('%');
+                    pattern.push(spec);
+                    continue;
+                }
+                None => {
+                    pattern.push('%');
+                    continue;
+                }
+            };
+            // Names go through chrono as literal text
+            pattern.push_str(&name.replace('%', "%%"));
+        }
+        let mut output = String::new();
+        write!(output, "{}", date.format(&pattern))?;
+        Ok(output)
+    }
+
+    pub fn weekday_name(self, weekday: Weekday) -> &'static str {
+        let index = weekday.num_days_from_monday() as usize;
+        match self {
+            OutputLocale::En => EN_WEEKDAYS[index],
+            OutputLocale::Ru => RU_WEEKDAYS[index],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_russian_plurals() {
+        let ru = OutputLocale::Ru;
+        let forms: Vec<String> = [1, 2, 5, 11, 12, 21, 22, 25, 101, 111].iter().map(|&n| ru.days_offset(n)).collect();
+        assert_eq!(
+            forms,
+            [
+                "через 1 день",
+                "через 2 дня",
+                "через 5 дней",
+                "через 11 дней",
+                "через 12 дней",
+                "через 21 день",
+                "через 22 дня",
+                "через 25 дней",
+                "через 101 день",
+                "через 111 дней",
+            ]
+        );
+        assert_eq!(ru.days_offset(-3), "3 дня назад");
+        assert_eq!(OutputLocale::En.days_offset(1), "in 1 day");
+        assert_eq!(OutputLocale::En.days_offset(-2), "2 days ago");
+        assert_eq!(ru.workdays_offset(21), "через 21 рабочий день");
+        assert_eq!(ru.workdays_offset(-3), "3 рабочих дня назад");
+        assert_eq!(OutputLocale::En.workdays_offset(0), "0 workdays");
+        assert_eq!(OutputLocale::En.workdays_offset(1), "in 1 workday");
+    }
+
+    #[test]
+    fn test_weekday_mappings() {
+        let ru = get_weekday_mappings("ru");
+        assert!(ru.contains(&("Понедельник", "Monday")));
+        assert!(ru.contains(&("Пн", "Mon")));
+        assert!(ru.contains(&("Среду", "Wednesday")));
+        assert!(get_weekday_mappings("ru,en").contains(&("Monday", "Monday")));
+        assert!(get_weekday_mappings("DE").contains(&("Mi", "Wed")));
+        assert!(get_weekday_mappings("xx").is_empty());
+        assert!(validate_weekday_locales("ru, en,pt").is_ok());
+        assert!(validate_weekday_locales("ru,xx").unwrap_err().contains("'xx'"));
+    }
+
+    #[test]
+    fn test_weekday_names_do_not_collide() {
+        let all = get_weekday_mappings(&WEEKDAY_LOCALES.iter().map(|n| n.code).collect::<Vec<_>>().join(","));
+        for (name, english) in &all {
+            let day = |e: &str| e.parse::<Weekday>().unwrap();
+            for (other, other_english) in &all {
+                if eq_ignore_case(name, other) {
+                    assert_eq!(day(english), day(other_english), "'{name}' and '{other}'");
+                }
+            }
+            assert!(is_word(name), "'{name}'");
+        }
+    }
+
+    #[test]
+    fn test_normalize_weekdays_whole_words() {
+        let ru = get_weekday_mappings("ru");
+        assert_eq!(normalize_weekdays("<2024-12-11 Среду>", &ru), "<2024-12-11 Wednesday>");
+        assert_eq!(normalize_weekdays("<2024-12-11 ср 10:00>", &ru), "<2024-12-11 Wed 10:00>");
+        assert_eq!(normalize_weekdays("Птица Среда", &ru), "Птица Wednesday");
+        assert!(matches!(normalize_weekdays("<2024-12-11 Wed>", &ru), Cow::Borrowed(_)));
+
+        let other = get_weekday_mappings("uk,pt,de");
+        assert_eq!(normalize_weekdays("<2024-12-13 П'ятниця>", &other), "<2024-12-13 Friday>");
+        assert_eq!(normalize_weekdays("<2024-12-09 Segunda-Feira>", &other), "<2024-12-09 Monday>");
+        assert_eq!(normalize_weekdays("<2024-12-12 DO> Dose", &other), "<2024-12-12 Thu> Dose");
+    }
+
+    #[test]
+    fn test_format_date() {
+        let date = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
+        assert_eq!(OutputLocale::Ru.format_date(date, "%A, %-d %B %Y").unwrap(), "четверг, 5 декабря 2024");
+        assert_eq!(OutputLocale::Ru.format_date(date, "%a %d %b").unwrap(), "Чт 05 дек");
+        assert_eq!(OutputLocale::En.format_date(date, "%A, %-d %B %Y").unwrap(), "Thursday, 5 December 2024");
+        assert_eq!(OutputLocale::En.format_date(date, "100%% %Y").unwrap(), "100% 2024");
+        assert!(OutputLocale::En.format_date(date, "%H:%M").is_err());
+    }
+}
@@ -1,29 +1,49 @@
 mod agenda;
 mod cli;
 mod clock;
+mod csv;
+mod date_expr;
 mod error;
 mod format;
 mod holidays;
+mod ics;
+mod lint;
+mod locale;
+mod normalize;
+mod org;
 mod parser;
-mod render;
+mod query;
+mod refile;
+mod sort;
+mod template;
+mod term;
 mod timestamp;
 mod types;
+mod urgency;
 
-use clap::Parser;
+use chrono_tz::Tz;
 use grep_regex::RegexMatcher;
 use grep_searcher::{Searcher, Sink, SinkMatch};
-use ignore::WalkBuilder;
+use ignore::{WalkBuilder, WalkState};
 use std::fs;
 use std::io::{self, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
-use crate::agenda::filter_agenda;
-use crate::cli::{get_weekday_mappings, Cli};
+use crate::agenda::{filter_agenda, prepare_tasks, resolve_today, AgendaOptions};
+use crate::cli::{Cli, Command};
 use crate::error::AppError;
 use crate::format::OutputFormat;
+use crate::holidays::{load_days_off, Calendars};
+use crate::locale::get_weekday_mappings;
 use crate::parser::extract_tasks;
-use crate::render::{render_html, render_markdown};
-use crate::types::{ProcessingStats, MAX_FILE_SIZE};
+use crate::query::Filter;
+use crate::types::{ProcessingStats, Task, MAX_FILE_SIZE};
+
+/// Files read for tasks when `--glob` is not given
+const TASK_GLOB: &str = "*.md,*.org,*.ics";
+/// Files checked by `lint` and `fmt` when `--glob` is not given
+const MARKDOWN_GLOB: &str = "*.md";
 
 fn main() {
     if let Err(e) = run() {
@@ -33,117 +53,348 @@ fn main() {
 }
 
 fn run() -> Result<(), AppError> {
-    let cli = Cli::parse();
+    let cli = Cli::parse_checked();
+
+    if let Some(command) = &cli.command {
+        return match command {
+            Command::Refile { source, dest } => {
+                let summary = refile::refile(source, dest)?;
+                io::stdout().write_all(summary.as_bytes())?;
+                Ok(())
+            }
+            Command::Lint => run_lint(&cli),
+            Command::Fmt { check, weekday_locale } => run_fmt(&cli, *check, weekday_locale),
+        };
+    }
 
     if let Some(year) = cli.holidays {
-        let calendar = holidays::HolidayCalendar::load()
-            .map_err(|e| AppError::Io(io::Error::other(e.to_string())))?;
-        let holidays = calendar.get_holidays_for_year(year);
-        let dates: Vec<String> = holidays.iter()
-            .map(|d| d.format("%Y-%m-%d").to_string())
-            .collect();
-        let output = serde_json::to_string_pretty(&dates)?;
+        let calendars = Calendars::load(&cli.holiday_calendar, &cli.holiday_files, cli.replace_holidays)?;
+        let calendar = calendars.default_calendar();
+        let output = if cli.holiday_details {
+            serde_json::to_string_pretty(&calendar.get_days_for_year(year))?
+        } else {
+            let dates: Vec<String> = calendar.get_holidays_for_year(year).iter()
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .collect();
+            serde_json::to_string_pretty(&dates)?
+        };
         io::stdout().write_all(output.as_bytes())?;
         return Ok(());
     }
 
     let mappings = get_weekday_mappings(&cli.locale);
-
-    if !cli.dir.exists() {
-        return Err(AppError::InvalidDirectory(format!("Directory does not exist: {}", cli.dir.display())));
-    }
-    if !cli.dir.is_dir() {
-        return Err(AppError::InvalidDirectory(format!("Path is not a directory: {}", cli.dir.display())));
-    }
+    let filter = cli.filter.as_deref().map(Filter::parse).transpose()?;
+    let tz: Tz = cli.tz.parse().map_err(|_| AppError::InvalidTimezone(cli.tz.clone()))?;
 
     let mut tasks = Vec::new();
     let mut stats = ProcessingStats::default();
     let matcher = RegexMatcher::new(r"(?m)(^[#*]+\s+(TODO|DONE)\s|DEADLINE:|SCHEDULED:|CREATED:|CLOSED:|CLOCK:)")
         .map_err(|e| AppError::Regex(e.to_string()))?;
 
-    let walker = WalkBuilder::new(&cli.dir).standard_filters(true).build();
+    let load = |path: &Path| load_file(path, &matcher, &mappings, tz);
+    for (_, outcome) in walk_parallel(&cli.dir, cli.glob.as_deref().unwrap_or(TASK_GLOB), load)? {
+        match outcome {
+            FileOutcome::Tasks(file_tasks) => {
+                tasks.extend(file_tasks);
+                stats.files_processed += 1;
+            }
+            FileOutcome::NoMatch => {}
+            FileOutcome::TooLarge => stats.files_skipped_size += 1,
+            FileOutcome::ReadFailed => stats.files_failed_read += 1,
+            FileOutcome::SearchFailed => stats.files_failed_search += 1,
+        }
+    }
 
-    for result in walker {
-        let entry = result?;
-        if !entry.file_type().is_some_and(|ft| ft.is_file()) {
-            continue;
+    if stats.has_warnings() {
+        stats.print_summary();
+    }
+
+    let urgency = cli.urgency_coefficients.unwrap_or_default();
+    let mut calendars = Calendars::load(&cli.holiday_calendar, &cli.holiday_files, cli.replace_holidays)?;
+    for path in &cli.days_off {
+        calendars.add_days_off(&load_days_off(path)?);
+    }
+    let options = AgendaOptions {
+        mode: cli.get_agenda_mode(),
+        date: cli.date.as_ref(),
+        from: cli.from.as_ref(),
+        to: cli.to.as_ref(),
+        tz: &cli.tz,
+        current_date: cli.current_date.as_ref(),
+        filter: filter.as_ref(),
+        sort: &cli.sort,
+        urgency: &urgency,
+        calendars: &calendars,
+        workday_offsets: cli.workday_offsets,
+        week_start: cli.week_start,
+        week_numbers: cli.week_numbers,
+        span: cli.span,
+        compact: cli.compact,
+    };
+
+    if cli.clocks {
+        let delimiter = match cli.format {
+            OutputFormat::Tsv => csv::Delimiter::Tab,
+            _ => csv::Delimiter::Comma,
+        };
+        let today = resolve_today(&cli.tz, cli.current_date.as_ref(), calendars.default_calendar())?;
+        let resolve = |expr: &date_expr::DateExpr| expr.resolve(today, calendars.default_calendar());
+        let from = cli.from.as_ref().map(resolve).transpose()?;
+        let to = cli.to.as_ref().map(resolve).transpose()?;
+        let tasks = prepare_tasks(tasks, &options, today);
+        let output = csv::render_clocks_table(&tasks, delimiter, &cli.columns, from, to);
+        return write_output(cli.output.as_deref(), &output);
+    }
+
+    let output = match (cli.template.as_ref(), cli.format) {
+        // Calendar clients expand repeaters themselves, so export source tasks
+        (_, OutputFormat::Ics) => {
+            let today = resolve_today(&cli.tz, cli.current_date.as_ref(), calendars.default_calendar())?;
+            ics::render_ics(&prepare_tasks(tasks, &options, today), tz, today, &calendars)
+        }
+        (Some(path), _) => {
+            let source = fs::read_to_string(path)
+                .map_err(|e| AppError::Template(format!("cannot read {}: {e}", path.display())))?;
+            render_template(&cli, &options, &source, &filter_agenda(tasks, &options)?)?
+        }
+        (None, OutputFormat::Json) => {
+            render_template(&cli, &options, template::JSON_TEMPLATE, &filter_agenda(tasks, &options)?)?
+        }
+        (None, OutputFormat::Markdown) => {
+            render_template(&cli, &options, template::MARKDOWN_TEMPLATE, &filter_agenda(tasks, &options)?)?
+        }
+        (None, OutputFormat::Html) => {
+            render_template(&cli, &options, template::HTML_TEMPLATE, &filter_agenda(tasks, &options)?)?
+        }
+        (None, OutputFormat::Term) => {
+            let color = term::use_color(cli.output.as_deref());
+            match filter_agenda(tasks, &options)? {
+                agenda::AgendaOutput::Days(days) => term::render_days_term(&days, color, cli.output_locale),
+                agenda::AgendaOutput::Tasks(tasks) => term::render_tasks_term(&tasks, color, cli.output_locale),
+            }
+        }
+        (None, OutputFormat::Org) => match filter_agenda(tasks, &options)? {
+            agenda::AgendaOutput::Days(days) => org::render_days_org(&days, cli.output_locale),
+            agenda::AgendaOutput::Tasks(tasks) => org::render_org(&tasks, cli.output_locale),
+        },
+        (None, format @ (OutputFormat::Csv | OutputFormat::Tsv)) => {
+            let delimiter = if format == OutputFormat::Csv { csv::Delimiter::Comma } else { csv::Delimiter::Tab };
+            match filter_agenda(tasks, &options)? {
+                agenda::AgendaOutput::Days(days) => csv::render_days_table(&days, delimiter, &cli.columns),
+                agenda::AgendaOutput::Tasks(tasks) => csv::render_tasks_table(&tasks, delimiter, &cli.columns),
+            }
         }
+    };
+
+    write_output(cli.output.as_deref(), &output)
+}
+
+/// Render an agenda result through a template
+///
+/// Week, month and span agendas get a calendar grid unless empty days were dropped
+fn render_template(
+    cli: &Cli,
+    options: &AgendaOptions,
+    source: &str,
+    agenda_output: &agenda::AgendaOutput,
+) -> Result<String, AppError> {
+    let grid = matches!(options.mode, "week" | "month" | "span") && !agenda::is_compact(options)?;
+    let calendar = grid.then_some(options.week_start);
+    let context = template::context(agenda_output, calendar, cli.output_locale)?;
+    template::Template::parse(source)?.render(context, cli.output_locale)
+}
 
-        let path = entry.path();
+/// Lint all matching files and fail if any issue was found
+fn run_lint(cli: &Cli) -> Result<(), AppError> {
+    let mappings = get_weekday_mappings(&cli.locale);
+    let mut issues = Vec::new();
 
-        if !matches_glob(path, &cli.glob)? {
+    for path in collect_files(&cli.dir, cli.glob.as_deref().unwrap_or(MARKDOWN_GLOB))? {
+        if fs::metadata(&path).is_ok_and(|m| m.len() > MAX_FILE_SIZE) {
             continue;
         }
+        let content = fs::read_to_string(&path)?;
+        issues.extend(lint::lint_content(&path, &content, &mappings));
+    }
 
-        match fs::metadata(path) {
-            Ok(metadata) => {
-                if metadata.len() > MAX_FILE_SIZE {
-                    stats.files_skipped_size += 1;
-                    continue;
-                }
-            }
-            Err(_) => {
-                stats.files_failed_read += 1;
-                continue;
+    let output = match cli.format {
+        OutputFormat::Json => serde_json::to_string_pretty(&issues)?,
+        _ => lint::render_issues_text(&issues),
+    };
+    write_output(cli.output.as_deref(), &output)?;
+
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::Lint(format!("{} issue(s) found", issues.len())))
+    }
+}
+
+/// Normalize timestamps in all matching files, or only report with `check`
+fn run_fmt(cli: &Cli, check: bool, weekday_locale: &str) -> Result<(), AppError> {
+    let mappings = get_weekday_mappings(&cli.locale);
+    let locale = normalize::WeekdayLocale::from_code(weekday_locale);
+    let mut changed = Vec::new();
+
+    for path in collect_files(&cli.dir, cli.glob.as_deref().unwrap_or(MARKDOWN_GLOB))? {
+        if fs::metadata(&path).is_ok_and(|m| m.len() > MAX_FILE_SIZE) {
+            continue;
+        }
+        let content = fs::read_to_string(&path)?;
+        let formatted = normalize::normalize_content(&content, locale, &mappings);
+        if formatted != content {
+            if !check {
+                fs::write(&path, formatted)?;
             }
+            changed.push(path.display().to_string());
         }
+    }
 
-        let mut found = false;
-        let mut searcher = Searcher::new();
-        if searcher.search_path(&matcher, path, FoundSink { found: &mut found }).is_err() {
-            stats.files_failed_search += 1;
-            continue;
+    let mut output = String::new();
+    for path in &changed {
+        let action = if check { "Needs formatting" } else { "Formatted" };
+        output.push_str(&format!("{action}: {path}\n"));
+    }
+    io::stdout().write_all(output.as_bytes())?;
+
+    if check && !changed.is_empty() {
+        return Err(AppError::Fmt(format!("{} file(s) need formatting", changed.len())));
+    }
+    Ok(())
+}
+
+/// Write output to file or stdout
+fn write_output(path: Option<&Path>, output: &str) -> Result<(), AppError> {
+    if let Some(out_path) = path {
+        fs::write(out_path, output)?;
+    } else {
+        io::stdout().write_all(output.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Walk directory and return files matching the glob pattern
+fn collect_files(dir: &Path, glob: &str) -> Result<Vec<PathBuf>, AppError> {
+    check_directory(dir)?;
+
+    let mut files = Vec::new();
+    let walker = WalkBuilder::new(dir)
+        .standard_filters(true)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+
+    for result in walker {
+        let entry = result?;
+        if is_candidate(&entry, glob)? {
+            files.push(entry.into_path());
         }
+    }
+
+    Ok(files)
+}
 
-        if found {
-            match fs::read_to_string(path) {
-                Ok(content) => {
-                    tasks.extend(extract_tasks(path, &content, &mappings));
-                    stats.files_processed += 1;
+/// Walk `dir` with a pool of workers that each run `load` on the files matching
+/// the glob pattern
+///
+/// Results are sorted by path, which is the order of the sorted serial walk, so
+/// the output does not depend on thread scheduling.
+fn walk_parallel<T, F>(dir: &Path, glob: &str, load: F) -> Result<Vec<(PathBuf, T)>, AppError>
+where
+    T: Send,
+    F: Fn(&Path) -> T + Sync,
+{
+    check_directory(dir)?;
+
+    let results = Mutex::new(Vec::new());
+    let error = Mutex::new(None);
+    WalkBuilder::new(dir).standard_filters(true).build_parallel().run(|| {
+        Box::new(|result| {
+            let candidate = result.map_err(AppError::from).and_then(|entry| {
+                Ok(is_candidate(&entry, glob)?.then(|| entry.into_path()))
+            });
+            match candidate {
+                Ok(Some(path)) => {
+                    let outcome = load(&path);
+                    results.lock().expect("walk results lock poisoned").push((path, outcome));
+                    WalkState::Continue
                 }
-                Err(_) => {
-                    stats.files_failed_read += 1;
+                Ok(None) => WalkState::Continue,
+                Err(e) => {
+                    error.lock().expect("walk error lock poisoned").get_or_insert(e);
+                    WalkState::Quit
                 }
             }
-        }
+        })
+    });
+
+    if let Some(e) = error.into_inner().expect("walk error lock poisoned") {
+        return Err(e);
     }
+    let mut results = results.into_inner().expect("walk results lock poisoned");
+    results.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(results)
+}
 
-    if stats.has_warnings() {
-        stats.print_summary();
+fn check_directory(dir: &Path) -> Result<(), AppError> {
+    if !dir.exists() {
+        return Err(AppError::InvalidDirectory(format!("Directory does not exist: {}", dir.display())));
     }
+    if !dir.is_dir() {
+        return Err(AppError::InvalidDirectory(format!("Path is not a directory: {}", dir.display())));
+    }
+    Ok(())
+}
 
-    let agenda_output = filter_agenda(
-        tasks,
-        cli.get_agenda_mode(),
-        cli.date.as_deref(),
-        cli.from.as_deref(),
-        cli.to.as_deref(),
-        &cli.tz,
-        cli.current_date.as_deref(),
-    )?;
+/// Regular file matching the glob pattern
+fn is_candidate(entry: &ignore::DirEntry, glob: &str) -> Result<bool, AppError> {
+    if !entry.file_type().is_some_and(|ft| ft.is_file()) {
+        return Ok(false);
+    }
+    matches_glob(entry.path(), glob)
+}
 
-    let output = match cli.format {
-        OutputFormat::Json => match agenda_output {
-            agenda::AgendaOutput::Days(days) => serde_json::to_string_pretty(&days)?,
-            agenda::AgendaOutput::Tasks(tasks) => serde_json::to_string_pretty(&tasks)?,
-        },
-        OutputFormat::Markdown => match agenda_output {
-            agenda::AgendaOutput::Days(days) => render::render_days_markdown(&days),
-            agenda::AgendaOutput::Tasks(tasks) => render_markdown(&tasks),
-        },
-        OutputFormat::Html => match agenda_output {
-            agenda::AgendaOutput::Days(days) => render::render_days_html(&days),
-            agenda::AgendaOutput::Tasks(tasks) => render_html(&tasks),
-        },
+/// Result of reading one candidate file
+enum FileOutcome {
+    Tasks(Vec<Task>),
+    /// The prefilter found no task markers
+    NoMatch,
+    TooLarge,
+    ReadFailed,
+    SearchFailed,
+}
+
+/// Read a file once, prefilter its content for task markers and parse it
+fn load_file(path: &Path, matcher: &RegexMatcher, mappings: &[(&str, &str)], tz: Tz) -> FileOutcome {
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.len() > MAX_FILE_SIZE => return FileOutcome::TooLarge,
+        Ok(_) => {}
+        Err(_) => return FileOutcome::ReadFailed,
+    }
+    let Ok(bytes) = fs::read(path) else {
+        return FileOutcome::ReadFailed;
     };
 
-    if let Some(out_path) = cli.output {
-        fs::write(&out_path, output)?;
-    } else {
-        io::stdout().write_all(output.as_bytes())?;
+    if !has_extension(path, "ics") {
+        let mut found = false;
+        let mut searcher = Searcher::new();
+        if searcher.search_slice(matcher, &bytes, FoundSink { found: &mut found }).is_err() {
+            return FileOutcome::SearchFailed;
+        }
+        if !found {
+            return FileOutcome::NoMatch;
+        }
     }
 
-    Ok(())
+    let Ok(content) = String::from_utf8(bytes) else {
+        return FileOutcome::ReadFailed;
+    };
+    FileOutcome::Tasks(if has_extension(path, "ics") {
+        ics::parse_ics(path, &content, tz)
+    } else if has_extension(path, "org") {
+        org::parse_org(path, &content, mappings)
+    } else {
+        extract_tasks(path, &content, mappings)
+    })
 }
 
 struct FoundSink<'a> {
@@ -159,7 +410,23 @@ impl<'a> Sink for FoundSink<'a> {
     }
 }
 
-fn matches_glob(path: &Path, pattern: &str) -> Result<bool, AppError> {
+fn has_extension(path: &Path, ext: &str) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case(ext))
+}
+
+/// Whether `path` matches one of the comma-separated patterns
+fn matches_glob(path: &Path, patterns: &str) -> Result<bool, AppError> {
+    for pattern in patterns.split(',').map(str::trim) {
+        if matches_pattern(path, pattern)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn matches_pattern(path: &Path, pattern: &str) -> Result<bool, AppError> {
     if let Some(ext) = pattern.strip_prefix("*.") {
         if ext.is_empty() {
             return Err(AppError::InvalidGlob("extension cannot be empty".to_string()));
@@ -203,5 +470,62 @@ mod tests {
     fn test_matches_glob_invalid() {
         let path = PathBuf::from("test.md");
         assert!(matches_glob(&path, "*.").is_err());
+        assert!(matches_glob(&path, "*.txt, *.").is_err());
+    }
+
+    #[test]
+    fn test_matches_glob_list() {
+        assert!(matches_glob(&PathBuf::from("cal.ics"), TASK_GLOB).unwrap());
+        assert!(matches_glob(&PathBuf::from("notes.md"), "*.txt, *.md").unwrap());
+        assert!(!matches_glob(&PathBuf::from("cal.ics"), MARKDOWN_GLOB).unwrap());
+        assert!(!matches_glob(&PathBuf::from("cal.ics"), "nothing*.md").unwrap());
+        assert!(matches_glob(&PathBuf::from("x.org"), TASK_GLOB).unwrap());
+        assert!(!matches_glob(&PathBuf::from("x.org"), "nothing*.md").unwrap());
+    }
+
+    #[test]
+    fn test_walk_parallel_reads_once_in_path_order() {
+        let dir = std::env::temp_dir().join(format!("walk-test-{}", std::process::id()));
+        for sub in ["b", "a/c", "a"] {
+            fs::create_dir_all(dir.join(sub)).unwrap();
+        }
+        let task = "# Notes\n\n## TODO Task\nSCHEDULED: <2025-03-03 Mon>\n";
+        for (name, content) in [("b/2.md", task), ("a/c/1.md", task), ("a/z.md", task), ("a/plain.md", "# Notes\n"), ("0.md", task), ("a/skip.txt", task)] {
+            fs::write(dir.join(name), content).unwrap();
+        }
+        fs::write(dir.join("a/bad.md"), b"## TODO \xff\n").unwrap();
+
+        let serial = collect_files(&dir, "*.md,*.org").unwrap();
+        let matcher = RegexMatcher::new(r"(?m)^[#*]+\s+(TODO|DONE)\s").unwrap();
+        let load = |path: &Path| load_file(path, &matcher, &[], chrono_tz::UTC);
+        let parallel = walk_parallel(&dir, "*.md,*.org", load).unwrap();
+        let paths: Vec<PathBuf> = parallel.iter().map(|(path, _)| path.clone()).collect();
+        assert_eq!(paths, serial);
+
+        let outcomes: Vec<(String, &str)> = parallel
+            .iter()
+            .map(|(path, outcome)| {
+                let name = path.strip_prefix(&dir).unwrap().to_string_lossy().replace('\\', "/");
+                let outcome = match outcome {
+                    FileOutcome::Tasks(tasks) if tasks.len() == 1 => "task",
+                    FileOutcome::Tasks(_) => "tasks",
+                    FileOutcome::NoMatch => "no match",
+                    FileOutcome::TooLarge => "too large",
+                    FileOutcome::ReadFailed => "read failed",
+                    FileOutcome::SearchFailed => "search failed",
+                };
+                (name, outcome)
+            })
+            .collect();
+        let expected = [
+            ("0.md", "task"),
+            ("a/bad.md", "read failed"),
+            ("a/c/1.md", "task"),
+            ("a/plain.md", "no match"),
+            ("a/z.md", "task"),
+            ("b/2.md", "task"),
+        ];
+        assert_eq!(outcomes, expected.map(|(name, outcome)| (name.to_string(), outcome)));
+        fs::remove_dir_all(&dir).unwrap();
     }
 }
@@ -0,0 +1,210 @@
+use chrono::{Datelike, Weekday};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+use crate::locale::{find_weekday, get_weekday_mappings};
+use crate::timestamp::{parse_timestamp_parts, TimestampParts};
+
+/// Timestamp span with optional planning keyword prefix
+static SPAN_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"(?:\b(SCHEDULED|DEADLINE|CLOSED|CREATED|CLOCK):[ \t]*)?",
+        r"(?:<(\d{4}-[^<>\n]*)>|\[(\d{4}-\d{1,2}-\d{1,2}[^\[\]\n]*)\])",
+    ))
+    .expect("Invalid SPAN_RE regex")
+});
+
+/// Language used for weekday names in rewritten timestamps
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WeekdayLocale {
+    En,
+    Ru,
+}
+
+impl WeekdayLocale {
+    /// Parse `ru`/`en`; `auto` and unknown values return `None`
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "ru" => Some(WeekdayLocale::Ru),
+            "en" => Some(WeekdayLocale::En),
+            _ => None,
+        }
+    }
+}
+
+/// Rewrite every parseable timestamp in `content` into canonical form
+///
+/// Only timestamp spans (and the spacing after their planning keyword) are
+/// touched; spans that cannot be parsed are left as they are. When `locale`
+/// is `None`, it is detected from the weekday names already used in the file.
+pub fn normalize_content(content: &str, locale: Option<WeekdayLocale>, mappings: &[(&str, &str)]) -> String {
+    let locale = locale.unwrap_or_else(|| detect_locale(content, mappings));
+
+    SPAN_RE
+        .replace_all(content, |caps: &Captures| {
+            let (inner, open, close) = match (caps.get(2), caps.get(3)) {
+                (Some(m), _) => (m.as_str(), '<', '>'),
+                (_, Some(m)) => (m.as_str(), '[', ']'),
+                _ => return caps[0].to_string(),
+            };
+            let Ok(parts) = parse_timestamp_parts(inner, mappings) else {
+                return caps[0].to_string();
+            };
+            let full = parts
+                .weekday
+                .as_ref()
+                .is_some_and(|(written, _)| is_full_weekday_name(written, mappings));
+
+            let mut result = String::new();
+            if let Some(keyword) = caps.get(1) {
+                result.push_str(keyword.as_str());
+                result.push_str(": ");
+            }
+            result.push(open);
+            result.push_str(&canonical_timestamp(&parts, locale, full));
+            result.push(close);
+            result
+        })
+        .into_owned()
+}
+
+/// Canonical form: `DATE WEEKDAY[ TIME[-END]][ REPEATER][ WARNING]`
+pub fn canonical_timestamp(parts: &TimestampParts, locale: WeekdayLocale, full_weekday: bool) -> String {
+    let mut result = format!(
+        "{} {}",
+        parts.date.format("%Y-%m-%d"),
+        weekday_name(parts.date.weekday(), locale, full_weekday)
+    );
+    if let Some(time) = parts.time {
+        result.push_str(&format!(" {}", time.format("%H:%M")));
+        if let Some(end) = parts.end_time {
+            result.push_str(&format!("-{}", end.format("%H:%M")));
+        }
+    }
+    if let Some(ref repeater) = parts.repeater {
+        result.push_str(&format!(" {repeater}"));
+    }
+    if let Some((value, ref unit)) = parts.warning {
+        result.push_str(&format!(" -{value}{unit}"));
+    }
+    result
+}
+
+/// Weekday name in the given locale
+pub fn weekday_name(weekday: Weekday, locale: WeekdayLocale, full: bool) -> String {
+    let english = english_weekday(weekday, full);
+    match locale {
+        WeekdayLocale::En => english.to_string(),
+        WeekdayLocale::Ru => get_weekday_mappings("ru")
+            .into_iter()
+            .find(|(_, en)| *en == english)
+            .map(|(localized, _)| localized.to_string())
+            .unwrap_or_else(|| english.to_string()),
+    }
+}
+
+fn english_weekday(weekday: Weekday, full: bool) -> &'static str {
+    match (weekday, full) {
+        (Weekday::Mon, false) => "Mon",
+        (Weekday::Tue, false) => "Tue",
+        (Weekday::Wed, false) => "Wed",
+        (Weekday::Thu, false) => "Thu",
+        (Weekday::Fri, false) => "Fri",
+        (Weekday::Sat, false) => "Sat",
+        (Weekday::Sun, false) => "Sun",
+        (Weekday::Mon, true) => "Monday",
+        (Weekday::Tue, true) => "Tuesday",
+        (Weekday::Wed, true) => "Wednesday",
+        (Weekday::Thu, true) => "Thursday",
+        (Weekday::Fri, true) => "Friday",
+        (Weekday::Sat, true) => "Saturday",
+        (Weekday::Sun, true) => "Sunday",
+    }
+}
+
+/// Check if a written weekday is a full name (e.g. "Monday", "Понедельник")
+fn is_full_weekday_name(written: &str, mappings: &[(&str, &str)]) -> bool {
+    let english = find_weekday(written, mappings).unwrap_or(written);
+    english.chars().count() > 3
+}
+
+/// Pick the locale whose weekday names dominate the file's timestamps
+fn detect_locale(content: &str, mappings: &[(&str, &str)]) -> WeekdayLocale {
+    let ru = get_weekday_mappings("ru");
+    let mut ru_count = 0usize;
+    let mut en_count = 0usize;
+
+    for caps in SPAN_RE.captures_iter(content) {
+        let Some(inner) = caps.get(2).or_else(|| caps.get(3)) else {
+            continue;
+        };
+        if let Ok(parts) = parse_timestamp_parts(inner.as_str(), mappings) {
+            if let Some((written, _)) = parts.weekday {
+                if find_weekday(&written, &ru).is_some() {
+                    ru_count += 1;
+                } else {
+                    en_count += 1;
+                }
+            }
+        }
+    }
+
+    if ru_count > en_count {
+        WeekdayLocale::Ru
+    } else {
+        WeekdayLocale::En
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(content: &str, locale: Option<WeekdayLocale>) -> String {
+        let mappings = get_weekday_mappings("ru");
+        normalize_content(content, locale, &mappings)
+    }
+
+    #[test]
+    fn test_fix_weekday_english() {
+        let output = normalize("`SCHEDULED: <2025-01-10 Mon>`\n", None);
+        assert_eq!(output, "`SCHEDULED: <2025-01-10 Fri>`\n");
+    }
+
+    #[test]
+    fn test_fix_weekday_russian_full() {
+        let output = normalize("`<2024-12-04 Понедельник 09:00-09:15>`\n", None);
+        assert_eq!(output, "`<2024-12-04 Среда 09:00-09:15>`\n");
+    }
+
+    #[test]
+    fn test_add_missing_weekday_and_spacing() {
+        let output = normalize("`DEADLINE:<2025-1-5>` and `CLOCK:  [2025-01-10 Fri 9:00]`\n", Some(WeekdayLocale::En));
+        assert_eq!(output, "`DEADLINE: <2025-01-05 Sun>` and `CLOCK: [2025-01-10 Fri 09:00]`\n");
+    }
+
+    #[test]
+    fn test_normalize_repeater_and_warning() {
+        let output = normalize("`SCHEDULED: <2025-01-10 Fri +1W -3D>`", Some(WeekdayLocale::En));
+        assert_eq!(output, "`SCHEDULED: <2025-01-10 Fri +1w -3d>`");
+    }
+
+    #[test]
+    fn test_detect_russian_locale() {
+        let output = normalize("`<2025-01-10 Пн>`\n`<2025-01-11 Mon>`\n`<2025-01-12 Вт>`\n", None);
+        assert_eq!(output, "`<2025-01-10 Пт>`\n`<2025-01-11 Сб>`\n`<2025-01-12 Вс>`\n");
+    }
+
+    #[test]
+    fn test_unparseable_and_text_untouched() {
+        let content = "# Heading  with  spaces\n`<2025-13-01>`\ntext <b>bold</b> [link]\n";
+        assert_eq!(normalize(content, None), content);
+    }
+
+    #[test]
+    fn test_idempotent() {
+        let content = "`SCHEDULED:<2025-01-10 Mon 9:00 ++1wd>`\n`<2024-12-20 Среда>--<2024-12-22 Пятница>`\n";
+        let once = normalize(content, None);
+        assert_eq!(normalize(&once, None), once);
+    }
+}
@@ -0,0 +1,5 @@
+mod parse;
+mod render;
+
+pub use parse::parse_org;
+pub use render::{render_days_org, render_org};
@@ -0,0 +1,255 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::render::{is_planning, CREATED_PROPERTY, UID_PROPERTY};
+use crate::clock::{calculate_total_minutes, extract_clocks, format_duration};
+use crate::holidays::inherit_file_calendar;
+use crate::parser::{extract_properties, parse_heading, split_tags};
+use crate::timestamp::{extract_created, extract_timestamp, parse_org_timestamp, parse_timestamp_fields};
+use crate::types::{ClockEntry, Priority, Task, MAX_TASKS};
+
+static ORG_HEADING_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(\*+)\s+(.*?)\s*$").expect("Invalid ORG_HEADING_RE regex"));
+
+/// Source link written by the org renderer: `[[file:PATH::LINE][...]]`
+static SOURCE_LINK_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\[\[file:(.+)::(\d+)\]\[[^\]]*\]\]$").expect("Invalid SOURCE_LINK_RE regex"));
+
+/// File-wide property: `#+PROPERTY: NAME VALUE`
+static FILE_PROPERTY_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)^#\+PROPERTY:\s+(\S+)\s+(.*?)\s*$").expect("Invalid FILE_PROPERTY_RE regex"));
+
+static PRIORITY_COOKIE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\[#([A-Z])\]\s+(.+)$").expect("Invalid PRIORITY_COOKIE_RE regex"));
+
+/// Heading with its body lines
+struct Section<'a> {
+    line: u32,
+    title: &'a str,
+    body: Vec<&'a str>,
+}
+
+/// Extract tasks from an org document
+///
+/// Headings are tasks when they carry a TODO/DONE keyword, a CREATED property
+/// or a timestamp, like in markdown. A `[[file:PATH::LINE]]` link right after
+/// the drawers (as written by `--format org`) restores the original source
+/// location, so rendered tasks parse back unchanged. A `#+PROPERTY:
+/// HOLIDAY_CALENDAR` line before the first heading applies to every task.
+pub fn parse_org(path: &Path, content: &str, mappings: &[(&str, &str)]) -> Vec<Task> {
+    let mut sections: Vec<Section> = Vec::new();
+    let mut file_properties = BTreeMap::new();
+    for (idx, line) in content.lines().enumerate() {
+        if let Some(caps) = FILE_PROPERTY_RE.captures(line).filter(|_| sections.is_empty()) {
+            file_properties.insert(caps[1].to_string(), caps[2].to_string());
+        } else if let Some(caps) = ORG_HEADING_RE.captures(line) {
+            sections.push(Section {
+                line: idx as u32 + 1,
+                title: caps.get(2).map_or("", |m| m.as_str()),
+                body: Vec::new(),
+            });
+        } else if let Some(section) = sections.last_mut() {
+            section.body.push(line);
+        }
+    }
+
+    let mut tasks = Vec::new();
+    for section in &sections {
+        if let Some(task) = section_to_task(path, section, mappings) {
+            tasks.push(task);
+            if tasks.len() >= MAX_TASKS {
+                eprintln!("Warning: Reached maximum task limit ({}) in {}", MAX_TASKS, path.display());
+                break;
+            }
+        }
+    }
+    inherit_file_calendar(&mut tasks, &file_properties);
+    tasks
+}
+
+fn section_to_task(path: &Path, section: &Section, mappings: &[(&str, &str)]) -> Option<Task> {
+    let (task_type, mut priority, title) = parse_heading(section.title);
+    let title = match PRIORITY_COOKIE_RE.captures(&title) {
+        Some(caps) if task_type.is_none() => {
+            priority = caps[1].chars().next().and_then(Priority::from_char);
+            caps[2].to_string()
+        }
+        _ => title,
+    };
+    let (heading, tags) = split_tags(&title);
+
+    let mut timestamp = None;
+    let mut created = None;
+    let mut uid = None;
+    let mut properties = BTreeMap::new();
+    let mut clocks: Vec<ClockEntry> = Vec::new();
+
+    let body = &section.body;
+    let mut pos = 0;
+    if let Some(line) = body.first().filter(|l| is_planning(l.trim_start())) {
+        timestamp = extract_timestamp(line, mappings);
+        pos = 1;
+    }
+
+    // Drawers
+    while let Some(line) = body.get(pos) {
+        let name = line.trim();
+        if !(name.len() > 2 && name.starts_with(':') && name.ends_with(':')) || name.eq_ignore_ascii_case(":END:") {
+            break;
+        }
+        let end = body[pos + 1..]
+            .iter()
+            .position(|l| l.trim().eq_ignore_ascii_case(":END:"))
+            .map_or(body.len(), |offset| pos + 1 + offset);
+        let drawer = body[pos..end.min(body.len())].join("\n");
+        if name.eq_ignore_ascii_case(":PROPERTIES:") {
+            properties.extend(extract_properties(&format!("{drawer}\n:END:")));
+        } else if name.eq_ignore_ascii_case(":LOGBOOK:") {
+            clocks.extend(extract_clocks(&drawer));
+        }
+        pos = end + 1;
+    }
+
+    if let Some(value) = properties.remove(CREATED_PROPERTY) {
+        created = extract_created(&format!("CREATED: {value}"), mappings);
+    }
+    if let Some(value) = properties.remove(UID_PROPERTY) {
+        uid = Some(value);
+    }
+
+    // Source link, optionally preceded by a non-planning timestamp line
+    let rest = body.get(pos..).unwrap_or_default();
+    let mut file = path.display().to_string();
+    let mut line = section.line;
+    let link = rest
+        .iter()
+        .take(2)
+        .position(|l| SOURCE_LINK_RE.is_match(l.trim()));
+    let content_start = match link {
+        Some(idx) => {
+            if let Some(caps) = SOURCE_LINK_RE.captures(rest[idx].trim()) {
+                file = caps[1].to_string();
+                line = caps[2].parse().unwrap_or(section.line);
+            }
+            if idx == 1 && timestamp.is_none() {
+                timestamp = Some(rest[0].trim().to_string());
+            }
+            idx + 1
+        }
+        None => match rest.first().and_then(|l| extract_timestamp(l, mappings)) {
+            Some(ts) if timestamp.is_none() => {
+                timestamp = Some(ts);
+                1
+            }
+            _ => 0,
+        },
+    };
+
+    if task_type.is_none() && created.is_none() && timestamp.is_none() {
+        return None;
+    }
+
+    let content: Vec<&str> = rest
+        .get(content_start..)
+        .unwrap_or_default()
+        .iter()
+        .map(|l| match l.strip_prefix(',') {
+            Some(unescaped) if unescaped.trim_start_matches(',').starts_with('*') => unescaped,
+            _ => l,
+        })
+        .collect();
+
+    let (ts_type, ts_date, ts_time, ts_end_time) = match timestamp {
+        Some(ref ts) => parse_timestamp_fields(ts, &[]),
+        None => (None, None, None, None),
+    };
+    let total_clock_time = calculate_total_minutes(&clocks).map(format_duration);
+
+    Some(Task {
+        file,
+        line,
+        heading,
+        content: content.join("\n").trim().to_string(),
+        task_type,
+        priority,
+        created,
+        timestamp_type: ts_type,
+        timestamp_date: ts_date,
+        timestamp_time: ts_time,
+        timestamp_end_time: ts_end_time,
+        clocks: if clocks.is_empty() { None } else { Some(clocks) },
+        total_clock_time,
+        tags: if tags.is_empty() { None } else { Some(tags) },
+        properties: if properties.is_empty() { None } else { Some(properties) },
+        urgency: None,
+        uid,
+        parsed_timestamp: timestamp.as_deref().and_then(|ts| parse_org_timestamp(ts, None)),
+        timestamp,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::locale::OutputLocale;
+    use crate::org::render_org;
+    use crate::parser::extract_tasks;
+
+    fn as_json(tasks: &[Task]) -> serde_json::Value {
+        serde_json::to_value(tasks).unwrap()
+    }
+
+    #[test]
+    fn test_roundtrip_markdown_tasks() {
+        let markdown = "# TODO [#A] Release :work:\n\
+`DEADLINE: <2024-12-15 Sun>`\n\n\
+Ship the release.\n\n\
+```\n:PROPERTIES:\n:EFFORT: 2\n:END:\nCREATED: <2024-12-01 Sun>\n\
+CLOCK: [2024-12-09 Mon 10:00]--[2024-12-09 Mon 12:30] =>  2:30\n```\n\n\
+## Meeting\n`<2024-12-20 Fri 10:00-11:00>`\n\n\
+## DONE Old\n`CLOSED: <2024-12-02 Mon>`\n";
+        let tasks = extract_tasks(Path::new("notes/work.md"), markdown, &[]);
+        assert_eq!(tasks.len(), 3);
+
+        let org = render_org(&tasks, OutputLocale::En);
+        let parsed = parse_org(Path::new("agenda.org"), &org, &[]);
+        assert_eq!(as_json(&parsed), as_json(&tasks));
+    }
+
+    #[test]
+    fn test_roundtrip_imported_fields() {
+        let mut task = extract_tasks(Path::new("a.md"), "# Call\n`<2025-01-10 Fri>`\n", &[]).remove(0);
+        task.uid = Some("abc@example.com".to_string());
+        task.priority = Some(Priority::B);
+        task.content = "* starts with a star\nsecond line".to_string();
+
+        let org = render_org(std::slice::from_ref(&task), OutputLocale::En);
+        let parsed = parse_org(Path::new("agenda.org"), &org, &[]);
+        assert_eq!(as_json(&parsed), as_json(&[task]));
+    }
+
+    #[test]
+    fn test_plain_org_file() {
+        let org = "#+TITLE: Notes\n\n* Projects\n** TODO Write report :work:\nSCHEDULED: <2025-01-10 Fri>\nSome text\n** Idea\n";
+        let tasks = parse_org(Path::new("notes.org"), org, &[]);
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].heading, "Write report");
+        assert_eq!(tasks[0].file, "notes.org");
+        assert_eq!(tasks[0].line, 4);
+        assert_eq!(tasks[0].timestamp_type.as_deref(), Some("SCHEDULED"));
+        assert_eq!(tasks[0].content, "Some text");
+        assert_eq!(tasks[0].tags, Some(vec!["work".to_string()]));
+    }
+
+    #[test]
+    fn test_file_calendar_property() {
+        let org = "#+PROPERTY: HOLIDAY_CALENDAR de\n* TODO Call\n* TODO Visit\n:PROPERTIES:\n:HOLIDAY_CALENDAR: rs\n:END:\n";
+        let tasks = parse_org(Path::new("team.org"), org, &[]);
+        let calendar = |i: usize| tasks[i].properties.as_ref().and_then(|p| p.get("HOLIDAY_CALENDAR")).cloned();
+        assert_eq!(calendar(0).as_deref(), Some("de"));
+        assert_eq!(calendar(1).as_deref(), Some("rs"));
+    }
+}
@@ -0,0 +1,225 @@
+use chrono::NaiveDate;
+
+use crate::locale::OutputLocale;
+use crate::types::{DayAgenda, Task, TaskType, TaskWithOffset};
+
+/// Property holding `Task::created` in the property drawer
+pub const CREATED_PROPERTY: &str = "CREATED";
+
+/// Property holding `Task::uid` in the property drawer
+pub const UID_PROPERTY: &str = "ICAL_UID";
+
+/// Render a flat task list as an org document, one top-level heading per task
+pub fn render_org(tasks: &[Task], locale: OutputLocale) -> String {
+    let mut output = format!("#+TITLE: {}\n\n", locale.labels().tasks);
+    for task in tasks {
+        render_task(&mut output, task, 1);
+    }
+    output
+}
+
+/// Render day agendas as an org document: day, section, task headings
+pub fn render_days_org(days: &[DayAgenda], locale: OutputLocale) -> String {
+    let labels = locale.labels();
+    let mut output = format!("#+TITLE: {}\n\n", labels.agenda);
+    for day in days {
+        let title = NaiveDate::parse_from_str(&day.date, "%Y-%m-%d")
+            .ok()
+            .and_then(|d| locale.format_date(d, labels.date_format).ok())
+            .unwrap_or_else(|| day.date.clone());
+        let title = match (&day.holiday_name, day.is_workday) {
+            (Some(name), _) => format!("{title} ({name})"),
+            (None, false) => format!("{title} ({})", labels.day_off),
+            (None, true) => title,
+        };
+        match day.week {
+            Some(week) => output.push_str(&format!("* {title} :W{week}:\n")),
+            None => output.push_str(&format!("* {title}\n")),
+        }
+        let scheduled: Vec<&TaskWithOffset> = day.scheduled_timed.iter().chain(&day.scheduled_no_time).collect();
+        let sections = [
+            (labels.overdue, day.overdue.iter().collect::<Vec<_>>()),
+            (labels.scheduled, scheduled),
+            (labels.upcoming, day.upcoming.iter().collect()),
+        ];
+        for (title, entries) in sections {
+            if entries.is_empty() {
+                continue;
+            }
+            output.push_str(&format!("** {title}\n"));
+            for entry in entries {
+                render_task(&mut output, &entry.task, 3);
+            }
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// Render one task as a heading at `level` with planning line, drawers, source link and body
+fn render_task(output: &mut String, task: &Task, level: usize) {
+    output.push_str(&"*".repeat(level));
+    match task.task_type {
+        Some(TaskType::Todo) => output.push_str(" TODO"),
+        Some(TaskType::Done) => output.push_str(" DONE"),
+        None => {}
+    }
+    if let Some(ref priority) = task.priority {
+        output.push_str(&format!(" [#{}]", priority.as_char()));
+    }
+    output.push(' ');
+    output.push_str(&task.heading);
+    if let Some(ref tags) = task.tags {
+        if !tags.is_empty() {
+            output.push_str(&format!(" :{}:", tags.join(":")));
+        }
+    }
+    output.push('\n');
+
+    let planning = task.timestamp.as_deref().filter(|ts| is_planning(ts));
+    if let Some(ts) = planning {
+        output.push_str(ts);
+        output.push('\n');
+    }
+
+    let has_properties = task.created.is_some()
+        || task.uid.is_some()
+        || task.properties.as_ref().is_some_and(|p| !p.is_empty());
+    if has_properties {
+        output.push_str(":PROPERTIES:\n");
+        if let Some(ref created) = task.created {
+            let value = created.strip_prefix("CREATED:").unwrap_or(created).trim();
+            output.push_str(&format!(":{CREATED_PROPERTY}: {value}\n"));
+        }
+        if let Some(ref uid) = task.uid {
+            output.push_str(&format!(":{UID_PROPERTY}: {uid}\n"));
+        }
+        for (key, value) in task.properties.iter().flatten() {
+            output.push_str(&format!(":{key}: {value}\n"));
+        }
+        output.push_str(":END:\n");
+    }
+
+    if let Some(ref clocks) = task.clocks {
+        output.push_str(":LOGBOOK:\n");
+        for clock in clocks {
+            output.push_str(&format!("CLOCK: [{}]", clock.start));
+            if let Some(ref end) = clock.end {
+                output.push_str(&format!("--[{end}]"));
+            }
+            if let Some(ref duration) = clock.duration {
+                output.push_str(&format!(" => {duration:>5}"));
+            }
+            output.push('\n');
+        }
+        output.push_str(":END:\n");
+    }
+
+    if let (Some(ts), None) = (task.timestamp.as_deref(), planning) {
+        output.push_str(ts);
+        output.push('\n');
+    }
+
+    output.push_str(&format!("[[file:{}::{}][{}:{}]]\n", task.file, task.line, task.file, task.line));
+
+    for line in task.content.lines() {
+        // Keep body lines from being read as headings
+        if line.trim_start_matches(',').starts_with('*') {
+            output.push(',');
+        }
+        output.push_str(line);
+        output.push('\n');
+    }
+}
+
+/// SCHEDULED/DEADLINE/CLOSED timestamps go on the planning line
+pub fn is_planning(timestamp: &str) -> bool {
+    ["SCHEDULED:", "DEADLINE:", "CLOSED:"]
+        .iter()
+        .any(|keyword| timestamp.starts_with(keyword))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ClockEntry, Priority};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_render_task() {
+        let task = Task {
+            file: "notes/work.md".to_string(),
+            line: 12,
+            heading: "Release".to_string(),
+            content: "Ship it".to_string(),
+            task_type: Some(TaskType::Todo),
+            priority: Some(Priority::A),
+            created: Some("CREATED: <2024-12-01 Sun>".to_string()),
+            timestamp: Some("DEADLINE: <2024-12-15 Sun>".to_string()),
+            timestamp_type: Some("DEADLINE".to_string()),
+            timestamp_date: Some("2024-12-15".to_string()),
+            timestamp_time: None,
+            timestamp_end_time: None,
+            clocks: Some(vec![ClockEntry {
+                start: "2024-12-09 Mon 10:00".to_string(),
+                end: Some("2024-12-09 Mon 12:30".to_string()),
+                duration: Some("2:30".to_string()),
+            }]),
+            total_clock_time: Some("2:30".to_string()),
+            tags: Some(vec!["work".to_string()]),
+            properties: Some(BTreeMap::from([("EFFORT".to_string(), "2".to_string())])),
+            urgency: None,
+            uid: None,
+            parsed_timestamp: None,
+        };
+        assert_eq!(
+            render_org(&[task], OutputLocale::En),
+            "#+TITLE: Tasks\n\n\
+* TODO [#A] Release :work:\n\
+DEADLINE: <2024-12-15 Sun>\n\
+:PROPERTIES:\n\
+:CREATED: <2024-12-01 Sun>\n\
+:EFFORT: 2\n\
+:END:\n\
+:LOGBOOK:\n\
+CLOCK: [2024-12-09 Mon 10:00]--[2024-12-09 Mon 12:30] =>  2:30\n\
+:END:\n\
+[[file:notes/work.md::12][notes/work.md:12]]\n\
+Ship it\n"
+        );
+    }
+
+    #[test]
+    fn test_render_days_levels() {
+        let mut day = DayAgenda::new(NaiveDate::from_ymd_opt(2024, 12, 5).unwrap());
+        let mut task = Task {
+            file: "a.md".to_string(),
+            line: 1,
+            heading: "Standup".to_string(),
+            content: String::new(),
+            task_type: None,
+            priority: None,
+            created: None,
+            timestamp: Some("<2024-12-05 Thu 09:00>".to_string()),
+            timestamp_type: Some("PLAIN".to_string()),
+            timestamp_date: Some("2024-12-05".to_string()),
+            timestamp_time: Some("09:00".to_string()),
+            timestamp_end_time: None,
+            clocks: None,
+            total_clock_time: None,
+            tags: None,
+            properties: None,
+            urgency: None,
+            uid: None,
+            parsed_timestamp: None,
+        };
+        day.scheduled_timed.push(TaskWithOffset::new(task.clone(), None));
+        task.content = "* not a heading".to_string();
+        day.upcoming.push(TaskWithOffset::new(task, Some(2)));
+
+        let org = render_days_org(&[day], OutputLocale::En);
+        assert!(org.contains("* 2024-12-05\n** Scheduled\n*** Standup\n<2024-12-05 Thu 09:00>\n[[file:a.md::1][a.md:1]]\n"));
+        assert!(org.contains("** Upcoming\n*** Standup\n"));
+        assert!(org.contains("\n,* not a heading\n"));
+    }
+}
@@ -2,10 +2,12 @@ use comrak::nodes::{AstNode, NodeValue};
 use comrak::{parse_document, Arena, Options};
 use once_cell::sync::Lazy;
 use regex::Regex;
+use std::collections::BTreeMap;
 use std::path::Path;
 
 use crate::clock::{calculate_total_minutes, extract_clocks, format_duration};
-use crate::timestamp::{extract_created, extract_timestamp, parse_timestamp_fields};
+use crate::holidays::inherit_file_calendar;
+use crate::timestamp::{extract_created, extract_timestamp, parse_org_timestamp, parse_timestamp_fields};
 use crate::types::{Priority, Task, TaskType, MAX_TASKS};
 
 /// Regex for parsing task headings: TODO/DONE [#A] Task title
@@ -14,6 +16,19 @@ static HEADING_RE: Lazy<Regex> = Lazy::new(|| {
         .expect("Invalid HEADING_RE regex")
 });
 
+/// Regex for trailing org-mode tags: Task title :work:urgent:
+static TAGS_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\s+:((?:[\w@#%]+:)+)\s*$").expect("Invalid TAGS_RE regex")
+});
+
+/// Regex for property drawer entries: :KEY: value
+static PROPERTY_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*:([\w-]+):\s*(.*?)\s*$").expect("Invalid PROPERTY_RE regex")
+});
+
+/// Maximum heading level supported by Markdown ATX headings
+pub const MAX_HEADING_LEVEL: usize = 6;
+
 /// Extract tasks from markdown content
 ///
 /// # Arguments
@@ -23,14 +38,23 @@ static HEADING_RE: Lazy<Regex> = Lazy::new(|| {
 ///
 /// # Returns
 /// Vector of extracted tasks (limited to MAX_TASKS)
+///
+/// A property drawer in a code block before the first heading sets
+/// file-wide properties; its `HOLIDAY_CALENDAR` applies to every task.
 pub fn extract_tasks(path: &Path, content: &str, mappings: &[(&str, &str)]) -> Vec<Task> {
     let arena = Arena::new();
     let root = parse_document(&arena, content, &Options::default());
 
     let mut tasks = Vec::new();
     let mut current_heading: Option<HeadingInfo> = None;
+    let mut file_properties = BTreeMap::new();
 
     for node in root.children() {
+        if current_heading.is_none() {
+            if let NodeValue::CodeBlock(code) = &node.data.borrow().value {
+                file_properties.extend(extract_properties(&code.literal));
+            }
+        }
         process_node(node, path, &mut tasks, &mut current_heading, mappings);
         
         // Safety limit to prevent memory exhaustion
@@ -47,6 +71,7 @@ pub fn extract_tasks(path: &Path, content: &str, mappings: &[(&str, &str)]) -> V
         }
     }
 
+    inherit_file_calendar(&mut tasks, &file_properties);
     tasks
 }
 
@@ -60,6 +85,8 @@ struct HeadingInfo {
     created: Option<String>,
     timestamp: Option<String>,
     clocks: Vec<crate::types::ClockEntry>,
+    tags: Vec<String>,
+    properties: BTreeMap<String, String>,
 }
 
 /// Process a single markdown node
@@ -82,6 +109,7 @@ fn process_node<'a>(
             // Start new heading
             let text = extract_text(node);
             let (task_type, priority, heading) = parse_heading(&text);
+            let (heading, tags) = split_tags(&heading);
             let line = node.data.borrow().sourcepos.start.line as u32;
             *current_heading = Some(HeadingInfo {
                 heading,
@@ -92,6 +120,8 @@ fn process_node<'a>(
                 created: None,
                 timestamp: None,
                 clocks: Vec::new(),
+                tags,
+                properties: BTreeMap::new(),
             });
         }
         NodeValue::Paragraph => {
@@ -128,6 +158,7 @@ fn process_node<'a>(
                 
                 // Extract CLOCK from code block
                 info.clocks.extend(extract_clocks(literal));
+                info.properties.extend(extract_properties(literal));
                 
                 // Accumulate data
                 if created.is_some() {
@@ -162,6 +193,8 @@ fn finalize_task(path: &Path, info: HeadingInfo) -> Option<Task> {
         (None, None)
     };
 
+    let parsed_timestamp = info.timestamp.as_deref().and_then(|ts| parse_org_timestamp(ts, None));
+
     Some(Task {
         file: path.display().to_string(),
         line: info.line,
@@ -177,11 +210,16 @@ fn finalize_task(path: &Path, info: HeadingInfo) -> Option<Task> {
         timestamp_end_time: ts_end_time,
         clocks: clocks_opt,
         total_clock_time: total_time,
+        tags: if info.tags.is_empty() { None } else { Some(info.tags) },
+        properties: if info.properties.is_empty() { None } else { Some(info.properties) },
+        urgency: None,
+        uid: None,
+        parsed_timestamp,
     })
 }
 
 /// Parse heading text to extract task type, priority, and title
-fn parse_heading(text: &str) -> (Option<TaskType>, Option<Priority>, String) {
+pub fn parse_heading(text: &str) -> (Option<TaskType>, Option<Priority>, String) {
     if let Some(caps) = HEADING_RE.captures(text) {
         let task_type = TaskType::from_str(&caps[1]);
         let priority = caps
@@ -195,6 +233,40 @@ fn parse_heading(text: &str) -> (Option<TaskType>, Option<Priority>, String) {
     }
 }
 
+/// Split trailing org-mode tags (`:work:urgent:`) from heading title
+pub fn split_tags(heading: &str) -> (String, Vec<String>) {
+    if let Some(m) = TAGS_RE.captures(heading) {
+        let tags = m[1]
+            .split(':')
+            .filter(|t| !t.is_empty())
+            .map(str::to_string)
+            .collect();
+        let title = heading[..m.get(0).map_or(heading.len(), |g| g.start())].to_string();
+        (title, tags)
+    } else {
+        (heading.to_string(), Vec::new())
+    }
+}
+
+/// Extract entries of a `:PROPERTIES:` ... `:END:` drawer
+pub fn extract_properties(text: &str) -> BTreeMap<String, String> {
+    let mut properties = BTreeMap::new();
+    let mut in_drawer = false;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.eq_ignore_ascii_case(":PROPERTIES:") {
+            in_drawer = true;
+        } else if trimmed.eq_ignore_ascii_case(":END:") {
+            in_drawer = false;
+        } else if in_drawer {
+            if let Some(caps) = PROPERTY_RE.captures(line) {
+                properties.insert(caps[1].to_string(), caps[2].to_string());
+            }
+        }
+    }
+    properties
+}
+
 /// Extract timestamps (CREATED and others) from paragraph node
 fn extract_timestamps_from_node<'a>(
     node: &'a AstNode<'a>,
@@ -240,6 +312,24 @@ fn extract_text<'a>(node: &'a AstNode<'a>) -> String {
     text
 }
 
+/// Return heading level for ATX heading lines (`# Title`)
+pub fn heading_level(line: &str) -> Option<usize> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if level == 0 || level > MAX_HEADING_LEVEL {
+        return None;
+    }
+    match line[level..].chars().next() {
+        None | Some(' ') | Some('\t') => Some(level),
+        _ => None,
+    }
+}
+
+/// Check if line opens or closes a fenced code block
+pub fn is_fence(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with("```") || trimmed.starts_with("~~~")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -295,6 +385,38 @@ mod tests {
         assert_eq!(heading, "Simple task");
     }
 
+    #[test]
+    fn test_split_tags() {
+        let (title, tags) = split_tags("Fix login :work:urgent:");
+        assert_eq!(title, "Fix login");
+        assert_eq!(tags, vec!["work", "urgent"]);
+
+        let (title, tags) = split_tags("Ratio 1:2 kept");
+        assert_eq!(title, "Ratio 1:2 kept");
+        assert!(tags.is_empty());
+    }
+
+    #[test]
+    fn test_extract_tags_and_properties() {
+        let content = "### TODO Task :work:\n```\n:PROPERTIES:\n:EFFORT: 2h\n:ID: abc\n:END:\n```\n";
+        let tasks = extract_tasks(Path::new("test.md"), content, &[]);
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].heading, "Task");
+        assert_eq!(tasks[0].tags, Some(vec!["work".to_string()]));
+        let properties = tasks[0].properties.as_ref().unwrap();
+        assert_eq!(properties.get("EFFORT"), Some(&"2h".to_string()));
+        assert_eq!(properties.get("ID"), Some(&"abc".to_string()));
+    }
+
+    #[test]
+    fn test_file_calendar_property() {
+        let content = "```\n:PROPERTIES:\n:HOLIDAY_CALENDAR: de\n:END:\n```\n\n### TODO A\n\n### TODO B\n```\n:PROPERTIES:\n:HOLIDAY_CALENDAR: rs\n:END:\n```\n";
+        let tasks = extract_tasks(Path::new("test.md"), content, &[]);
+        let calendar = |i: usize| tasks[i].properties.as_ref().and_then(|p| p.get("HOLIDAY_CALENDAR")).cloned();
+        assert_eq!(calendar(0).as_deref(), Some("de"));
+        assert_eq!(calendar(1).as_deref(), Some("rs"));
+    }
+
     #[test]
     fn test_parse_heading_no_task() {
         let (task_type, priority, heading) = parse_heading("Regular heading");
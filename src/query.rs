@@ -0,0 +1,621 @@
+use chrono::{NaiveDate, TimeDelta};
+use regex::Regex;
+
+use crate::error::AppError;
+use crate::timestamp::add_months;
+use crate::types::{Priority, Task, TaskType};
+
+/// Comparison operator in a condition
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// `field:value` - contains / matches
+    Match,
+}
+
+impl CmpOp {
+    fn compare<T: PartialOrd>(self, left: &T, right: &T) -> bool {
+        match self {
+            CmpOp::Eq | CmpOp::Match => left == right,
+            CmpOp::Ne => left != right,
+            CmpOp::Lt => left < right,
+            CmpOp::Le => left <= right,
+            CmpOp::Gt => left > right,
+            CmpOp::Ge => left >= right,
+        }
+    }
+}
+
+/// Date field of a task usable in comparisons
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DateField {
+    Deadline,
+    Scheduled,
+    Closed,
+    Created,
+    /// Any timestamp date
+    Date,
+}
+
+/// Date operand: absolute or relative to the current date
+#[derive(Debug, Clone, PartialEq)]
+enum DateValue {
+    Absolute(NaiveDate),
+    Days(i64),
+    Months(i32),
+}
+
+impl DateValue {
+    fn resolve(&self, today: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            DateValue::Absolute(date) => Some(*date),
+            DateValue::Days(days) => TimeDelta::try_days(*days).and_then(|delta| today.checked_add_signed(delta)),
+            DateValue::Months(months) => add_months(today, *months),
+        }
+    }
+
+    /// Whether the offset fits between the first and the last representable date
+    fn in_range(&self) -> bool {
+        self.resolve(NaiveDate::MIN).is_some() || self.resolve(NaiveDate::MAX).is_some()
+    }
+}
+
+/// Single test applied to a task
+#[derive(Debug, Clone)]
+enum Condition {
+    /// Bare word: heading or content contains text
+    Text(String),
+    Heading(String),
+    Content(String),
+    Tag(String),
+    File(Regex),
+    State(CmpOp, Option<TaskType>),
+    Priority(CmpOp, Option<char>),
+    Date(DateField, CmpOp, Option<DateValue>),
+    Property(String, CmpOp, String),
+    Urgency(CmpOp, f64),
+    Has(String),
+}
+
+/// Parsed filter expression
+#[derive(Debug, Clone)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Cond(Condition),
+}
+
+/// Task filter compiled from a query such as
+/// `priority<=B and tag:work and not file:archive/** and deadline<+7d`
+#[derive(Debug, Clone)]
+pub struct Filter {
+    expr: Expr,
+}
+
+impl Filter {
+    /// Parse a filter expression
+    pub fn parse(query: &str) -> Result<Self, AppError> {
+        let tokens = tokenize(query)?;
+        if tokens.is_empty() {
+            return Err(AppError::Query("empty filter expression".to_string()));
+        }
+        let mut parser = QueryParser { tokens, pos: 0 };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(AppError::Query(format!("unexpected '{}'", token.text)));
+        }
+        Ok(Self { expr })
+    }
+
+    /// Check if task matches the filter; relative dates resolve against `today`
+    pub fn matches(&self, task: &Task, today: NaiveDate) -> bool {
+        eval(&self.expr, task, today)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    text: String,
+    quoted: bool,
+}
+
+/// Split query into words, quoted strings and parentheses
+fn tokenize(query: &str) -> Result<Vec<Token>, AppError> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut chars = query.chars();
+
+    let flush = |current: &mut String, quoted: &mut bool, tokens: &mut Vec<Token>| {
+        if !current.is_empty() || *quoted {
+            tokens.push(Token { text: std::mem::take(current), quoted: *quoted });
+        }
+        *quoted = false;
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                // Only a token that is entirely quoted is literal text
+                quoted = current.is_empty();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(ch) => current.push(ch),
+                        None => return Err(AppError::Query("unterminated quoted string".to_string())),
+                    }
+                }
+            }
+            '(' | ')' => {
+                flush(&mut current, &mut quoted, &mut tokens);
+                tokens.push(Token { text: c.to_string(), quoted: false });
+            }
+            c if c.is_whitespace() => flush(&mut current, &mut quoted, &mut tokens),
+            c => current.push(c),
+        }
+    }
+    flush(&mut current, &mut quoted, &mut tokens);
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser: or > and > not > atom
+struct QueryParser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl QueryParser {
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        self.tokens
+            .get(self.pos)
+            .is_some_and(|t| !t.quoted && t.text.eq_ignore_ascii_case(keyword))
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, AppError> {
+        let mut left = self.parse_and()?;
+        while self.peek_keyword("or") {
+            self.pos += 1;
+            let right = self.parse_and()?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, AppError> {
+        let mut left = self.parse_unary()?;
+        loop {
+            if self.peek_keyword("and") {
+                self.pos += 1;
+            } else if self.pos >= self.tokens.len() || self.peek_keyword("or") || self.peek_keyword(")") {
+                break;
+            }
+            // Juxtaposition is an implicit "and"
+            let right = self.parse_unary()?;
+            left = Expr::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, AppError> {
+        let Some(token) = self.tokens.get(self.pos).cloned() else {
+            return Err(AppError::Query("unexpected end of expression".to_string()));
+        };
+        self.pos += 1;
+
+        if !token.quoted {
+            match token.text.to_lowercase().as_str() {
+                "not" => return Ok(Expr::Not(Box::new(self.parse_unary()?))),
+                "(" => {
+                    let expr = self.parse_or()?;
+                    if !self.peek_keyword(")") {
+                        return Err(AppError::Query("missing ')'".to_string()));
+                    }
+                    self.pos += 1;
+                    return Ok(expr);
+                }
+                ")" | "and" | "or" => {
+                    return Err(AppError::Query(format!("unexpected '{}'", token.text)));
+                }
+                _ => {}
+            }
+        }
+
+        parse_condition(&token).map(Expr::Cond)
+    }
+}
+
+/// Split `field<op>value` into its parts
+fn split_condition(text: &str) -> Option<(&str, CmpOp, &str)> {
+    const OPERATORS: &[(&str, CmpOp)] = &[
+        ("<=", CmpOp::Le),
+        (">=", CmpOp::Ge),
+        ("!=", CmpOp::Ne),
+        ("<", CmpOp::Lt),
+        (">", CmpOp::Gt),
+        ("=", CmpOp::Eq),
+        (":", CmpOp::Match),
+    ];
+
+    let (idx, op_str, op) = text
+        .char_indices()
+        .find_map(|(idx, _)| {
+            OPERATORS
+                .iter()
+                .find(|(op_str, _)| text[idx..].starts_with(op_str))
+                .map(|&(op_str, op)| (idx, op_str, op))
+        })?;
+    if idx == 0 {
+        return None;
+    }
+    Some((&text[..idx], op, &text[idx + op_str.len()..]))
+}
+
+fn parse_condition(token: &Token) -> Result<Condition, AppError> {
+    if token.quoted {
+        return Ok(Condition::Text(token.text.to_lowercase()));
+    }
+    let Some((field, op, value)) = split_condition(&token.text) else {
+        return Ok(Condition::Text(token.text.to_lowercase()));
+    };
+    if value.is_empty() {
+        return Err(AppError::Query(format!("missing value in '{}'", token.text)));
+    }
+
+    let field_lower = field.to_lowercase();
+    let text_only = |cond: Condition| {
+        if op == CmpOp::Match {
+            Ok(cond)
+        } else {
+            Err(AppError::Query(format!("field '{field}' supports only ':' in '{}'", token.text)))
+        }
+    };
+
+    match field_lower.as_str() {
+        "heading" | "title" => text_only(Condition::Heading(value.to_lowercase())),
+        "content" | "body" => text_only(Condition::Content(value.to_lowercase())),
+        "text" => text_only(Condition::Text(value.to_lowercase())),
+        "tag" => text_only(Condition::Tag(value.to_lowercase())),
+        "file" => text_only(Condition::File(glob_to_regex(value)?)),
+        "has" => text_only(Condition::Has(value.to_lowercase())),
+        "state" | "keyword" | "is" => {
+            let state = match value.to_lowercase().as_str() {
+                "todo" => Some(TaskType::Todo),
+                "done" => Some(TaskType::Done),
+                "none" => None,
+                _ => return Err(AppError::Query(format!("unknown state '{value}' (use todo, done or none)"))),
+            };
+            if !matches!(op, CmpOp::Match | CmpOp::Eq | CmpOp::Ne) {
+                return Err(AppError::Query(format!("invalid operator for state in '{}'", token.text)));
+            }
+            Ok(Condition::State(op, state))
+        }
+        "priority" | "pri" => {
+            let priority = if value.eq_ignore_ascii_case("none") {
+                None
+            } else {
+                let mut chars = value.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if c.is_ascii_alphabetic() => Some(c.to_ascii_uppercase()),
+                    _ => return Err(AppError::Query(format!("invalid priority '{value}' (use A-Z or none)"))),
+                }
+            };
+            Ok(Condition::Priority(op, priority))
+        }
+        "deadline" | "scheduled" | "closed" | "created" | "date" => {
+            let date_field = match field_lower.as_str() {
+                "deadline" => DateField::Deadline,
+                "scheduled" => DateField::Scheduled,
+                "closed" => DateField::Closed,
+                "created" => DateField::Created,
+                _ => DateField::Date,
+            };
+            let date_value = if value.eq_ignore_ascii_case("none") {
+                None
+            } else {
+                Some(parse_date_value(value)?)
+            };
+            Ok(Condition::Date(date_field, op, date_value))
+        }
+        "urgency" => {
+            let score: f64 = value
+                .parse()
+                .map_err(|_| AppError::Query(format!("invalid urgency '{value}' (use a number)")))?;
+            Ok(Condition::Urgency(op, score))
+        }
+        _ => {
+            if let Some(name) = field.strip_prefix("prop.").or_else(|| field.strip_prefix("property.")) {
+                if name.is_empty() {
+                    return Err(AppError::Query(format!("missing property name in '{}'", token.text)));
+                }
+                return Ok(Condition::Property(name.to_string(), op, value.to_string()));
+            }
+            Err(AppError::Query(format!("unknown field '{field}'")))
+        }
+    }
+}
+
+/// Parse `today`, `tomorrow`, `yesterday`, `+7d`, `-1w`, `+2m`, `+1y` or `YYYY-MM-DD`
+fn parse_date_value(value: &str) -> Result<DateValue, AppError> {
+    match value.to_lowercase().as_str() {
+        "today" => return Ok(DateValue::Days(0)),
+        "tomorrow" => return Ok(DateValue::Days(1)),
+        "yesterday" => return Ok(DateValue::Days(-1)),
+        _ => {}
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(DateValue::Absolute(date));
+    }
+
+    let invalid = || AppError::Query(format!("invalid date '{value}' (use YYYY-MM-DD, today or +Nd/w/m/y)"));
+    let (sign, rest) = match value.chars().next() {
+        Some('+') => (1, &value[1..]),
+        Some('-') => (-1, &value[1..]),
+        _ => return Err(invalid()),
+    };
+    let unit = rest.chars().last().ok_or_else(invalid)?;
+    let amount: i64 = rest[..rest.len() - unit.len_utf8()].parse().map_err(|_| invalid())?;
+    let amount = sign * amount;
+
+    let out_of_range = || AppError::Query(format!("date offset '{value}' is out of range"));
+    let months = |factor: i32| {
+        i32::try_from(amount).ok().and_then(|months| months.checked_mul(factor)).map(DateValue::Months)
+    };
+    let offset = match unit {
+        'd' => Some(DateValue::Days(amount)),
+        'w' => amount.checked_mul(7).map(DateValue::Days),
+        'm' => months(1),
+        'y' => months(12),
+        _ => return Err(invalid()),
+    };
+    offset.filter(DateValue::in_range).ok_or_else(out_of_range)
+}
+
+/// Convert a path glob (`*`, `**`, `?`) to a regex matching whole path components
+fn glob_to_regex(glob: &str) -> Result<Regex, AppError> {
+    let mut pattern = String::from("(?:^|/)");
+    let mut chars = glob.trim_start_matches("./").chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    pattern.push_str("(?:.*/)?");
+                } else {
+                    pattern.push_str(".*");
+                }
+            }
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern).map_err(|e| AppError::Query(format!("invalid file pattern '{glob}': {e}")))
+}
+
+fn eval(expr: &Expr, task: &Task, today: NaiveDate) -> bool {
+    match expr {
+        Expr::And(a, b) => eval(a, task, today) && eval(b, task, today),
+        Expr::Or(a, b) => eval(a, task, today) || eval(b, task, today),
+        Expr::Not(e) => !eval(e, task, today),
+        Expr::Cond(cond) => eval_condition(cond, task, today),
+    }
+}
+
+fn eval_condition(cond: &Condition, task: &Task, today: NaiveDate) -> bool {
+    match cond {
+        Condition::Text(text) => {
+            task.heading.to_lowercase().contains(text) || task.content.to_lowercase().contains(text)
+        }
+        Condition::Heading(text) => task.heading.to_lowercase().contains(text),
+        Condition::Content(text) => task.content.to_lowercase().contains(text),
+        Condition::Tag(tag) => task
+            .tags
+            .as_ref()
+            .is_some_and(|tags| tags.iter().any(|t| t.to_lowercase() == *tag)),
+        Condition::File(re) => re.is_match(&task.file.replace('\\', "/")),
+        Condition::State(op, state) => (*op == CmpOp::Ne) != (task.task_type == *state),
+        Condition::Priority(op, priority) => match (priority, &task.priority) {
+            (None, p) => (*op == CmpOp::Ne) != p.is_none(),
+            (Some(_), None) => *op == CmpOp::Ne,
+            (Some(expected), Some(actual)) => {
+                let actual = actual.order();
+                let expected = Priority::from_char(*expected).map_or(u32::MAX, |p| p.order());
+                op.compare(&actual, &expected)
+            }
+        },
+        Condition::Date(field, op, value) => {
+            let date = task_date(task, *field);
+            match (value, date) {
+                (None, date) => (*op == CmpOp::Ne) != date.is_none(),
+                (Some(_), None) => false,
+                (Some(value), Some(date)) => value.resolve(today).is_some_and(|v| op.compare(&date, &v)),
+            }
+        }
+        Condition::Property(name, op, expected) => {
+            let actual = task.properties.as_ref().and_then(|props| {
+                props
+                    .iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                    .map(|(_, v)| v.as_str())
+            });
+            match actual {
+                None => *op == CmpOp::Ne,
+                Some(actual) => match (actual.parse::<f64>(), expected.parse::<f64>()) {
+                    (Ok(a), Ok(e)) if *op != CmpOp::Match => op.compare(&a, &e),
+                    _ if *op == CmpOp::Match => actual.to_lowercase().contains(&expected.to_lowercase()),
+                    _ => op.compare(&actual.to_lowercase().as_str(), &expected.to_lowercase().as_str()),
+                },
+            }
+        }
+        Condition::Urgency(op, expected) => task.urgency.as_ref().is_some_and(|u| op.compare(&u.score, expected)),
+        Condition::Has(what) => match what.as_str() {
+            "tags" | "tag" => task.tags.as_ref().is_some_and(|t| !t.is_empty()),
+            "priority" => task.priority.is_some(),
+            "clock" | "clocks" => task.clocks.as_ref().is_some_and(|c| !c.is_empty()),
+            "timestamp" => task.timestamp.is_some(),
+            "deadline" => task_date(task, DateField::Deadline).is_some(),
+            "scheduled" => task_date(task, DateField::Scheduled).is_some(),
+            "created" => task.created.is_some(),
+            other => task
+                .properties
+                .as_ref()
+                .is_some_and(|props| props.keys().any(|k| k.eq_ignore_ascii_case(other))),
+        },
+    }
+}
+
+/// Date of a task for the given field
+fn task_date(task: &Task, field: DateField) -> Option<NaiveDate> {
+    match field {
+        DateField::Deadline => task.deadline_date(),
+        DateField::Scheduled => task.scheduled_date(),
+        DateField::Closed => task.closed_date(),
+        DateField::Created => task.created_date(),
+        DateField::Date => task.date(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn task(heading: &str, priority: Option<Priority>, ts_type: Option<&str>, date: Option<&str>) -> Task {
+        Task {
+            file: "notes/work/plan.md".to_string(),
+            line: 1,
+            heading: heading.to_string(),
+            content: "Discuss budget".to_string(),
+            task_type: Some(TaskType::Todo),
+            priority,
+            created: Some("CREATED: <2024-12-01 Sun>".to_string()),
+            timestamp: date.map(|d| format!("{}: <{d}>", ts_type.unwrap_or("SCHEDULED"))),
+            timestamp_type: ts_type.map(str::to_string),
+            timestamp_date: date.map(str::to_string),
+            timestamp_time: None,
+            timestamp_end_time: None,
+            clocks: None,
+            total_clock_time: None,
+            tags: Some(vec!["work".to_string(), "Urgent".to_string()]),
+            properties: Some(BTreeMap::from([("EFFORT".to_string(), "3".to_string())])),
+            urgency: None,
+            uid: None,
+            parsed_timestamp: None,
+        }
+    }
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, 10).unwrap()
+    }
+
+    fn matches(query: &str, task: &Task) -> bool {
+        Filter::parse(query).unwrap().matches(task, today())
+    }
+
+    #[test]
+    fn test_example_query() {
+        let t = task("Plan", Some(Priority::A), Some("DEADLINE"), Some("2025-01-15"));
+        assert!(matches("priority<=B and tag:work and not file:archive/** and deadline<+7d", &t));
+        let far = task("Plan", Some(Priority::A), Some("DEADLINE"), Some("2025-02-15"));
+        assert!(!matches("priority<=B and tag:work and deadline<+7d", &far));
+    }
+
+    #[test]
+    fn test_priority_comparison() {
+        let c = task("Plan", Some(Priority::C), None, None);
+        assert!(!matches("priority<=B", &c));
+        assert!(matches("priority>B", &c));
+        assert!(matches("priority:C", &c));
+        let none = task("Plan", None, None, None);
+        assert!(!matches("priority<=B", &none));
+        assert!(matches("priority:none", &none));
+    }
+
+    #[test]
+    fn test_text_and_fields() {
+        let t = task("Weekly sync", None, None, None);
+        assert!(matches("sync", &t));
+        assert!(matches("budget", &t));
+        assert!(matches("heading:\"weekly sync\"", &t));
+        assert!(!matches("heading:budget", &t));
+        assert!(matches("content:budget", &t));
+        assert!(matches("tag:urgent", &t));
+    }
+
+    #[test]
+    fn test_state_and_properties() {
+        let t = task("Plan", None, None, None);
+        assert!(matches("state:todo", &t));
+        assert!(!matches("state:done", &t));
+        assert!(matches("state!=done", &t));
+        assert!(matches("prop.EFFORT>=3", &t));
+        assert!(!matches("prop.effort>3", &t));
+        assert!(matches("has:effort", &t));
+    }
+
+    #[test]
+    fn test_file_glob() {
+        let t = task("Plan", None, None, None);
+        assert!(matches("file:work/*.md", &t));
+        assert!(matches("file:**/plan.md", &t));
+        assert!(!matches("file:archive/**", &t));
+    }
+
+    #[test]
+    fn test_dates() {
+        let t = task("Plan", None, Some("SCHEDULED"), Some("2025-01-09"));
+        assert!(matches("scheduled<today", &t));
+        assert!(matches("scheduled=yesterday", &t));
+        assert!(matches("scheduled>=2025-01-01", &t));
+        assert!(matches("deadline:none", &t));
+        assert!(matches("created<-1m", &t));
+        assert!(matches("scheduled<+1000y", &t));
+    }
+
+    #[test]
+    fn test_date_offset_out_of_range() {
+        for value in ["+999999999d", "+99999999999999999d", "-9999999999999999w", "+3000000000m", "+300000000y"] {
+            let err = Filter::parse(&format!("deadline<{value}")).unwrap_err().to_string();
+            assert!(err.contains("out of range"), "{value}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_urgency() {
+        let mut t = task("Plan", None, None, None);
+        assert!(!matches("urgency>0", &t));
+        t.urgency = crate::urgency::compute_urgency(&t, today(), &Default::default());
+        assert!(matches("urgency>1", &t));
+        assert!(!matches("urgency>=2", &t));
+        assert!(Filter::parse("urgency>high").is_err());
+    }
+
+    #[test]
+    fn test_or_and_parentheses() {
+        let t = task("Plan", Some(Priority::B), None, None);
+        assert!(matches("(priority:A or priority:B) and not tag:home", &t));
+        assert!(!matches("priority:A or tag:home", &t));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Filter::parse("").is_err());
+        assert!(Filter::parse("(tag:work").is_err());
+        assert!(Filter::parse("tag:work)").is_err());
+        assert!(Filter::parse("unknown:x").is_err());
+        assert!(Filter::parse("deadline<soon").is_err());
+        assert!(Filter::parse("priority<=11").is_err());
+        assert!(Filter::parse("heading:\"open").is_err());
+        assert!(Filter::parse("tag:work and").is_err());
+    }
+}
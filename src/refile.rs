@@ -0,0 +1,373 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::AppError;
+use crate::parser::{heading_level, is_fence, parse_heading, MAX_HEADING_LEVEL};
+
+/// Heading subtree cut out of a document
+#[derive(Debug)]
+struct Subtree {
+    lines: Vec<String>,
+    level: usize,
+    title: String,
+}
+
+/// Line separator and final newline of a file, restored when writing it back
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LineEndings {
+    newline: &'static str,
+    final_newline: bool,
+}
+
+impl LineEndings {
+    fn detect(content: &str) -> Self {
+        LineEndings {
+            newline: if content.contains("\r\n") { "\r\n" } else { "\n" },
+            final_newline: content.is_empty() || content.ends_with('\n'),
+        }
+    }
+}
+
+/// Refile destination: target file and optional heading inside it
+#[derive(Debug, PartialEq)]
+struct Destination {
+    file: PathBuf,
+    heading: Option<String>,
+}
+
+/// Move a heading subtree from `source` (`FILE:LINE`) to `dest` (`FILE[:HEADING]`)
+///
+/// The subtree keeps its timestamps, clocks and children. Heading levels are
+/// shifted so the subtree becomes a direct child of the destination heading,
+/// or a top-level heading when no destination heading is given.
+pub fn refile(source: &str, dest: &str) -> Result<String, AppError> {
+    let (src_file, src_line) = parse_source(source)?;
+    let dest = parse_destination(dest);
+
+    let src_content = fs::read_to_string(&src_file)?;
+    let src_endings = LineEndings::detect(&src_content);
+    let mut src_lines = split_lines(&src_content);
+    let subtree = take_subtree(&mut src_lines, src_line)?;
+    let title = subtree.title.clone();
+    let line_count = subtree.lines.len();
+
+    if same_file(&src_file, &dest.file) {
+        insert_subtree(&mut src_lines, subtree, dest.heading.as_deref())?;
+        fs::write(&src_file, join_lines(&src_lines, src_endings))?;
+    } else {
+        let dest_content = fs::read_to_string(&dest.file)?;
+        let dest_endings = LineEndings::detect(&dest_content);
+        let mut dest_lines = split_lines(&dest_content);
+        insert_subtree(&mut dest_lines, subtree, dest.heading.as_deref())?;
+        // Write destination first so a failure never loses the subtree
+        fs::write(&dest.file, join_lines(&dest_lines, dest_endings))?;
+        fs::write(&src_file, join_lines(&src_lines, src_endings))?;
+    }
+
+    let target = match dest.heading {
+        Some(ref heading) => format!("{} under '{heading}'", dest.file.display()),
+        None => dest.file.display().to_string(),
+    };
+    Ok(format!(
+        "Refiled '{title}' ({line_count} lines) from {}:{src_line} to {target}\n",
+        src_file.display()
+    ))
+}
+
+/// Parse `FILE:LINE` source location
+fn parse_source(source: &str) -> Result<(PathBuf, usize), AppError> {
+    let (file, line) = source
+        .rsplit_once(':')
+        .ok_or_else(|| AppError::Refile(format!("source '{source}' must be FILE:LINE")))?;
+    let line: usize = line
+        .parse()
+        .ok()
+        .filter(|&l| l > 0)
+        .ok_or_else(|| AppError::Refile(format!("invalid line number in '{source}'")))?;
+    Ok((PathBuf::from(file), line))
+}
+
+/// Parse `FILE` or `FILE:HEADING` destination
+fn parse_destination(dest: &str) -> Destination {
+    if Path::new(dest).exists() {
+        return Destination { file: PathBuf::from(dest), heading: None };
+    }
+    match dest.split_once(':') {
+        Some((file, heading)) if !heading.trim().is_empty() => Destination {
+            file: PathBuf::from(file),
+            heading: Some(heading.trim().to_string()),
+        },
+        Some((file, _)) => Destination { file: PathBuf::from(file), heading: None },
+        None => Destination { file: PathBuf::from(dest), heading: None },
+    }
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn split_lines(content: &str) -> Vec<String> {
+    content.lines().map(str::to_string).collect()
+}
+
+fn join_lines(lines: &[String], endings: LineEndings) -> String {
+    let mut output = lines.join(endings.newline);
+    if endings.final_newline && !output.is_empty() {
+        output.push_str(endings.newline);
+    }
+    output
+}
+
+/// Collect (index, level) of all headings outside fenced code blocks
+fn find_headings(lines: &[String]) -> Vec<(usize, usize)> {
+    let mut headings = Vec::new();
+    let mut in_fence = false;
+    for (idx, line) in lines.iter().enumerate() {
+        if is_fence(line) {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        if let Some(level) = heading_level(line) {
+            headings.push((idx, level));
+        }
+    }
+    headings
+}
+
+/// End index (exclusive) of the subtree started by heading at `start`
+fn subtree_end(headings: &[(usize, usize)], start: usize, level: usize, total: usize) -> usize {
+    headings
+        .iter()
+        .find(|&&(idx, l)| idx > start && l <= level)
+        .map(|&(idx, _)| idx)
+        .unwrap_or(total)
+}
+
+/// Remove the subtree whose heading is on 1-based `line`
+fn take_subtree(lines: &mut Vec<String>, line: usize) -> Result<Subtree, AppError> {
+    let start = line - 1;
+    let headings = find_headings(lines);
+    let level = headings
+        .iter()
+        .find(|&&(idx, _)| idx == start)
+        .map(|&(_, l)| l)
+        .ok_or_else(|| AppError::Refile(format!("line {line} is not a heading")))?;
+    let end = subtree_end(&headings, start, level, lines.len());
+
+    let mut taken: Vec<String> = lines.drain(start..end).collect();
+    while taken.last().is_some_and(|l| l.trim().is_empty()) {
+        taken.pop();
+    }
+    let title = heading_text(&taken[0]).to_string();
+
+    Ok(Subtree { lines: taken, level, title })
+}
+
+/// Insert subtree under `heading` (or at top level when `None`)
+fn insert_subtree(lines: &mut Vec<String>, subtree: Subtree, heading: Option<&str>) -> Result<(), AppError> {
+    let headings = find_headings(lines);
+
+    let (position, target_level) = if let Some(name) = heading {
+        let matches: Vec<(usize, usize)> = headings
+            .iter()
+            .copied()
+            .filter(|&(idx, _)| heading_matches(&lines[idx], name))
+            .collect();
+        let (idx, level) = match matches.as_slice() {
+            [] => return Err(AppError::Refile(format!("heading '{name}' not found"))),
+            [single] => *single,
+            _ => {
+                let found: Vec<String> = matches.iter().map(|(idx, _)| (idx + 1).to_string()).collect();
+                return Err(AppError::Refile(format!(
+                    "heading '{name}' is ambiguous (lines {})",
+                    found.join(", ")
+                )));
+            }
+        };
+        (subtree_end(&headings, idx, level, lines.len()), level + 1)
+    } else {
+        (lines.len(), 1)
+    };
+
+    let shifted = shift_levels(&subtree, target_level)?;
+
+    // Keep the subtree separated from surrounding content by blank lines
+    let mut insert_at = position;
+    while insert_at > 0 && lines[insert_at - 1].trim().is_empty() {
+        insert_at -= 1;
+    }
+    let mut block = Vec::with_capacity(shifted.len() + 2);
+    if insert_at > 0 {
+        block.push(String::new());
+    }
+    block.extend(shifted);
+    if insert_at < lines.len() {
+        block.push(String::new());
+    }
+    lines.splice(insert_at..position, block);
+
+    Ok(())
+}
+
+/// Shift heading levels of a subtree so its root lands on `target_level`
+fn shift_levels(subtree: &Subtree, target_level: usize) -> Result<Vec<String>, AppError> {
+    let delta = target_level as isize - subtree.level as isize;
+    let headings = find_headings(&subtree.lines);
+    let mut lines = subtree.lines.clone();
+
+    for (idx, level) in headings {
+        let new_level = (level as isize + delta) as usize;
+        if new_level > MAX_HEADING_LEVEL {
+            return Err(AppError::Refile(format!(
+                "refiling '{}' would exceed maximum heading level {MAX_HEADING_LEVEL}",
+                subtree.title
+            )));
+        }
+        lines[idx] = format!("{}{}", "#".repeat(new_level), &lines[idx][level..]);
+    }
+
+    Ok(lines)
+}
+
+fn heading_text(line: &str) -> &str {
+    line.trim_start_matches('#').trim()
+}
+
+/// Match heading by full text or by title without TODO keyword and priority
+fn heading_matches(line: &str, name: &str) -> bool {
+    let text = heading_text(line);
+    if text == name {
+        return true;
+    }
+    let (_, _, title) = parse_heading(text);
+    title == name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        split_lines(text)
+    }
+
+    #[test]
+    fn test_take_subtree_with_children() {
+        let mut doc = lines("# Inbox\n## TODO Task\n`SCHEDULED: <2025-01-10 Fri>`\n### Child\ntext\n## Other\n");
+        let subtree = take_subtree(&mut doc, 2).unwrap();
+        assert_eq!(subtree.level, 2);
+        assert_eq!(subtree.title, "TODO Task");
+        assert_eq!(subtree.lines.len(), 4);
+        assert_eq!(doc, lines("# Inbox\n## Other"));
+    }
+
+    #[test]
+    fn test_take_subtree_not_heading() {
+        let mut doc = lines("# Inbox\ntext\n");
+        assert!(take_subtree(&mut doc, 2).is_err());
+    }
+
+    #[test]
+    fn test_heading_in_code_block_ignored() {
+        let mut doc = lines("## Task\n```\n# not a heading\n```\n## Next\n");
+        let subtree = take_subtree(&mut doc, 1).unwrap();
+        assert_eq!(subtree.lines.len(), 4);
+    }
+
+    #[test]
+    fn test_insert_under_heading_adjusts_levels() {
+        let mut src = lines("# Inbox\n## TODO Task\n### Child\n");
+        let subtree = take_subtree(&mut src, 2).unwrap();
+        let mut dest = lines("# Projects\n## Work\nnotes\n## Home\n");
+        insert_subtree(&mut dest, subtree, Some("Work")).unwrap();
+        assert_eq!(
+            dest,
+            lines("# Projects\n## Work\nnotes\n\n### TODO Task\n#### Child\n\n## Home")
+        );
+    }
+
+    #[test]
+    fn test_insert_top_level() {
+        let mut src = lines("# Inbox\n### TODO Task\n`CLOCK: <2025-01-10 Fri 10:00>`\n");
+        let subtree = take_subtree(&mut src, 2).unwrap();
+        let mut dest = lines("# Projects\n");
+        insert_subtree(&mut dest, subtree, None).unwrap();
+        assert_eq!(dest, lines("# Projects\n\n# TODO Task\n`CLOCK: <2025-01-10 Fri 10:00>`"));
+    }
+
+    #[test]
+    fn test_insert_matches_title_without_keyword() {
+        let mut src = lines("## Note\n");
+        let subtree = take_subtree(&mut src, 1).unwrap();
+        let mut dest = lines("# TODO [#A] Release\n");
+        insert_subtree(&mut dest, subtree, Some("Release")).unwrap();
+        assert_eq!(dest[2], "## Note");
+    }
+
+    #[test]
+    fn test_insert_heading_not_found() {
+        let mut src = lines("## Note\n");
+        let subtree = take_subtree(&mut src, 1).unwrap();
+        let mut dest = lines("# Projects\n");
+        assert!(insert_subtree(&mut dest, subtree, Some("Missing")).is_err());
+    }
+
+    #[test]
+    fn test_insert_exceeds_max_level() {
+        let mut src = lines("# Task\n###### Deep\n");
+        let subtree = take_subtree(&mut src, 1).unwrap();
+        let mut dest = lines("## Target\n");
+        assert!(insert_subtree(&mut dest, subtree, Some("Target")).is_err());
+    }
+
+    #[test]
+    fn test_line_endings_preserved() {
+        let crlf = "# Inbox\r\n## Task\r\n# Done";
+        let endings = LineEndings::detect(crlf);
+        assert_eq!(endings, LineEndings { newline: "\r\n", final_newline: false });
+        assert_eq!(join_lines(&lines(crlf), endings), crlf);
+
+        let lf = "# Inbox\n## Task\n";
+        assert_eq!(join_lines(&lines(lf), LineEndings::detect(lf)), lf);
+        assert_eq!(join_lines(&[], LineEndings::detect("")), "");
+    }
+
+    #[test]
+    fn test_refile_keeps_file_line_endings() {
+        let dir = std::env::temp_dir().join(format!("refile-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let inbox = dir.join("inbox.md");
+        let projects = dir.join("projects.md");
+        fs::write(&inbox, "# Inbox\r\n## TODO Task\r\ntext\r\n# Later").unwrap();
+        fs::write(&projects, "# Projects\n").unwrap();
+
+        let source = format!("{}:2", inbox.display());
+        refile(&source, projects.to_str().unwrap()).unwrap();
+        assert_eq!(fs::read_to_string(&inbox).unwrap(), "# Inbox\r\n# Later");
+        assert_eq!(fs::read_to_string(&projects).unwrap(), "# Projects\n\n# TODO Task\ntext\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_source() {
+        let (file, line) = parse_source("notes/inbox.md:12").unwrap();
+        assert_eq!(file, PathBuf::from("notes/inbox.md"));
+        assert_eq!(line, 12);
+        assert!(parse_source("inbox.md").is_err());
+        assert!(parse_source("inbox.md:0").is_err());
+    }
+
+    #[test]
+    fn test_parse_destination_with_heading() {
+        let dest = parse_destination("missing-dir/projects.md:Work");
+        assert_eq!(dest.file, PathBuf::from("missing-dir/projects.md"));
+        assert_eq!(dest.heading, Some("Work".to_string()));
+    }
+}
@@ -0,0 +1,249 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use crate::types::{DayAgenda, Task, TaskWithOffset};
+
+/// Field a task list can be sorted by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortField {
+    Priority,
+    Deadline,
+    Scheduled,
+    Created,
+    File,
+    Heading,
+    /// Most urgent first
+    Urgency,
+}
+
+/// One component of a composite sort order, e.g. `-deadline`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SortKey {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (descending, name) = match s.strip_prefix('-') {
+            Some(name) => (true, name),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let field = match name.to_lowercase().as_str() {
+            "priority" => SortField::Priority,
+            "deadline" => SortField::Deadline,
+            "scheduled" => SortField::Scheduled,
+            "created" => SortField::Created,
+            "file" => SortField::File,
+            "heading" => SortField::Heading,
+            "urgency" => SortField::Urgency,
+            _ => {
+                return Err(format!(
+                    "Invalid sort key: {s}. Valid keys: priority, deadline, scheduled, created, file, heading, urgency"
+                ))
+            }
+        };
+        Ok(SortKey { field, descending })
+    }
+}
+
+/// Compare two tasks by the given keys
+///
+/// Tasks lacking a value for a key (no priority, no deadline, ...) always
+/// sort after tasks that have one, regardless of direction.
+pub fn compare_tasks(a: &Task, b: &Task, keys: &[SortKey]) -> Ordering {
+    keys.iter()
+        .map(|key| {
+            let ordering = match key.field {
+                SortField::Priority => compare_present(
+                    a.priority.as_ref().map(|p| p.order()),
+                    b.priority.as_ref().map(|p| p.order()),
+                    key.descending,
+                ),
+                SortField::Deadline => compare_present(a.deadline_date(), b.deadline_date(), key.descending),
+                SortField::Scheduled => compare_present(a.scheduled_date(), b.scheduled_date(), key.descending),
+                SortField::Created => compare_present(a.created_date(), b.created_date(), key.descending),
+                SortField::File => a.file.cmp(&b.file),
+                SortField::Heading => a.heading.cmp(&b.heading),
+                SortField::Urgency => compare_present(
+                    a.urgency.as_ref().map(|u| u.score),
+                    b.urgency.as_ref().map(|u| u.score),
+                    !key.descending,
+                ),
+            };
+            match key.field {
+                SortField::File | SortField::Heading if key.descending => ordering.reverse(),
+                _ => ordering,
+            }
+        })
+        .find(|o| o.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Final tie-breaker: source location
+pub fn compare_location(a: &Task, b: &Task) -> Ordering {
+    a.file.cmp(&b.file).then(a.line.cmp(&b.line))
+}
+
+/// Sort a flat task list: priority first unless keys are given, then location
+pub fn sort_tasks(tasks: &mut [Task], keys: &[SortKey]) {
+    tasks.sort_by(|a, b| {
+        let primary = if keys.is_empty() {
+            compare_present(
+                a.priority.as_ref().map(|p| p.order()),
+                b.priority.as_ref().map(|p| p.order()),
+                false,
+            )
+        } else {
+            compare_tasks(a, b, keys)
+        };
+        primary.then_with(|| compare_location(a, b))
+    });
+}
+
+/// Re-sort every section of a day by the given keys
+///
+/// The sort is stable, so entries equal under `keys` keep the section's
+/// default order (offset, time, location).
+pub fn sort_day_agenda(day: &mut DayAgenda, keys: &[SortKey]) {
+    if keys.is_empty() {
+        return;
+    }
+    for section in [
+        &mut day.overdue,
+        &mut day.scheduled_timed,
+        &mut day.scheduled_no_time,
+        &mut day.upcoming,
+    ] {
+        sort_section(section, keys);
+    }
+}
+
+fn sort_section(section: &mut [TaskWithOffset], keys: &[SortKey]) {
+    section.sort_by(|a, b| compare_tasks(&a.task, &b.task, keys));
+}
+
+/// Order present values by direction, missing values last
+fn compare_present<T: PartialOrd>(a: Option<T>, b: Option<T>, descending: bool) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) if descending => b.partial_cmp(&a).unwrap_or(Ordering::Equal),
+        (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Priority;
+
+    fn task(file: &str, line: u32, priority: Option<Priority>, deadline: Option<&str>) -> Task {
+        Task {
+            file: file.to_string(),
+            line,
+            heading: format!("Task {line}"),
+            content: String::new(),
+            task_type: None,
+            priority,
+            created: None,
+            timestamp: deadline.map(|d| format!("DEADLINE: <{d}>")),
+            timestamp_type: deadline.map(|_| "DEADLINE".to_string()),
+            timestamp_date: deadline.map(str::to_string),
+            timestamp_time: None,
+            timestamp_end_time: None,
+            clocks: None,
+            total_clock_time: None,
+            tags: None,
+            properties: None,
+            urgency: None,
+            uid: None,
+            parsed_timestamp: None,
+        }
+    }
+
+    fn keys(spec: &str) -> Vec<SortKey> {
+        spec.split(',').map(|k| k.parse().unwrap()).collect()
+    }
+
+    fn lines(tasks: &[Task]) -> Vec<u32> {
+        tasks.iter().map(|t| t.line).collect()
+    }
+
+    #[test]
+    fn test_parse_sort_key() {
+        assert_eq!(
+            "-deadline".parse::<SortKey>(),
+            Ok(SortKey { field: SortField::Deadline, descending: true })
+        );
+        assert_eq!(
+            "Priority".parse::<SortKey>(),
+            Ok(SortKey { field: SortField::Priority, descending: false })
+        );
+        assert!("size".parse::<SortKey>().is_err());
+    }
+
+    #[test]
+    fn test_urgency_sort_most_urgent_first() {
+        let mut tasks = vec![task("a.md", 1, None, None), task("a.md", 2, None, None), task("a.md", 3, None, None)];
+        for (t, score) in tasks.iter_mut().zip([2.5, 9.0]) {
+            t.urgency = Some(crate::types::Urgency {
+                score,
+                priority: score,
+                deadline: 0.0,
+                scheduled: 0.0,
+                age: 0.0,
+                tags: 0.0,
+                clock: 0.0,
+            });
+        }
+        sort_tasks(&mut tasks, &keys("urgency"));
+        assert_eq!(lines(&tasks), vec![2, 1, 3]);
+        sort_tasks(&mut tasks, &keys("-urgency"));
+        assert_eq!(lines(&tasks), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_default_sort_priority_then_location() {
+        let mut tasks = vec![
+            task("b.md", 1, None, None),
+            task("a.md", 9, Some(Priority::B), None),
+            task("a.md", 2, Some(Priority::B), None),
+            task("c.md", 1, Some(Priority::A), None),
+        ];
+        sort_tasks(&mut tasks, &[]);
+        assert_eq!(lines(&tasks), vec![1, 2, 9, 1]);
+        assert_eq!(tasks[0].file, "c.md");
+        assert_eq!(tasks[3].file, "b.md");
+    }
+
+    #[test]
+    fn test_composite_sort_missing_last() {
+        let mut tasks = vec![
+            task("a.md", 1, Some(Priority::A), None),
+            task("a.md", 2, Some(Priority::C), Some("2025-01-11")),
+            task("a.md", 3, Some(Priority::A), Some("2025-02-01")),
+            task("a.md", 4, Some(Priority::B), Some("2025-01-11")),
+        ];
+        sort_tasks(&mut tasks, &keys("deadline,priority"));
+        assert_eq!(lines(&tasks), vec![4, 2, 3, 1]);
+
+        sort_tasks(&mut tasks, &keys("-deadline,priority"));
+        assert_eq!(lines(&tasks), vec![3, 4, 2, 1]);
+    }
+
+    #[test]
+    fn test_sort_day_agenda_is_stable() {
+        let mut day = DayAgenda::new(chrono::NaiveDate::from_ymd_opt(2025, 1, 10).unwrap());
+        for (line, priority) in [(1, Some(Priority::B)), (2, None), (3, Some(Priority::A)), (4, Some(Priority::B))] {
+            day.scheduled_no_time.push(TaskWithOffset::new(task("a.md", line, priority, None), None));
+        }
+        sort_day_agenda(&mut day, &keys("priority"));
+        let order: Vec<u32> = day.scheduled_no_time.iter().map(|t| t.task.line).collect();
+        assert_eq!(order, vec![3, 1, 4, 2]);
+    }
+}
@@ -0,0 +1,295 @@
+mod eval;
+mod parse;
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use serde_json::{json, Map, Value};
+
+use crate::agenda::{week_begin, AgendaOutput};
+use crate::error::AppError;
+use crate::locale::OutputLocale;
+use crate::types::DayAgenda;
+
+/// Built-in templates of the `json`, `md` and `html` formats
+pub const JSON_TEMPLATE: &str = include_str!("../templates/json.tmpl");
+pub const MARKDOWN_TEMPLATE: &str = include_str!("../templates/markdown.tmpl");
+pub const HTML_TEMPLATE: &str = include_str!("../templates/html.tmpl");
+
+/// Compiled output template
+///
+/// Supports `{{ expr | filter(args) }}`, `{% if %}`/`{% elif %}`/`{% else %}`,
+/// `{% for x in list %}` with `loop.index`/`loop.first`/`loop.last`,
+/// `{% macro name(args) %}` and `{# comments #}`. A line holding only a
+/// block tag produces no output; `{{-`/`-}}` strip surrounding whitespace.
+#[derive(Debug)]
+pub struct Template {
+    nodes: Vec<parse::Node>,
+    macros: HashMap<String, parse::Macro>,
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self, AppError> {
+        let (nodes, macros) = parse::parse(source)?;
+        Ok(Self { nodes, macros })
+    }
+
+    /// Render with the top-level fields of `context` as variables
+    pub fn render(&self, context: Value, locale: OutputLocale) -> Result<String, AppError> {
+        let Value::Object(context) = context else {
+            return Err(AppError::Template("context must be an object".to_string()));
+        };
+        let mut scope = eval::Scope::new(context, &self.macros, locale);
+        let mut output = String::new();
+        eval::render_nodes(&self.nodes, &mut scope, &mut output)?;
+        Ok(output)
+    }
+}
+
+/// Template variables for an agenda result
+///
+/// `kind` is `"agenda"` with `days` or `"tasks"` with `tasks`; `labels` holds
+/// the renderer strings of `locale`. When `calendar` gives the first day of the
+/// week, agendas also get `calendar` week rows, their `weekdays` names and
+/// `week_numbers` (each row's week label, null when weeks are not numbered).
+pub fn context(output: &AgendaOutput, calendar: Option<Weekday>, locale: OutputLocale) -> Result<Value, AppError> {
+    let labels = locale.labels();
+    Ok(match output {
+        AgendaOutput::Days(days) => {
+            let (rows, weekdays, week_numbers) = match calendar {
+                Some(week_start) => {
+                    let rows = calendar_weeks(days, week_start)?;
+                    let shift = week_start.num_days_from_monday() as usize;
+                    let mut weekdays = labels.weekdays_short;
+                    weekdays.rotate_left(shift);
+                    (rows.clone(), json!(weekdays), row_week_numbers(&rows))
+                }
+                None => (Value::Null, Value::Null, Value::Null),
+            };
+            json!({
+                "kind": "agenda",
+                "labels": serde_json::to_value(labels)?,
+                "days": serde_json::to_value(days)?,
+                "calendar": rows,
+                "weekdays": weekdays,
+                "week_numbers": week_numbers,
+            })
+        }
+        AgendaOutput::Tasks(tasks) => json!({
+            "kind": "tasks",
+            "labels": serde_json::to_value(labels)?,
+            "tasks": serde_json::to_value(tasks)?,
+        }),
+    })
+}
+
+/// Week rows from the `week_start` before the first day to the end of the last day's week;
+/// each cell has `date`, `day` and the day's `agenda` (null outside the range)
+fn calendar_weeks(days: &[DayAgenda], week_start: Weekday) -> Result<Value, AppError> {
+    let by_date: BTreeMap<NaiveDate, &DayAgenda> = days
+        .iter()
+        .filter_map(|day| NaiveDate::parse_from_str(&day.date, "%Y-%m-%d").ok().map(|d| (d, day)))
+        .collect();
+    let (Some(first), Some(last)) = (by_date.keys().next(), by_date.keys().next_back()) else {
+        return Ok(Value::Array(Vec::new()));
+    };
+    let start = week_begin(*first, week_start);
+    let end = week_begin(*last, week_start) + Duration::days(6);
+
+    let mut weeks = Vec::new();
+    let mut week = Vec::new();
+    let mut date = start;
+    while date <= end {
+        let mut cell = Map::new();
+        cell.insert("date".to_string(), date.format("%Y-%m-%d").to_string().into());
+        cell.insert("day".to_string(), date.day().into());
+        let agenda = match by_date.get(&date) {
+            Some(day) => serde_json::to_value(day)?,
+            None => Value::Null,
+        };
+        cell.insert("agenda".to_string(), agenda);
+        week.push(Value::Object(cell));
+        if week.len() == 7 {
+            weeks.push(Value::Array(std::mem::take(&mut week)));
+        }
+        date += Duration::days(1);
+    }
+    Ok(Value::Array(weeks))
+}
+
+/// Week label of each calendar row: the `week` of its first day inside the range
+fn row_week_numbers(rows: &Value) -> Value {
+    let Some(rows) = rows.as_array() else {
+        return Value::Null;
+    };
+    let numbers: Vec<Value> = rows
+        .iter()
+        .map(|row| {
+            row.as_array()
+                .and_then(|cells| cells.iter().find(|cell| !cell["agenda"].is_null()))
+                .map(|cell| cell["agenda"]["week"].clone())
+                .unwrap_or(Value::Null)
+        })
+        .collect();
+    if numbers.iter().all(Value::is_null) {
+        Value::Null
+    } else {
+        Value::Array(numbers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Priority, Task, TaskType, TaskWithOffset};
+
+    fn render(source: &str, context: Value) -> String {
+        Template::parse(source).unwrap().render(context, OutputLocale::En).unwrap()
+    }
+
+    fn render_builtin(source: &str, output: AgendaOutput, calendar: Option<Weekday>, locale: OutputLocale) -> String {
+        let template = Template::parse(source).unwrap();
+        template.render(context(&output, calendar, locale).unwrap(), locale).unwrap()
+    }
+
+    fn task(heading: &str) -> Task {
+        Task {
+            file: "test.md".to_string(),
+            line: 1,
+            heading: heading.to_string(),
+            content: String::new(),
+            task_type: Some(TaskType::Todo),
+            priority: None,
+            created: None,
+            timestamp: None,
+            timestamp_type: None,
+            timestamp_date: None,
+            timestamp_time: None,
+            timestamp_end_time: None,
+            clocks: None,
+            total_clock_time: None,
+            tags: None,
+            properties: None,
+            urgency: None,
+            uid: None,
+            parsed_timestamp: None,
+        }
+    }
+
+    #[test]
+    fn test_loops_conditionals_and_filters() {
+        let source = "{% for t in tasks %}\n{{ loop.index }}. {{ t.name | upper }}{% if t.done %} (done){% elif t.hours > 1 %} ({{ t.hours }}h){% endif %}\n{% endfor %}\n";
+        let context = json!({"tasks": [{"name": "a", "done": true}, {"name": "b", "hours": 2}, {"name": "c"}]});
+        assert_eq!(render(source, context), "1. A (done)\n2. B (2h)\n3. C\n");
+    }
+
+    #[test]
+    fn test_whitespace_control() {
+        let source = "  {% if true %}\nyes\n  {% endif %}\n{{- 'x' -}}\n  end{# note #}\n";
+        assert_eq!(render(source, json!({})), "yes\nxend\n");
+    }
+
+    #[test]
+    fn test_macros_and_date_filters() {
+        let source = "{% macro item(d, total) %}\n{{ d | date('%d.%m.%Y') }} {{ total | duration }}\n{% endmacro %}\n{{ item(date, '2:30') -}}\n{{ item(date, 45) }}";
+        assert_eq!(render(source, json!({"date": "2024-12-05"})), "05.12.2024 2h 30m\n05.12.2024 45m\n");
+        let err = Template::parse("{{ date | date('%H:%M') }}")
+            .unwrap()
+            .render(json!({"date": "2024-12-05"}), OutputLocale::En)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("%H:%M"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = Template::parse("a\n{% if x %}\nb").unwrap_err().to_string();
+        assert!(err.contains("endif"), "{err}");
+        let err = Template::parse("{{ a | }}").unwrap_err().to_string();
+        assert!(err.contains("line 1"), "{err}");
+        assert!(Template::parse("{% endfor %}").is_err());
+        assert!(Template::parse("{{ x | nope }}").unwrap().render(json!({"x": 1}), OutputLocale::En).is_err());
+    }
+
+    #[test]
+    fn test_builtin_markdown() {
+        let mut todo = task("Test Task");
+        todo.content = "Description".to_string();
+        todo.priority = Some(Priority::Other('D'));
+        let output = render_builtin(MARKDOWN_TEMPLATE, AgendaOutput::Tasks(vec![todo]), None, OutputLocale::En);
+        assert_eq!(
+            output,
+            "# Tasks\n\n## Test Task\n**File:** test.md:1\n**Type:** Todo\n**Priority:** D\n\nDescription\n\n"
+        );
+    }
+
+    #[test]
+    fn test_builtin_markdown_day_off_headings() {
+        let mut holiday = DayAgenda::new(NaiveDate::from_ymd_opt(2025, 6, 12).unwrap());
+        holiday.is_workday = false;
+        holiday.holiday_name = Some("День России".to_string());
+        let saturday = DayAgenda::new(NaiveDate::from_ymd_opt(2025, 6, 14).unwrap());
+        let monday = DayAgenda::new(NaiveDate::from_ymd_opt(2025, 6, 16).unwrap());
+        let days = AgendaOutput::Days(vec![holiday, saturday, monday]);
+        let output = render_builtin(MARKDOWN_TEMPLATE, days, None, OutputLocale::Ru);
+        let headings: Vec<&str> = output.lines().filter(|l| l.starts_with("## ")).collect();
+        assert_eq!(
+            headings,
+            [
+                "## 12 июня 2025, четверг (День России)",
+                "## 14 июня 2025, суббота (выходной)",
+                "## 16 июня 2025, понедельник",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_builtin_json_matches_serde() {
+        let tasks = vec![task("A"), task("B")];
+        let output = render_builtin(JSON_TEMPLATE, AgendaOutput::Tasks(tasks.clone()), None, OutputLocale::En);
+        assert_eq!(output, serde_json::to_string_pretty(&tasks).unwrap());
+    }
+
+    #[test]
+    fn test_builtin_html() {
+        let mut escaped = task("Test & Task");
+        escaped.file = "<script>.md".to_string();
+        let output = render_builtin(HTML_TEMPLATE, AgendaOutput::Tasks(vec![escaped]), None, OutputLocale::En);
+        assert!(output.starts_with("<!DOCTYPE html>\n"));
+        assert!(output.contains("<meta charset=\"utf-8\">"));
+        assert!(output.contains("&lt;script&gt;"));
+        assert!(output.contains("Test &amp; Task"));
+        assert!(!output.contains("<link") && !output.contains("<script"));
+
+        // Wednesday to Thursday of the next week: two full grid rows
+        let start = NaiveDate::from_ymd_opt(2024, 12, 4).unwrap();
+        let mut days: Vec<DayAgenda> = (0..8).map(|i| DayAgenda::new(start + Duration::days(i))).collect();
+        days[0].overdue.push(TaskWithOffset::new(task("Отчёт"), Some(-2)));
+        days[1].upcoming.push(TaskWithOffset::new(task("Release"), Some(3)));
+        let output = render_builtin(HTML_TEMPLATE, AgendaOutput::Days(days), Some(Weekday::Mon), OutputLocale::En);
+        assert_eq!(output.matches("<tr>").count(), 3);
+        assert_eq!(output.matches("<td class=\"outside\">").count(), 6);
+        assert!(output.contains("<li class=\"overdue\" title=\"Отчёт\">Отчёт</li>"));
+        assert!(output.contains("<a class=\"day-number\" href=\"#day-2024-12-05\">5</a>"));
+        assert!(output.contains("<section class=\"day\" id=\"day-2024-12-05\">"));
+        assert!(output.contains("<details class=\"task upcoming\">"));
+    }
+
+    #[test]
+    fn test_html_calendar_week_start_and_numbers() {
+        // Sunday 2025-03-30 to Saturday 2025-04-05, one row starting on Sunday
+        let start = NaiveDate::from_ymd_opt(2025, 3, 30).unwrap();
+        let week = || (0..7).map(|i| DayAgenda::new(start + Duration::days(i))).collect::<Vec<_>>();
+        let output = render_builtin(HTML_TEMPLATE, AgendaOutput::Days(week()), Some(Weekday::Sun), OutputLocale::En);
+        assert_eq!(output.matches("<tr>").count(), 2);
+        assert!(output.contains("<thead><tr><th>Sun</th><th>Mon</th>"));
+        assert!(!output.contains("class=\"week-number\""));
+
+        let mut days = week();
+        days[0].week = Some(14);
+        let output = render_builtin(HTML_TEMPLATE, AgendaOutput::Days(days), Some(Weekday::Sun), OutputLocale::En);
+        assert!(output.contains("<th class=\"week-number\"></th><th>Sun</th>"));
+        assert!(output.contains("<th class=\"week-number\">14</th>"));
+        assert!(output.contains("<h2 class=\"week\">Week 14</h2>"));
+    }
+}
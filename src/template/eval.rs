@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use serde_json::{Map, Value};
+
+use super::parse::{BinOp, Expr, Macro, Node};
+use crate::error::AppError;
+use crate::locale::OutputLocale;
+
+/// Nested macro calls allowed before giving up on a recursive template
+const MAX_CALL_DEPTH: usize = 64;
+
+/// Variables visible while rendering, innermost scope last
+pub struct Scope<'a> {
+    frames: Vec<Map<String, Value>>,
+    macros: &'a HashMap<String, Macro>,
+    locale: OutputLocale,
+    depth: usize,
+}
+
+impl<'a> Scope<'a> {
+    pub fn new(context: Map<String, Value>, macros: &'a HashMap<String, Macro>, locale: OutputLocale) -> Self {
+        Self { frames: vec![context], macros, locale, depth: 0 }
+    }
+
+    fn lookup(&self, name: &str) -> Value {
+        self.frames
+            .iter()
+            .rev()
+            .find_map(|frame| frame.get(name))
+            .cloned()
+            .unwrap_or(Value::Null)
+    }
+}
+
+/// Render nodes into `output`
+pub fn render_nodes(nodes: &[Node], scope: &mut Scope, output: &mut String) -> Result<(), AppError> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Output(expr) => output.push_str(&to_text(&eval(expr, scope)?)),
+            Node::If { branches, otherwise } => {
+                let mut taken = None;
+                for (condition, body) in branches {
+                    if is_truthy(&eval(condition, scope)?) {
+                        taken = Some(body);
+                        break;
+                    }
+                }
+                render_nodes(taken.unwrap_or(otherwise), scope, output)?;
+            }
+            Node::For { var, iterable, body } => {
+                let items = match eval(iterable, scope)? {
+                    Value::Array(items) => items,
+                    Value::Object(map) => map
+                        .into_iter()
+                        .map(|(key, value)| Value::Object(Map::from_iter([("key".into(), key.into()), ("value".into(), value)])))
+                        .collect(),
+                    Value::Null => Vec::new(),
+                    other => return Err(AppError::Template(format!("cannot iterate over {}", type_name(&other)))),
+                };
+                let length = items.len();
+                for (index, item) in items.into_iter().enumerate() {
+                    let mut frame = Map::new();
+                    frame.insert(var.clone(), item);
+                    frame.insert(
+                        "loop".to_string(),
+                        serde_json::json!({
+                            "index": index + 1,
+                            "index0": index,
+                            "first": index == 0,
+                            "last": index + 1 == length,
+                            "length": length,
+                        }),
+                    );
+                    scope.frames.push(frame);
+                    let result = render_nodes(body, scope, output);
+                    scope.frames.pop();
+                    result?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn eval(expr: &Expr, scope: &mut Scope) -> Result<Value, AppError> {
+    Ok(match expr {
+        Expr::Literal(value) => value.clone(),
+        Expr::Var(name) => scope.lookup(name),
+        Expr::Attr(base, name) => match eval(base, scope)? {
+            Value::Object(mut map) => map.remove(name).unwrap_or(Value::Null),
+            _ => Value::Null,
+        },
+        Expr::Index(base, index) => {
+            let index = eval(index, scope)?;
+            match (eval(base, scope)?, index) {
+                (Value::Object(mut map), Value::String(key)) => map.remove(&key).unwrap_or(Value::Null),
+                (Value::Array(items), Value::Number(n)) => {
+                    let i = n.as_i64().unwrap_or_default();
+                    let i = if i < 0 { items.len() as i64 + i } else { i };
+                    usize::try_from(i).ok().and_then(|i| items.get(i).cloned()).unwrap_or(Value::Null)
+                }
+                _ => Value::Null,
+            }
+        }
+        Expr::Not(inner) => Value::Bool(!is_truthy(&eval(inner, scope)?)),
+        Expr::Binary(BinOp::And, left, right) => {
+            let left = eval(left, scope)?;
+            if is_truthy(&left) {
+                eval(right, scope)?
+            } else {
+                left
+            }
+        }
+        Expr::Binary(BinOp::Or, left, right) => {
+            let left = eval(left, scope)?;
+            if is_truthy(&left) {
+                left
+            } else {
+                eval(right, scope)?
+            }
+        }
+        Expr::Binary(op, left, right) => {
+            let (left, right) = (eval(left, scope)?, eval(right, scope)?);
+            Value::Bool(compare(*op, &left, &right))
+        }
+        Expr::Filter { expr, name, args } => {
+            let value = eval(expr, scope)?;
+            let args = args.iter().map(|arg| eval(arg, scope)).collect::<Result<Vec<_>, _>>()?;
+            apply_filter(name, value, &args, scope.locale)?
+        }
+        Expr::Call { name, args } => {
+            let Some(mac) = scope.macros.get(name) else {
+                return Err(AppError::Template(format!("unknown macro '{name}'")));
+            };
+            if scope.depth >= MAX_CALL_DEPTH {
+                return Err(AppError::Template(format!("macro '{name}' nested too deeply")));
+            }
+            let mut frame = Map::new();
+            for (i, param) in mac.params.iter().enumerate() {
+                let value = match args.get(i) {
+                    Some(arg) => eval(arg, scope)?,
+                    None => Value::Null,
+                };
+                frame.insert(param.clone(), value);
+            }
+            let body = &mac.body;
+            let mut output = String::new();
+            scope.frames.push(frame);
+            scope.depth += 1;
+            let result = render_nodes(body, scope, &mut output);
+            scope.depth -= 1;
+            scope.frames.pop();
+            result?;
+            Value::String(output)
+        }
+    })
+}
+
+fn compare(op: BinOp, left: &Value, right: &Value) -> bool {
+    use std::cmp::Ordering;
+
+    let ordering = match (left, right) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (Value::Null, Value::Null) => Some(Ordering::Equal),
+        _ => None,
+    };
+    match op {
+        BinOp::Eq => ordering.map_or(left == right, |o| o == Ordering::Equal),
+        BinOp::Ne => ordering.map_or(left != right, |o| o != Ordering::Equal),
+        BinOp::Lt => ordering == Some(Ordering::Less),
+        BinOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        BinOp::Gt => ordering == Some(Ordering::Greater),
+        BinOp::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        BinOp::And | BinOp::Or => unreachable!("logical operators short-circuit in eval"),
+    }
+}
+
+/// `null`, `false`, `0`, `""`, `[]` and `{}` are false
+pub fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+/// Text written for `{{ value }}`
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "list",
+        Value::Object(_) => "object",
+    }
+}
+
+fn apply_filter(name: &str, value: Value, args: &[Value], locale: OutputLocale) -> Result<Value, AppError> {
+    let arg = |i: usize| args.get(i).map(to_text).unwrap_or_default();
+    Ok(match name {
+        "e" | "escape" => Value::String(html_escape(&to_text(&value))),
+        "json" => Value::String(serde_json::to_string_pretty(&value)?),
+        "upper" => Value::String(to_text(&value).to_uppercase()),
+        "lower" => Value::String(to_text(&value).to_lowercase()),
+        "capitalize" => {
+            let text = to_text(&value).to_lowercase();
+            let mut chars = text.chars();
+            Value::String(chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default())
+        }
+        "trim" => Value::String(to_text(&value).trim().to_string()),
+        "join" => match value {
+            Value::Array(items) => Value::String(items.iter().map(to_text).collect::<Vec<_>>().join(&arg(0))),
+            other => other,
+        },
+        "default" => {
+            if is_truthy(&value) {
+                value
+            } else {
+                args.first().cloned().unwrap_or(Value::Null)
+            }
+        }
+        "length" => Value::from(match &value {
+            Value::Array(items) => items.len(),
+            Value::Object(map) => map.len(),
+            Value::String(s) => s.chars().count(),
+            _ => 0,
+        }),
+        "abs" => match value.as_i64() {
+            Some(n) => Value::from(n.abs()),
+            None => value.as_f64().map_or(Value::Null, |n| Value::from(n.abs())),
+        },
+        "date" => match value {
+            Value::Null => Value::Null,
+            other => {
+                let text = to_text(&other);
+                let date = text
+                    .get(..10)
+                    .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+                    .ok_or_else(|| AppError::Template(format!("date filter expects YYYY-MM-DD, got '{text}'")))?;
+                let format = if args.is_empty() { "%Y-%m-%d".to_string() } else { arg(0) };
+                let formatted = locale
+                    .format_date(date, &format)
+                    .map_err(|_| AppError::Template(format!("date filter cannot format a date as '{format}'")))?;
+                Value::String(formatted)
+            }
+        },
+        "duration" => match minutes(&value) {
+            Some(total) => Value::String(match (total / 60, total % 60) {
+                (0, m) => format!("{m}m"),
+                (h, 0) => format!("{h}h"),
+                (h, m) => format!("{h}h {m}m"),
+            }),
+            None => Value::Null,
+        },
+        "offset" => match value.as_i64() {
+            Some(n) if arg(0) == "wd" => Value::String(locale.workdays_offset(n)),
+            Some(n) => Value::String(locale.days_offset(n)),
+            None => Value::Null,
+        },
+        "plural" => match value.as_i64() {
+            Some(n) => Value::String(locale.plural_form(n, &arg(0), &arg(1), &arg(2)).to_string()),
+            None => Value::Null,
+        },
+        "minutes" => minutes(&value).map_or(Value::Null, Value::from),
+        "priority" => match value {
+            // Priority::Other serializes as {"Other": "D"}
+            Value::Object(map) => map.into_iter().next().map_or(Value::Null, |(_, letter)| letter),
+            other => other,
+        },
+        _ => return Err(AppError::Template(format!("unknown filter '{name}'"))),
+    })
+}
+
+/// Minutes from a number or an `H:MM` clock duration
+fn minutes(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(n) => n.as_u64(),
+        Value::String(s) => {
+            let (hours, mins) = s.trim().split_once(':')?;
+            Some(hours.parse::<u64>().ok()? * 60 + mins.parse::<u64>().ok()?)
+        }
+        _ => None,
+    }
+}
+
+/// Escape HTML special characters
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
@@ -0,0 +1,523 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::error::AppError;
+
+/// Binary operator in an expression
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinOp {
+    And,
+    Or,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Template expression
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Literal(Value),
+    Var(String),
+    Attr(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Filter { expr: Box<Expr>, name: String, args: Vec<Expr> },
+    /// Macro call
+    Call { name: String, args: Vec<Expr> },
+}
+
+/// Template tree node
+#[derive(Debug, Clone)]
+pub enum Node {
+    Text(String),
+    Output(Expr),
+    If { branches: Vec<(Expr, Vec<Node>)>, otherwise: Vec<Node> },
+    For { var: String, iterable: Expr, body: Vec<Node> },
+}
+
+/// `{% macro name(params) %}...{% endmacro %}`
+#[derive(Debug, Clone)]
+pub struct Macro {
+    pub params: Vec<String>,
+    pub body: Vec<Node>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TagKind {
+    Text,
+    Expr,
+    Block,
+    Comment,
+}
+
+/// Raw piece of the template source
+#[derive(Debug, Clone)]
+struct Piece {
+    kind: TagKind,
+    text: String,
+    line: usize,
+    /// `{{-` / `{%-`: strip whitespace before the tag
+    trim_before: bool,
+    /// `-}}` / `-%}`: strip whitespace after the tag
+    trim_after: bool,
+}
+
+/// Parse template source into nodes and top-level macros
+pub fn parse(source: &str) -> Result<(Vec<Node>, HashMap<String, Macro>), AppError> {
+    let mut pieces = split_pieces(source)?;
+    strip_whitespace(&mut pieces);
+
+    let mut parser = NodeParser { pieces, pos: 0, macros: HashMap::new() };
+    let (nodes, end) = parser.parse_nodes(&[])?;
+    if let Some((tag, line)) = end {
+        return Err(error(line, &format!("unexpected '{{% {tag} %}}'")));
+    }
+    Ok((nodes, parser.macros))
+}
+
+fn error(line: usize, msg: &str) -> AppError {
+    AppError::Template(format!("line {line}: {msg}"))
+}
+
+/// Split source into text, `{{ }}`, `{% %}` and `{# #}` pieces
+fn split_pieces(source: &str) -> Result<Vec<Piece>, AppError> {
+    let mut pieces = Vec::new();
+    let mut rest = source;
+    let mut line = 1;
+
+    while !rest.is_empty() {
+        let next = ["{{", "{%", "{#"].iter().filter_map(|open| rest.find(open)).min();
+        let Some(start) = next else {
+            pieces.push(Piece { kind: TagKind::Text, text: rest.to_string(), line, trim_before: false, trim_after: false });
+            break;
+        };
+        if start > 0 {
+            pieces.push(Piece {
+                kind: TagKind::Text,
+                text: rest[..start].to_string(),
+                line,
+                trim_before: false,
+                trim_after: false,
+            });
+            line += rest[..start].matches('\n').count();
+        }
+
+        let (kind, close) = match &rest[start..start + 2] {
+            "{{" => (TagKind::Expr, "}}"),
+            "{%" => (TagKind::Block, "%}"),
+            _ => (TagKind::Comment, "#}"),
+        };
+        let body_start = start + 2;
+        let Some(len) = rest[body_start..].find(close) else {
+            return Err(error(line, &format!("unclosed tag, expected '{close}'")));
+        };
+        let mut inner = &rest[body_start..body_start + len];
+        let trim_before = inner.starts_with('-');
+        let trim_after = inner.ends_with('-') && kind != TagKind::Comment;
+        if trim_before {
+            inner = &inner[1..];
+        }
+        if trim_after {
+            inner = &inner[..inner.len() - 1];
+        }
+        pieces.push(Piece { kind, text: inner.trim().to_string(), line, trim_before, trim_after });
+        line += inner.matches('\n').count();
+        rest = &rest[body_start + len + 2..];
+    }
+    Ok(pieces)
+}
+
+/// Drop lines holding only a block tag or comment, then apply `-` trim markers
+fn strip_whitespace(pieces: &mut [Piece]) {
+    let is_tag = |p: &Piece| matches!(p.kind, TagKind::Block | TagKind::Comment);
+    let standalone: Vec<bool> = (0..pieces.len())
+        .map(|i| {
+            if !is_tag(&pieces[i]) {
+                return false;
+            }
+            let line_start = match i.checked_sub(1).map(|j| &pieces[j]) {
+                None => true,
+                Some(prev) if prev.kind == TagKind::Text => {
+                    let tail = prev.text.trim_end_matches([' ', '\t']);
+                    tail.ends_with('\n') || (i == 1 && tail.is_empty())
+                }
+                Some(_) => false,
+            };
+            let line_end = match pieces.get(i + 1) {
+                None => true,
+                Some(next) if next.kind == TagKind::Text => {
+                    let head = next.text.trim_start_matches([' ', '\t']);
+                    head.is_empty() && i + 2 == pieces.len() || head.starts_with('\n') || head.starts_with("\r\n")
+                }
+                Some(_) => false,
+            };
+            line_start && line_end
+        })
+        .collect();
+
+    for i in 0..pieces.len() {
+        let (trim_prev, trim_next) = if standalone[i] {
+            (Trim::LineIndent, Trim::LineEnd)
+        } else {
+            (Trim::None, Trim::None)
+        };
+        let trim_prev = if pieces[i].trim_before { Trim::All } else { trim_prev };
+        let trim_next = if pieces[i].trim_after { Trim::All } else { trim_next };
+
+        if i > 0 && pieces[i - 1].kind == TagKind::Text {
+            let text = &mut pieces[i - 1].text;
+            match trim_prev {
+                Trim::All => text.truncate(text.trim_end().len()),
+                Trim::LineIndent => text.truncate(text.trim_end_matches([' ', '\t']).len()),
+                _ => {}
+            }
+        }
+        if let Some(next) = pieces.get_mut(i + 1).filter(|p| p.kind == TagKind::Text) {
+            match trim_next {
+                Trim::All => next.text = next.text.trim_start().to_string(),
+                Trim::LineEnd => {
+                    let head = next.text.trim_start_matches([' ', '\t']);
+                    let head = head
+                        .strip_prefix("\r\n")
+                        .or_else(|| head.strip_prefix('\n'))
+                        .unwrap_or(head);
+                    next.text = head.to_string();
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Trim {
+    None,
+    All,
+    LineIndent,
+    LineEnd,
+}
+
+struct NodeParser {
+    pieces: Vec<Piece>,
+    pos: usize,
+    macros: HashMap<String, Macro>,
+}
+
+/// Block tag that ended a node list, with its source line
+type EndTag = Option<(String, usize)>;
+
+impl NodeParser {
+    /// Parse nodes until one of `until` block tags (returned) or the end of input
+    fn parse_nodes(&mut self, until: &[&str]) -> Result<(Vec<Node>, EndTag), AppError> {
+        let mut nodes = Vec::new();
+        while let Some(piece) = self.pieces.get(self.pos).cloned() {
+            self.pos += 1;
+            match piece.kind {
+                TagKind::Text => {
+                    if !piece.text.is_empty() {
+                        nodes.push(Node::Text(piece.text));
+                    }
+                }
+                TagKind::Comment => {}
+                TagKind::Expr => nodes.push(Node::Output(parse_expr(&piece.text, piece.line)?)),
+                TagKind::Block => {
+                    let (keyword, rest) = piece.text.split_once(char::is_whitespace).unwrap_or((&piece.text, ""));
+                    let rest = rest.trim();
+                    if until.contains(&keyword) {
+                        return Ok((nodes, Some((piece.text.clone(), piece.line))));
+                    }
+                    match keyword {
+                        "if" => nodes.push(self.parse_if(rest, piece.line)?),
+                        "for" => nodes.push(self.parse_for(rest, piece.line)?),
+                        "macro" => self.parse_macro(rest, piece.line)?,
+                        _ => return Err(error(piece.line, &format!("unexpected '{{% {} %}}'", piece.text))),
+                    }
+                }
+            }
+        }
+        if until.is_empty() {
+            Ok((nodes, None))
+        } else {
+            Err(AppError::Template(format!("missing '{{% {} %}}'", until.last().unwrap_or(&"end"))))
+        }
+    }
+
+    fn parse_if(&mut self, condition: &str, line: usize) -> Result<Node, AppError> {
+        let mut branches = Vec::new();
+        let mut condition = parse_expr(condition, line)?;
+        loop {
+            let (body, end) = self.parse_nodes(&["elif", "else", "endif"])?;
+            branches.push((condition, body));
+            let Some((tag, tag_line)) = end else { unreachable!("parse_nodes fails without an end tag") };
+            let (keyword, rest) = tag.split_once(char::is_whitespace).unwrap_or((&tag, ""));
+            match keyword {
+                "elif" => condition = parse_expr(rest, tag_line)?,
+                "else" => {
+                    let (otherwise, _) = self.parse_nodes(&["endif"])?;
+                    return Ok(Node::If { branches, otherwise });
+                }
+                _ => return Ok(Node::If { branches, otherwise: Vec::new() }),
+            }
+        }
+    }
+
+    fn parse_for(&mut self, spec: &str, line: usize) -> Result<Node, AppError> {
+        let Some((var, iterable)) = spec.split_once(" in ") else {
+            return Err(error(line, "expected '{% for name in expression %}'"));
+        };
+        let var = var.trim();
+        if !is_identifier(var) {
+            return Err(error(line, &format!("invalid loop variable '{var}'")));
+        }
+        let iterable = parse_expr(iterable, line)?;
+        let (body, _) = self.parse_nodes(&["endfor"])?;
+        Ok(Node::For { var: var.to_string(), iterable, body })
+    }
+
+    fn parse_macro(&mut self, signature: &str, line: usize) -> Result<(), AppError> {
+        let invalid = || error(line, "expected '{% macro name(param, ...) %}'");
+        let (name, params) = signature.split_once('(').ok_or_else(invalid)?;
+        let params = params.trim_end().strip_suffix(')').ok_or_else(invalid)?;
+        let name = name.trim();
+        let params: Vec<String> = params
+            .split(',')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(str::to_string)
+            .collect();
+        if !is_identifier(name) || !params.iter().all(|p| is_identifier(p)) {
+            return Err(invalid());
+        }
+        let (body, _) = self.parse_nodes(&["endmacro"])?;
+        self.macros.insert(name.to_string(), Macro { params, body });
+        Ok(())
+    }
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_') && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Punct(&'static str),
+}
+
+const PUNCT: &[&str] = &["==", "!=", "<=", ">=", "<", ">", "(", ")", "[", "]", ".", ",", "|"];
+
+fn tokenize(source: &str, line: usize) -> Result<Vec<Token>, AppError> {
+    let mut tokens = Vec::new();
+    let mut rest = source.trim_start();
+    while !rest.is_empty() {
+        let c = rest.chars().next().unwrap_or_default();
+        if c == '"' || c == '\'' {
+            let end = rest[1..]
+                .find(c)
+                .ok_or_else(|| error(line, &format!("unterminated string in '{source}'")))?;
+            tokens.push(Token::Str(rest[1..end + 1].replace("\\n", "\n")));
+            rest = &rest[end + 2..];
+        } else if c.is_ascii_digit() || (c == '-' && rest[1..].starts_with(|d: char| d.is_ascii_digit())) {
+            let len = rest[1..]
+                .find(|d: char| !(d.is_ascii_digit() || d == '.'))
+                .map_or(rest.len(), |i| i + 1);
+            let number = rest[..len]
+                .parse()
+                .map_err(|_| error(line, &format!("invalid number '{}'", &rest[..len])))?;
+            tokens.push(Token::Num(number));
+            rest = &rest[len..];
+        } else if c.is_alphabetic() || c == '_' {
+            let len = rest.find(|d: char| !(d.is_alphanumeric() || d == '_')).unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..len].to_string()));
+            rest = &rest[len..];
+        } else if let Some(punct) = PUNCT.iter().find(|p| rest.starts_with(*p)) {
+            tokens.push(Token::Punct(punct));
+            rest = &rest[punct.len()..];
+        } else {
+            return Err(error(line, &format!("unexpected '{c}' in '{source}'")));
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+/// Parse a `{{ }}` / `{% %}` expression
+pub fn parse_expr(source: &str, line: usize) -> Result<Expr, AppError> {
+    let tokens = tokenize(source, line)?;
+    if tokens.is_empty() {
+        return Err(error(line, "empty expression"));
+    }
+    let mut parser = ExprParser { tokens, pos: 0, line, source };
+    let expr = parser.parse_or()?;
+    if parser.pos < parser.tokens.len() {
+        return Err(parser.unexpected());
+    }
+    Ok(expr)
+}
+
+struct ExprParser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    line: usize,
+    source: &'a str,
+}
+
+impl ExprParser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat_punct(&mut self, punct: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Punct(p)) if *p == punct) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(word)) if word == keyword) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_punct(&mut self, punct: &str) -> Result<(), AppError> {
+        if self.eat_punct(punct) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn unexpected(&self) -> AppError {
+        match self.peek() {
+            Some(token) => error(self.line, &format!("unexpected {token:?} in '{}'", self.source)),
+            None => error(self.line, &format!("unexpected end of '{}'", self.source)),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, AppError> {
+        let mut expr = self.parse_and()?;
+        while self.eat_keyword("or") {
+            expr = Expr::Binary(BinOp::Or, Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, AppError> {
+        let mut expr = self.parse_not()?;
+        while self.eat_keyword("and") {
+            expr = Expr::Binary(BinOp::And, Box::new(expr), Box::new(self.parse_not()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, AppError> {
+        if self.eat_keyword("not") {
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, AppError> {
+        let left = self.parse_filtered()?;
+        let op = match self.peek() {
+            Some(Token::Punct("==")) => BinOp::Eq,
+            Some(Token::Punct("!=")) => BinOp::Ne,
+            Some(Token::Punct("<")) => BinOp::Lt,
+            Some(Token::Punct("<=")) => BinOp::Le,
+            Some(Token::Punct(">")) => BinOp::Gt,
+            Some(Token::Punct(">=")) => BinOp::Ge,
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+        Ok(Expr::Binary(op, Box::new(left), Box::new(self.parse_filtered()?)))
+    }
+
+    fn parse_filtered(&mut self) -> Result<Expr, AppError> {
+        let mut expr = self.parse_postfix()?;
+        while self.eat_punct("|") {
+            let Some(Token::Ident(name)) = self.peek().cloned() else {
+                return Err(self.unexpected());
+            };
+            self.pos += 1;
+            let args = if self.eat_punct("(") { self.parse_args()? } else { Vec::new() };
+            expr = Expr::Filter { expr: Box::new(expr), name, args };
+        }
+        Ok(expr)
+    }
+
+    /// Comma-separated arguments after an opening parenthesis
+    fn parse_args(&mut self) -> Result<Vec<Expr>, AppError> {
+        let mut args = Vec::new();
+        if self.eat_punct(")") {
+            return Ok(args);
+        }
+        loop {
+            args.push(self.parse_or()?);
+            if self.eat_punct(")") {
+                return Ok(args);
+            }
+            self.expect_punct(",")?;
+        }
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expr, AppError> {
+        let mut expr = self.parse_primary()?;
+        loop {
+            if self.eat_punct(".") {
+                let Some(Token::Ident(name)) = self.peek().cloned() else {
+                    return Err(self.unexpected());
+                };
+                self.pos += 1;
+                expr = Expr::Attr(Box::new(expr), name);
+            } else if self.eat_punct("[") {
+                let index = self.parse_or()?;
+                self.expect_punct("]")?;
+                expr = Expr::Index(Box::new(expr), Box::new(index));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, AppError> {
+        let Some(token) = self.peek().cloned() else {
+            return Err(self.unexpected());
+        };
+        self.pos += 1;
+        match token {
+            Token::Str(s) => Ok(Expr::Literal(Value::String(s))),
+            Token::Num(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => Ok(Expr::Literal(Value::from(n as i64))),
+            Token::Num(n) => Ok(Expr::Literal(Value::from(n))),
+            Token::Punct("(") => {
+                let expr = self.parse_or()?;
+                self.expect_punct(")")?;
+                Ok(expr)
+            }
+            Token::Ident(name) => match name.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "null" | "none" => Ok(Expr::Literal(Value::Null)),
+                _ if self.eat_punct("(") => Ok(Expr::Call { name, args: self.parse_args()? }),
+                _ => Ok(Expr::Var(name)),
+            },
+            Token::Punct(_) => {
+                self.pos -= 1;
+                Err(self.unexpected())
+            }
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use std::ffi::OsString;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+
+use chrono::NaiveDate;
+
+use crate::locale::OutputLocale;
+use crate::types::{DayAgenda, Task, TaskType, TaskWithOffset};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const CYAN: &str = "\x1b[36m";
+
+/// Width of the `10:00-11:00` time column
+const TIME_WIDTH: usize = 11;
+
+/// Upper bound of the file column, longer names are cut
+const MAX_CATEGORY_WIDTH: usize = 16;
+
+/// Colour only when writing to a terminal and `NO_COLOR` is unset or empty
+pub fn use_color(output: Option<&Path>) -> bool {
+    output.is_none() && color_enabled(std::env::var_os("NO_COLOR"), io::stdout().is_terminal())
+}
+
+fn color_enabled(no_color: Option<OsString>, is_tty: bool) -> bool {
+    is_tty && no_color.is_none_or(|v| v.is_empty())
+}
+
+/// ANSI painter that is a no-op when colours are disabled
+#[derive(Clone, Copy)]
+struct Palette {
+    enabled: bool,
+}
+
+impl Palette {
+    fn paint(&self, code: &str, text: &str) -> String {
+        if self.enabled && !text.is_empty() {
+            format!("{code}{text}{RESET}")
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// Render day agendas as a compact org-agenda-like view
+pub fn render_days_term(days: &[DayAgenda], color: bool, locale: OutputLocale) -> String {
+    let palette = Palette { enabled: color };
+    let labels = locale.labels();
+    let days: Vec<(&DayAgenda, Vec<_>)> = days.iter().map(|day| (day, day_lines(day, locale))).collect();
+    let all = || days.iter().flat_map(|(_, lines)| lines);
+    let width = category_width(all().map(|(entry, _, _)| &entry.task));
+    let label_width = all().map(|(_, label, _)| label.chars().count()).max().unwrap_or(0);
+
+    let mut output = String::new();
+    for (day, lines) in &days {
+        if let Some(week) = day.week {
+            output.push_str(&palette.paint(CYAN, &format!("{} {week}", labels.week)));
+            output.push('\n');
+        }
+        let heading = NaiveDate::parse_from_str(&day.date, "%Y-%m-%d")
+            .ok()
+            .and_then(|d| locale.format_date(d, labels.long_date_format).ok())
+            .unwrap_or_else(|| day.date.clone());
+        output.push_str(&palette.paint(BOLD, &heading));
+        let day_off = match (&day.holiday_name, day.is_workday) {
+            (Some(name), _) => Some(name.as_str()),
+            (None, false) => Some(labels.day_off),
+            (None, true) => None,
+        };
+        if let Some(day_off) = day_off {
+            output.push(' ');
+            output.push_str(&palette.paint(RED, &format!("({day_off})")));
+        }
+        output.push('\n');
+        for (entry, label, color) in lines {
+            let task = &entry.task;
+            let done = task.task_type == Some(TaskType::Done);
+            let columns = format!(
+                "{:<width$} {:<TIME_WIDTH$} {:<label_width$}",
+                category(task),
+                time_range(task),
+                label
+            );
+            let columns = palette.paint(if done { DIM } else { color }, &columns);
+            output.push_str(&format!("  {columns} {}\n", title(task, palette)));
+        }
+    }
+    output
+}
+
+/// Entries of a day in section order with their label and colour
+fn day_lines(day: &DayAgenda, locale: OutputLocale) -> Vec<(&TaskWithOffset, String, &'static str)> {
+    let labels = locale.labels();
+    let mut lines = Vec::new();
+    for entry in &day.overdue {
+        let label = match (entry.workdays_offset, entry.days_offset) {
+            (Some(workdays), Some(offset)) if offset < 0 => format!("{}:", locale.workdays_offset(workdays)),
+            (None, Some(offset)) if offset < 0 => format!("{}:", locale.days_offset(offset)),
+            _ => format!("{}:", labels.overdue),
+        };
+        lines.push((entry, label, RED));
+    }
+    for entry in day.scheduled_timed.iter().chain(&day.scheduled_no_time) {
+        lines.push((entry, timestamp_label(&entry.task, locale), GREEN));
+    }
+    for entry in &day.upcoming {
+        let label = match (entry.workdays_offset, entry.days_offset) {
+            (Some(workdays), _) => format!("{}:", locale.workdays_offset(workdays)),
+            (None, Some(offset)) => format!("{}:", locale.days_offset(offset)),
+            (None, None) => format!("{}:", labels.upcoming),
+        };
+        lines.push((entry, label, YELLOW));
+    }
+    lines
+}
+
+/// Render a flat task list, one line per task with its timestamp
+pub fn render_tasks_term(tasks: &[Task], color: bool, locale: OutputLocale) -> String {
+    let palette = Palette { enabled: color };
+    let width = category_width(tasks.iter());
+    let label_width = tasks.iter().map(|t| timestamp_label(t, locale).chars().count()).max().unwrap_or(0);
+
+    let mut output = String::new();
+    for task in tasks {
+        let label = timestamp_label(task, locale);
+        let date = match (&task.timestamp_date, time_range(task)) {
+            (Some(date), time) if !time.is_empty() => format!("{date} {time}"),
+            (Some(date), _) => date.clone(),
+            (None, _) => String::new(),
+        };
+        output.push_str(&format!(
+            "  {:<width$} {:<label_width$} {:<22} {}\n",
+            category(task),
+            label,
+            date,
+            title(task, palette)
+        ));
+    }
+    output
+}
+
+/// `Deadline:` / `Scheduled:` / `Closed:` for planning timestamps
+fn timestamp_label(task: &Task, locale: OutputLocale) -> String {
+    let labels = locale.labels();
+    match task.timestamp_type.as_deref() {
+        Some("DEADLINE") => format!("{}:", labels.deadline),
+        Some("SCHEDULED") => format!("{}:", labels.scheduled),
+        Some("CLOSED") => format!("{}:", labels.closed),
+        _ => String::new(),
+    }
+}
+
+/// Keyword, priority cookie, heading and tags
+fn title(task: &Task, palette: Palette) -> String {
+    let mut parts = Vec::new();
+    match task.task_type {
+        Some(TaskType::Todo) => parts.push(palette.paint(&format!("{BOLD}{RED}"), "TODO")),
+        Some(TaskType::Done) => parts.push(palette.paint(GREEN, "DONE")),
+        None => {}
+    }
+    if let Some(ref priority) = task.priority {
+        parts.push(palette.paint(BOLD, &format!("[#{}]", priority.as_char())));
+    }
+    if task.task_type == Some(TaskType::Done) {
+        parts.push(palette.paint(DIM, &task.heading));
+    } else {
+        parts.push(task.heading.clone());
+    }
+    if let Some(tags) = task.tags.as_ref().filter(|t| !t.is_empty()) {
+        parts.push(palette.paint(CYAN, &format!(":{}:", tags.join(":"))));
+    }
+    parts.join(" ")
+}
+
+fn time_range(task: &Task) -> String {
+    match (&task.timestamp_time, &task.timestamp_end_time) {
+        (Some(start), Some(end)) => format!("{start}-{end}"),
+        (Some(start), None) => start.clone(),
+        _ => String::new(),
+    }
+}
+
+/// File name without extension, like an org-agenda category
+fn category(task: &Task) -> String {
+    let stem = Path::new(&task.file)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(&task.file);
+    format!("{}:", stem.chars().take(MAX_CATEGORY_WIDTH).collect::<String>())
+}
+
+fn category_width<'a>(tasks: impl Iterator<Item = &'a Task>) -> usize {
+    tasks.map(|task| category(task).chars().count()).max().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Priority;
+
+    fn task(file: &str, heading: &str, ts_type: &str, time: Option<&str>) -> Task {
+        Task {
+            file: file.to_string(),
+            line: 1,
+            heading: heading.to_string(),
+            content: String::new(),
+            task_type: Some(TaskType::Todo),
+            priority: None,
+            created: None,
+            timestamp: None,
+            timestamp_type: Some(ts_type.to_string()),
+            timestamp_date: Some("2024-12-05".to_string()),
+            timestamp_time: time.map(str::to_string),
+            timestamp_end_time: None,
+            clocks: None,
+            total_clock_time: None,
+            tags: None,
+            properties: None,
+            urgency: None,
+            uid: None,
+            parsed_timestamp: None,
+        }
+    }
+
+    #[test]
+    fn test_days_layout_without_color() {
+        let mut day = DayAgenda::new(NaiveDate::from_ymd_opt(2024, 12, 5).unwrap());
+        let mut release = task("notes/work.md", "Release", "DEADLINE", None);
+        release.priority = Some(Priority::A);
+        release.tags = Some(vec!["work".to_string()]);
+        day.overdue.push(TaskWithOffset::new(release, Some(-3)));
+        day.scheduled_timed.push(TaskWithOffset::new(task("home.md", "Call", "SCHEDULED", Some("10:00")), None));
+        day.upcoming.push(TaskWithOffset::new(task("home.md", "Pay rent", "DEADLINE", None), Some(5)));
+
+        assert_eq!(
+            render_days_term(std::slice::from_ref(&day), false, OutputLocale::En),
+            "Thursday, 5 December 2024\n\
+\x20 work:             3 days ago: TODO [#A] Release :work:\n\
+\x20 home: 10:00       Scheduled:  TODO Call\n\
+\x20 home:             in 5 days:  TODO Pay rent\n"
+        );
+        assert_eq!(
+            render_days_term(&[day], false, OutputLocale::Ru),
+            "четверг, 5 декабря 2024\n\
+\x20 work:             3 дня назад:   TODO [#A] Release :work:\n\
+\x20 home: 10:00       Запланировано: TODO Call\n\
+\x20 home:             через 5 дней:  TODO Pay rent\n"
+        );
+    }
+
+    #[test]
+    fn test_colors_by_section() {
+        let mut day = DayAgenda::new(NaiveDate::from_ymd_opt(2024, 12, 5).unwrap());
+        day.overdue.push(TaskWithOffset::new(task("a.md", "Late", "DEADLINE", None), Some(-1)));
+        day.upcoming.push(TaskWithOffset::new(task("a.md", "Soon", "DEADLINE", None), Some(2)));
+
+        let output = render_days_term(&[day], true, OutputLocale::En);
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].starts_with(BOLD));
+        assert!(lines[1].contains(RED) && lines[1].contains(RESET));
+        assert!(lines[2].contains(YELLOW));
+        assert!(!render_days_term(&[], true, OutputLocale::En).contains('\x1b'));
+    }
+
+    #[test]
+    fn test_color_enabled() {
+        assert!(color_enabled(None, true));
+        assert!(color_enabled(Some(OsString::new()), true));
+        assert!(!color_enabled(Some(OsString::from("1")), true));
+        assert!(!color_enabled(None, false));
+    }
+}
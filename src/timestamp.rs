@@ -1,7 +1,9 @@
 mod extract;
 mod parser;
+mod parts;
 mod repeater;
 
 pub use extract::{extract_created, extract_timestamp, parse_timestamp_fields};
-pub use parser::{parse_org_timestamp, ParsedTimestamp};
-pub use repeater::{add_months, closest_date, next_occurrence, DatePreference, Repeater, RepeaterUnit};
+pub use parser::{parse_org_timestamp, ParsedTimestamp, Warning};
+pub use parts::{parse_timestamp_parts, TimestampIssue, TimestampParts};
+pub use repeater::{add_months, closest_date, next_occurrence, occurrences, DatePreference, Repeater, RepeaterUnit};
@@ -1,6 +1,7 @@
 use once_cell::sync::Lazy;
 use regex::Regex;
-use std::borrow::Cow;
+
+use crate::locale::normalize_weekdays;
 
 static TIMESTAMP_RE: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"^\s*((?:SCHEDULED|DEADLINE|CLOSED):\s*)<(\d{4}-\d{2}-\d{2}[^>]*)>")
@@ -87,13 +88,3 @@ pub fn parse_timestamp_fields(
 
     (ts_type, date, time, end_time)
 }
-
-fn normalize_weekdays<'a>(text: &'a str, mappings: &[(&str, &str)]) -> Cow<'a, str> {
-    let mut result = Cow::Borrowed(text);
-    for (localized, english) in mappings {
-        if result.contains(localized) {
-            result = Cow::Owned(result.replace(localized, english));
-        }
-    }
-    result
-}
@@ -3,7 +3,8 @@ use once_cell::sync::Lazy;
 use regex::Regex;
 use std::borrow::Cow;
 
-use super::repeater::{parse_repeater, Repeater};
+use super::repeater::{parse_repeater, Repeater, RepeaterUnit};
+use crate::locale::normalize_weekdays;
 
 static RANGE_RE: Lazy<Regex> = Lazy::new(|| {
     Regex::new(concat!(
@@ -11,7 +12,7 @@ static RANGE_RE: Lazy<Regex> = Lazy::new(|| {
         r"(?: (?:Mon|Tue|Wed|Thu|Fri|Sat|Sun|Monday|Tuesday|Wednesday|Thursday|Friday|Saturday|Sunday))?",
         r"(?: (\d{1,2}:\d{2})(?:-(\d{1,2}:\d{2}))?)?",
         r"(?:\s*([.+]+\d+(?:wd|[dwmyh])))?",
-        r"(?:\s+-(\d+)d)?>",
+        r"(?:\s+-(\d+(?:wd|[dwmyh])))?>",
         r"--",
         r"<(\d{4}-\d{2}-\d{2})",
         r"(?: (?:Mon|Tue|Wed|Thu|Fri|Sat|Sun|Monday|Tuesday|Wednesday|Thursday|Friday|Saturday|Sunday))?",
@@ -25,7 +26,7 @@ static SINGLE_RE: Lazy<Regex> = Lazy::new(|| {
         r"(?: (?:Mon|Tue|Wed|Thu|Fri|Sat|Sun|Monday|Tuesday|Wednesday|Thursday|Friday|Saturday|Sunday))?",
         r"(?: (\d{1,2}:\d{2})(?:-(\d{1,2}:\d{2}))?)?",
         r"(?:\s*([.+]+\d+(?:wd|[dwmyh])))?",
-        r"(?:\s+-(\d+)d)?>",
+        r"(?:\s+-(\d+(?:wd|[dwmyh])))?>",
     )).expect("Invalid SINGLE_RE regex")
 });
 
@@ -33,6 +34,21 @@ static SINGLE_RE: Lazy<Regex> = Lazy::new(|| {
 pub struct ParsedTimestamp {
     pub date: NaiveDate,
     pub repeater: Option<Repeater>,
+    /// Deadline warning period, e.g. `-3d` or `-3wd`
+    pub warning: Option<Warning>,
+}
+
+/// How long before a deadline it shows up as upcoming
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub value: u32,
+    pub unit: RepeaterUnit,
+}
+
+/// Parse `3wd` / `2w` (the part after `-`); units are those of repeaters
+fn parse_warning(s: &str) -> Option<Warning> {
+    let Repeater { value, unit, .. } = parse_repeater(&format!("+{s}"))?;
+    Some(Warning { value, unit })
 }
 
 pub fn parse_org_timestamp(ts: &str, mappings: Option<&[(&str, &str)]>) -> Option<ParsedTimestamp> {
@@ -45,30 +61,22 @@ pub fn parse_org_timestamp(ts: &str, mappings: Option<&[(&str, &str)]>) -> Optio
     if let Some(caps) = RANGE_RE.captures(&ts) {
         let date = NaiveDate::parse_from_str(&caps[1], "%Y-%m-%d").ok()?;
         let repeater = caps.get(4).and_then(|m| parse_repeater(m.as_str()));
+        let warning = caps.get(5).and_then(|m| parse_warning(m.as_str()));
         
-        return Some(ParsedTimestamp { date, repeater });
+        return Some(ParsedTimestamp { date, repeater, warning });
     }
 
     if let Some(caps) = SINGLE_RE.captures(&ts) {
         let date = NaiveDate::parse_from_str(&caps[1], "%Y-%m-%d").ok()?;
         let repeater = caps.get(4).and_then(|m| parse_repeater(m.as_str()));
+        let warning = caps.get(5).and_then(|m| parse_warning(m.as_str()));
         
-        return Some(ParsedTimestamp { date, repeater });
+        return Some(ParsedTimestamp { date, repeater, warning });
     }
 
     None
 }
 
-fn normalize_weekdays<'a>(text: &'a str, mappings: &[(&str, &str)]) -> Cow<'a, str> {
-    let mut result = Cow::Borrowed(text);
-    for (localized, english) in mappings {
-        if result.contains(localized) {
-            result = Cow::Owned(result.replace(localized, english));
-        }
-    }
-    result
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -100,4 +108,17 @@ mod tests {
         let repeater = parsed.repeater.unwrap();
         assert_eq!(repeater.unit, super::super::repeater::RepeaterUnit::Day);
     }
+
+    #[test]
+    fn test_parse_timestamp_with_warning() {
+        let parsed = parse_org_timestamp("<2025-12-31 Wed -3wd>", None).unwrap();
+        assert_eq!(parsed.warning, Some(Warning { value: 3, unit: RepeaterUnit::Workday }));
+        assert!(parsed.repeater.is_none());
+
+        let parsed = parse_org_timestamp("<2025-12-31 Wed +1m -2w>", None).unwrap();
+        assert_eq!(parsed.warning, Some(Warning { value: 2, unit: RepeaterUnit::Week }));
+        assert!(parsed.repeater.is_some());
+
+        assert!(parse_org_timestamp("<2025-12-31 Wed>", None).unwrap().warning.is_none());
+    }
 }
@@ -0,0 +1,212 @@
+use chrono::{NaiveDate, NaiveTime, Weekday};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::repeater::{parse_repeater, Repeater};
+use crate::locale::{find_weekday, is_word};
+
+static DATE_TOKEN_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(\d{4})-(\d{1,2})-(\d{1,2})$").expect("Invalid DATE_TOKEN_RE regex")
+});
+
+static TIME_TOKEN_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(\d{1,2}:\d{2})(?:-(\d{1,2}:\d{2}))?$").expect("Invalid TIME_TOKEN_RE regex")
+});
+
+static REPEATER_TOKEN_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(\.\+|\+\+|\+)(\d+)([A-Za-z]+)$").expect("Invalid REPEATER_TOKEN_RE regex")
+});
+
+static WARNING_TOKEN_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^-(\d+)([A-Za-z]+)$").expect("Invalid WARNING_TOKEN_RE regex")
+});
+
+/// Components of a single timestamp, parsed tolerantly
+///
+/// Unlike `parse_org_timestamp`, this accepts non-canonical input (unpadded
+/// dates, mismatched weekdays, upper-case units) so callers can report or
+/// rewrite it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimestampParts {
+    pub date: NaiveDate,
+    /// Weekday as written and the weekday it denotes
+    pub weekday: Option<(String, Weekday)>,
+    pub time: Option<NaiveTime>,
+    pub end_time: Option<NaiveTime>,
+    pub repeater: Option<Repeater>,
+    /// Warning period (value, unit), e.g. `-3d`
+    pub warning: Option<(u32, String)>,
+}
+
+/// Reason a timestamp could not be parsed
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimestampIssue {
+    InvalidDate(String),
+    UnknownWeekday(String),
+    InvalidTime(String),
+    InvalidRepeater(String),
+    InvalidWarning(String),
+    Unexpected(String),
+}
+
+impl std::fmt::Display for TimestampIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimestampIssue::InvalidDate(s) => write!(f, "invalid date '{s}'"),
+            TimestampIssue::UnknownWeekday(s) => write!(f, "unrecognized weekday '{s}'"),
+            TimestampIssue::InvalidTime(s) => write!(f, "invalid time '{s}'"),
+            TimestampIssue::InvalidRepeater(s) => write!(f, "invalid repeater '{s}'"),
+            TimestampIssue::InvalidWarning(s) => write!(f, "invalid warning period '{s}'"),
+            TimestampIssue::Unexpected(s) => write!(f, "unexpected '{s}'"),
+        }
+    }
+}
+
+impl TimestampParts {
+    /// True if the written weekday does not match the date
+    pub fn weekday_mismatch(&self) -> bool {
+        use chrono::Datelike;
+        self.weekday.as_ref().is_some_and(|(_, wd)| *wd != self.date.weekday())
+    }
+}
+
+/// Parse the inside of `<...>` (without brackets) into its components
+pub fn parse_timestamp_parts(inner: &str, mappings: &[(&str, &str)]) -> Result<TimestampParts, TimestampIssue> {
+    let mut tokens = inner.split_whitespace();
+
+    let date_token = tokens.next().ok_or_else(|| TimestampIssue::InvalidDate(String::new()))?;
+    let date = parse_date_token(date_token)?;
+
+    let mut parts = TimestampParts {
+        date,
+        weekday: None,
+        time: None,
+        end_time: None,
+        repeater: None,
+        warning: None,
+    };
+
+    for token in tokens {
+        if let Some(caps) = TIME_TOKEN_RE.captures(token) {
+            if parts.time.is_some() {
+                return Err(TimestampIssue::Unexpected(token.to_string()));
+            }
+            parts.time = Some(parse_time(&caps[1])?);
+            parts.end_time = caps.get(2).map(|m| parse_time(m.as_str())).transpose()?;
+        } else if let Some(caps) = REPEATER_TOKEN_RE.captures(token) {
+            let canonical = format!("{}{}{}", &caps[1], &caps[2], caps[3].to_lowercase());
+            let repeater = parse_repeater(&canonical)
+                .filter(|r| r.value > 0)
+                .ok_or_else(|| TimestampIssue::InvalidRepeater(token.to_string()))?;
+            if parts.repeater.is_some() {
+                return Err(TimestampIssue::Unexpected(token.to_string()));
+            }
+            parts.repeater = Some(repeater);
+        } else if token.starts_with('+') || token.starts_with(".+") {
+            return Err(TimestampIssue::InvalidRepeater(token.to_string()));
+        } else if let Some(caps) = WARNING_TOKEN_RE.captures(token) {
+            let unit = caps[2].to_lowercase();
+            let value: u32 = caps[1]
+                .parse()
+                .map_err(|_| TimestampIssue::InvalidWarning(token.to_string()))?;
+            if !matches!(unit.as_str(), "d" | "w" | "m" | "y" | "h" | "wd") {
+                return Err(TimestampIssue::InvalidWarning(token.to_string()));
+            }
+            parts.warning = Some((value, unit));
+        } else if token.starts_with('-') {
+            return Err(TimestampIssue::InvalidWarning(token.to_string()));
+        } else if is_word(token) && parts.weekday.is_none() && parts.time.is_none() {
+            let weekday = resolve_weekday(token, mappings)
+                .ok_or_else(|| TimestampIssue::UnknownWeekday(token.to_string()))?;
+            parts.weekday = Some((token.to_string(), weekday));
+        } else if token.chars().next().is_some_and(char::is_alphabetic) && parts.weekday.is_none() {
+            // Weekday glued to time, e.g. "Fri10:00"
+            return Err(TimestampIssue::UnknownWeekday(token.to_string()));
+        } else {
+            return Err(TimestampIssue::Unexpected(token.to_string()));
+        }
+    }
+
+    Ok(parts)
+}
+
+fn parse_date_token(token: &str) -> Result<NaiveDate, TimestampIssue> {
+    let caps = DATE_TOKEN_RE
+        .captures(token)
+        .ok_or_else(|| TimestampIssue::InvalidDate(token.to_string()))?;
+    let year: i32 = caps[1].parse().map_err(|_| TimestampIssue::InvalidDate(token.to_string()))?;
+    let month: u32 = caps[2].parse().map_err(|_| TimestampIssue::InvalidDate(token.to_string()))?;
+    let day: u32 = caps[3].parse().map_err(|_| TimestampIssue::InvalidDate(token.to_string()))?;
+    NaiveDate::from_ymd_opt(year, month, day).ok_or_else(|| TimestampIssue::InvalidDate(token.to_string()))
+}
+
+fn parse_time(s: &str) -> Result<NaiveTime, TimestampIssue> {
+    NaiveTime::parse_from_str(s, "%H:%M").map_err(|_| TimestampIssue::InvalidTime(s.to_string()))
+}
+
+/// Resolve English or localized weekday name (full or abbreviated)
+fn resolve_weekday(token: &str, mappings: &[(&str, &str)]) -> Option<Weekday> {
+    if let Ok(weekday) = token.parse::<Weekday>() {
+        return Some(weekday);
+    }
+    find_weekday(token, mappings).and_then(|english| english.parse::<Weekday>().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RU: &[(&str, &str)] = &[("Пятница", "Friday"), ("Пт", "Fri")];
+
+    #[test]
+    fn test_parse_full_timestamp() {
+        let parts = parse_timestamp_parts("2025-01-10 Fri 10:00-11:30 +1w -2d", &[]).unwrap();
+        assert_eq!(parts.date, NaiveDate::from_ymd_opt(2025, 1, 10).unwrap());
+        assert_eq!(parts.weekday, Some(("Fri".to_string(), Weekday::Fri)));
+        assert_eq!(parts.time, NaiveTime::from_hms_opt(10, 0, 0));
+        assert_eq!(parts.end_time, NaiveTime::from_hms_opt(11, 30, 0));
+        assert!(parts.repeater.is_some());
+        assert_eq!(parts.warning, Some((2, "d".to_string())));
+        assert!(!parts.weekday_mismatch());
+    }
+
+    #[test]
+    fn test_parse_localized_weekday_mismatch() {
+        let parts = parse_timestamp_parts("2025-01-11 Пт", RU).unwrap();
+        assert!(parts.weekday_mismatch());
+    }
+
+    #[test]
+    fn test_parse_invalid_month() {
+        assert_eq!(
+            parse_timestamp_parts("2025-13-01", &[]),
+            Err(TimestampIssue::InvalidDate("2025-13-01".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_glued_weekday_and_time() {
+        assert_eq!(
+            parse_timestamp_parts("2025-01-10 Fri10:00", &[]),
+            Err(TimestampIssue::UnknownWeekday("Fri10:00".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_repeater() {
+        assert!(matches!(
+            parse_timestamp_parts("2025-01-10 Fri +1x", &[]),
+            Err(TimestampIssue::InvalidRepeater(_))
+        ));
+        assert!(matches!(
+            parse_timestamp_parts("2025-01-10 Fri +0d", &[]),
+            Err(TimestampIssue::InvalidRepeater(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_uppercase_repeater_unit() {
+        let parts = parse_timestamp_parts("2025-01-10 +1W", &[]).unwrap();
+        assert_eq!(parts.repeater.unwrap().unit, super::super::repeater::RepeaterUnit::Week);
+    }
+}
@@ -28,6 +28,25 @@ pub enum RepeaterUnit {
     Workday,
 }
 
+impl std::fmt::Display for Repeater {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let prefix = match self.repeater_type {
+            RepeaterType::Cumulative => "+",
+            RepeaterType::CatchUp => "++",
+            RepeaterType::Restart => ".+",
+        };
+        let unit = match self.unit {
+            RepeaterUnit::Day => "d",
+            RepeaterUnit::Week => "w",
+            RepeaterUnit::Month => "m",
+            RepeaterUnit::Year => "y",
+            RepeaterUnit::Hour => "h",
+            RepeaterUnit::Workday => "wd",
+        };
+        write!(f, "{prefix}{}{unit}", self.value)
+    }
+}
+
 /// Parse repeater string like "+1d", "++2w", ".+1m", "+1wd"
 pub fn parse_repeater(s: &str) -> Option<Repeater> {
     let s = s.trim();
@@ -84,7 +103,15 @@ pub enum DatePreference {
 }
 
 /// Calculate closest date to current starting from base_date (org-mode logic)
-pub fn closest_date(base_date: NaiveDate, current: NaiveDate, prefer: DatePreference, repeater: &Repeater) -> Option<NaiveDate> {
+///
+/// Workday repeaters count workdays of `calendar`.
+pub fn closest_date(
+    base_date: NaiveDate,
+    current: NaiveDate,
+    prefer: DatePreference,
+    repeater: &Repeater,
+    calendar: &HolidayCalendar,
+) -> Option<NaiveDate> {
     use chrono::Datelike;
     
     // If current <= base_date, return base_date
@@ -159,22 +186,10 @@ pub fn closest_date(base_date: NaiveDate, current: NaiveDate, prefer: DatePrefer
             }
         }
         RepeaterUnit::Workday => {
-            let calendar = HolidayCalendar::load().ok()?;
-            
-            // Find n1 (last workday occurrence <= current)
-            let mut n1 = base_date;
-            let mut count = 0u32;
-            
-            while n1 < current {
-                let next = calendar.next_workday(n1);
-                if next > current {
-                    break;
-                }
-                n1 = next;
-                count += 1;
-                if count >= repeater.value {
-                    count = 0;
-                }
+            // Find n1 (last workday after base_date up to current, or base_date itself)
+            let mut n1 = current;
+            while n1 > base_date && !calendar.is_workday(n1) {
+                n1 -= chrono::Duration::days(1);
             }
             
             // Find n2 (next workday occurrence > current)
@@ -195,13 +210,48 @@ pub fn closest_date(base_date: NaiveDate, current: NaiveDate, prefer: DatePrefer
     }
 }
 
+/// Days of `from..=to` on which the repeater falls, i.e. where `closest_date`
+/// preferring the future lands on the day itself
+///
+/// Jumps from one occurrence to the next in a single pass. Workday repeaters
+/// are checked day by day: from a day off their next occurrence skips the
+/// workdays in between.
+pub fn occurrences(
+    base_date: NaiveDate,
+    repeater: &Repeater,
+    from: NaiveDate,
+    to: NaiveDate,
+    calendar: &HolidayCalendar,
+) -> Vec<NaiveDate> {
+    let mut result = Vec::new();
+    let mut day = from;
+    while day <= to {
+        let Some(next) = closest_date(base_date, day, DatePreference::Future, repeater, calendar) else {
+            break;
+        };
+        if next == day {
+            result.push(day);
+            day += chrono::Duration::days(1);
+        } else if next > day && repeater.unit != RepeaterUnit::Workday {
+            day = next;
+        } else {
+            day += chrono::Duration::days(1);
+        }
+    }
+    result
+}
+
 /// Calculate next occurrence date for a repeater
 #[allow(dead_code)]
-pub fn next_occurrence(base_date: NaiveDate, repeater: &Repeater, from_date: NaiveDate) -> Option<NaiveDate> {
+pub fn next_occurrence(
+    base_date: NaiveDate,
+    repeater: &Repeater,
+    from_date: NaiveDate,
+    calendar: &HolidayCalendar,
+) -> Option<NaiveDate> {
     use chrono::Datelike;
     
     if repeater.unit == RepeaterUnit::Workday {
-        let calendar = HolidayCalendar::load().ok()?;
         let mut current = base_date;
         let mut count = 0u32;
         
@@ -305,20 +355,12 @@ pub fn next_occurrence(base_date: NaiveDate, repeater: &Repeater, from_date: Nai
 pub fn add_months(date: NaiveDate, months: i32) -> Option<NaiveDate> {
     use chrono::Datelike;
     
-    let mut year = date.year();
-    let mut month = date.month() as i32 + months;
+    let total = date.year() as i64 * 12 + date.month0() as i64 + months as i64;
+    let year = i32::try_from(total.div_euclid(12)).ok()?;
+    let month = total.rem_euclid(12) as u32 + 1;
     
-    while month > 12 {
-        month -= 12;
-        year += 1;
-    }
-    while month < 1 {
-        month += 12;
-        year -= 1;
-    }
-    
-    let day = date.day().min(days_in_month(year, month as u32));
-    NaiveDate::from_ymd_opt(year, month as u32, day)
+    let day = date.day().min(days_in_month(year, month));
+    NaiveDate::from_ymd_opt(year, month, day)
 }
 
 fn days_in_month(year: i32, month: u32) -> u32 {
@@ -340,6 +382,10 @@ fn days_in_month(year: i32, month: u32) -> u32 {
 mod tests {
     use super::*;
 
+    fn ru() -> HolidayCalendar {
+        HolidayCalendar::builtin("ru").unwrap()
+    }
+
     #[test]
     fn test_parse_workday_repeater() {
         let r = parse_repeater("+1wd").unwrap();
@@ -369,6 +415,13 @@ mod tests {
         assert_eq!(r.unit, RepeaterUnit::Workday);
     }
 
+    #[test]
+    fn test_repeater_display_roundtrip() {
+        for s in ["+1d", "++2w", ".+1m", "+3wd", "+1y"] {
+            assert_eq!(parse_repeater(s).unwrap().to_string(), s);
+        }
+    }
+
     #[test]
     fn test_parse_regular_day() {
         let r = parse_repeater("+1d").unwrap();
@@ -384,7 +437,7 @@ mod tests {
             unit: RepeaterUnit::Workday,
         };
         let from = NaiveDate::from_ymd_opt(2025, 12, 5).unwrap();
-        let next = next_occurrence(base, &repeater, from).unwrap();
+        let next = next_occurrence(base, &repeater, from, &ru()).unwrap();
         let expected = NaiveDate::from_ymd_opt(2025, 12, 8).unwrap(); // Monday
         assert_eq!(next, expected);
     }
@@ -398,9 +451,14 @@ mod tests {
             unit: RepeaterUnit::Workday,
         };
         let from = NaiveDate::from_ymd_opt(2026, 1, 5).unwrap();
-        let next = next_occurrence(base, &repeater, from).unwrap();
+        let next = next_occurrence(base, &repeater, from, &ru()).unwrap();
         let expected = NaiveDate::from_ymd_opt(2026, 1, 12).unwrap(); // First workday after holidays
         assert_eq!(next, expected);
+
+        // Germany has no New Year holidays after January 1st
+        let de = HolidayCalendar::builtin("de").unwrap();
+        let next = next_occurrence(base, &repeater, from, &de).unwrap();
+        assert_eq!(next, NaiveDate::from_ymd_opt(2026, 1, 6).unwrap());
     }
 
     #[test]
@@ -428,7 +486,7 @@ mod tests {
             unit: RepeaterUnit::Year,
         };
         let from = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
-        let next = next_occurrence(base, &repeater, from).unwrap();
+        let next = next_occurrence(base, &repeater, from, &ru()).unwrap();
         let expected = NaiveDate::from_ymd_opt(2025, 12, 5).unwrap();
         assert_eq!(next, expected);
     }
@@ -442,7 +500,7 @@ mod tests {
             unit: RepeaterUnit::Year,
         };
         let from = NaiveDate::from_ymd_opt(2025, 12, 6).unwrap();
-        let next = next_occurrence(base, &repeater, from).unwrap();
+        let next = next_occurrence(base, &repeater, from, &ru()).unwrap();
         eprintln!("base: {}, from: {}, next: {}", base, from, next);
         // When from < base, next occurrence is base itself (first occurrence)
         let expected = NaiveDate::from_ymd_opt(2025, 12, 11).unwrap();
@@ -458,8 +516,33 @@ mod tests {
             unit: RepeaterUnit::Month,
         };
         let from = NaiveDate::from_ymd_opt(2024, 12, 5).unwrap();
-        let next = next_occurrence(base, &repeater, from).unwrap();
+        let next = next_occurrence(base, &repeater, from, &ru()).unwrap();
         let expected = NaiveDate::from_ymd_opt(2025, 1, 5).unwrap();
         assert_eq!(next, expected);
     }
+
+    #[test]
+    fn test_occurrences_match_closest_date() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let cases = [
+            (date(2024, 12, 5), "+1d"),
+            (date(2024, 12, 5), "+2w"),
+            (date(2024, 1, 31), "+1m"),
+            (date(2024, 1, 30), "+2m"),
+            (date(2024, 2, 29), "+1y"),
+            (date(2024, 12, 25), "+1wd"),
+            (date(2024, 12, 25), "+2wd"),
+            (date(2026, 3, 1), "+1w"),
+        ];
+        let (from, to) = (date(2025, 12, 1), date(2026, 12, 31));
+        for (base, repeater) in cases {
+            let repeater = parse_repeater(repeater).unwrap();
+            let expected: Vec<NaiveDate> = from
+                .iter_days()
+                .take_while(|day| *day <= to)
+                .filter(|day| closest_date(base, *day, DatePreference::Future, &repeater, &ru()) == Some(*day))
+                .collect();
+            assert_eq!(occurrences(base, &repeater, from, to, &ru()), expected, "{base} {repeater:?}");
+        }
+    }
 }
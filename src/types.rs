@@ -1,5 +1,8 @@
-use chrono::NaiveDate;
+use chrono::{Datelike, NaiveDate, Weekday};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::timestamp::ParsedTimestamp;
 
 /// Task status type (TODO or DONE)
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -44,6 +47,16 @@ impl Priority {
         }
     }
 
+    /// Letter of the `[#X]` cookie
+    pub fn as_char(&self) -> char {
+        match self {
+            Priority::A => 'A',
+            Priority::B => 'B',
+            Priority::C => 'C',
+            Priority::Other(c) => *c,
+        }
+    }
+
     /// Get numeric order for sorting (lower is higher priority)
     pub fn order(&self) -> u32 {
         match self {
@@ -92,6 +105,71 @@ pub struct Task {
     pub clocks: Option<Vec<ClockEntry>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub total_clock_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub properties: Option<BTreeMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub urgency: Option<Urgency>,
+    /// UID of the calendar entry the task was imported from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uid: Option<String>,
+    /// `timestamp` parsed once at extraction, so agendas don't re-parse it per day
+    #[serde(skip)]
+    pub parsed_timestamp: Option<ParsedTimestamp>,
+}
+
+/// Urgency score with the contribution of each factor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Urgency {
+    pub score: f64,
+    pub priority: f64,
+    pub deadline: f64,
+    pub scheduled: f64,
+    pub age: f64,
+    pub tags: f64,
+    pub clock: f64,
+}
+
+impl Task {
+    /// Date of the main timestamp, whatever its type
+    pub fn date(&self) -> Option<NaiveDate> {
+        self.timestamp_date
+            .as_deref()
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+    }
+
+    /// Date of the DEADLINE timestamp
+    pub fn deadline_date(&self) -> Option<NaiveDate> {
+        self.date_of_type("DEADLINE")
+    }
+
+    /// Date of the SCHEDULED timestamp
+    pub fn scheduled_date(&self) -> Option<NaiveDate> {
+        self.date_of_type("SCHEDULED")
+    }
+
+    /// Date of the CLOSED timestamp
+    pub fn closed_date(&self) -> Option<NaiveDate> {
+        self.date_of_type("CLOSED")
+    }
+
+    /// Date of the CREATED timestamp
+    pub fn created_date(&self) -> Option<NaiveDate> {
+        self.created
+            .as_deref()
+            .and_then(|c| c.split('<').nth(1))
+            .and_then(|c| c.get(..10))
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+    }
+
+    fn date_of_type(&self, ts_type: &str) -> Option<NaiveDate> {
+        if self.timestamp_type.as_deref() == Some(ts_type) {
+            self.date()
+        } else {
+            None
+        }
+    }
 }
 
 /// Maximum file size to process (10 MB)
@@ -138,12 +216,28 @@ pub struct TaskWithOffset {
     pub task: Task,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub days_offset: Option<i64>,
+    /// `days_offset` in workdays, counting holidays and days off, with `--workday-offsets`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workdays_offset: Option<i64>,
+}
+
+impl TaskWithOffset {
+    pub fn new(task: Task, days_offset: Option<i64>) -> Self {
+        Self { task, days_offset, workdays_offset: None }
+    }
 }
 
 /// Day agenda containing tasks for a specific date
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DayAgenda {
     pub date: String,
+    /// False on weekends, holidays and days off of the default calendar
+    pub is_workday: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub holiday_name: Option<String>,
+    /// Week number, on the first day of each week of `week`/`month` agendas with `--week-numbers`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub week: Option<u32>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub overdue: Vec<TaskWithOffset>,
     pub scheduled_timed: Vec<TaskWithOffset>,
@@ -155,10 +249,21 @@ impl DayAgenda {
     pub fn new(date: NaiveDate) -> Self {
         Self {
             date: date.format("%Y-%m-%d").to_string(),
+            is_workday: !matches!(date.weekday(), Weekday::Sat | Weekday::Sun),
+            holiday_name: None,
+            week: None,
             overdue: Vec::new(),
             scheduled_timed: Vec::new(),
             scheduled_no_time: Vec::new(),
             upcoming: Vec::new(),
         }
     }
+
+    /// No entries in any section
+    pub fn is_empty(&self) -> bool {
+        self.overdue.is_empty()
+            && self.scheduled_timed.is_empty()
+            && self.scheduled_no_time.is_empty()
+            && self.upcoming.is_empty()
+    }
 }
@@ -0,0 +1,255 @@
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+
+use crate::types::{Priority, Task, TaskType, Urgency};
+
+/// Days of `created` age at which the age factor reaches its maximum
+const AGE_MAX_DAYS: f64 = 365.0;
+
+/// Days a scheduled task may be late before the scheduled factor saturates
+const SCHEDULED_MAX_DAYS: f64 = 14.0;
+
+/// Days after a clock entry during which the task counts as recently worked on
+const RECENT_CLOCK_DAYS: i64 = 7;
+
+/// Weights of urgency factors (Taskwarrior-style)
+///
+/// Each factor is a value in `0.0..=1.0` multiplied by its coefficient; the
+/// score is the sum of all products.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UrgencyCoefficients {
+    pub priority: f64,
+    pub deadline: f64,
+    pub scheduled: f64,
+    pub age: f64,
+    pub tags: f64,
+    pub clock: f64,
+}
+
+impl Default for UrgencyCoefficients {
+    fn default() -> Self {
+        Self {
+            priority: 6.0,
+            deadline: 12.0,
+            scheduled: 5.0,
+            age: 2.0,
+            tags: 1.0,
+            clock: 4.0,
+        }
+    }
+}
+
+impl FromStr for UrgencyCoefficients {
+    type Err = String;
+
+    /// Parse `name=value` pairs separated by commas; omitted names keep defaults
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut coefficients = Self::default();
+        for pair in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (name, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid urgency coefficient '{pair}'. Use NAME=VALUE"))?;
+            let value: f64 = value
+                .trim()
+                .parse()
+                .ok()
+                .filter(|v: &f64| v.is_finite())
+                .ok_or_else(|| format!("Invalid urgency coefficient value '{value}' for '{name}'"))?;
+            let slot = match name.trim() {
+                "priority" => &mut coefficients.priority,
+                "deadline" => &mut coefficients.deadline,
+                "scheduled" => &mut coefficients.scheduled,
+                "age" => &mut coefficients.age,
+                "tags" => &mut coefficients.tags,
+                "clock" => &mut coefficients.clock,
+                other => {
+                    return Err(format!(
+                        "Unknown urgency coefficient '{other}'. Valid names: priority, deadline, scheduled, age, tags, clock"
+                    ))
+                }
+            };
+            *slot = value;
+        }
+        Ok(coefficients)
+    }
+}
+
+/// Compute urgency of an open task; DONE tasks have no urgency
+pub fn compute_urgency(task: &Task, today: NaiveDate, coefficients: &UrgencyCoefficients) -> Option<Urgency> {
+    if matches!(task.task_type, Some(TaskType::Done)) {
+        return None;
+    }
+
+    let priority = coefficients.priority * priority_factor(task.priority.as_ref());
+    let deadline = coefficients.deadline * task.deadline_date().map_or(0.0, |d| deadline_factor(d, today));
+    let scheduled = coefficients.scheduled * task.scheduled_date().map_or(0.0, |d| scheduled_factor(d, today));
+    let age = coefficients.age * task.created_date().map_or(0.0, |d| age_factor(d, today));
+    let tags = coefficients.tags * tags_factor(task.tags.as_ref().map_or(0, Vec::len));
+    let clock = coefficients.clock * clock_factor(task, today);
+
+    let score = round(priority + deadline + scheduled + age + tags + clock);
+    Some(Urgency {
+        score,
+        priority: round(priority),
+        deadline: round(deadline),
+        scheduled: round(scheduled),
+        age: round(age),
+        tags: round(tags),
+        clock: round(clock),
+    })
+}
+
+/// A = 1.0, B = 0.65, C = 0.3, lower priorities and none = 0.0
+fn priority_factor(priority: Option<&Priority>) -> f64 {
+    match priority {
+        Some(Priority::A) => 1.0,
+        Some(Priority::B) => 0.65,
+        Some(Priority::C) => 0.3,
+        _ => 0.0,
+    }
+}
+
+/// 1.0 when a week or more overdue, 0.2 when two weeks or more away, linear between
+fn deadline_factor(deadline: NaiveDate, today: NaiveDate) -> f64 {
+    let days_overdue = (today - deadline).num_days() as f64;
+    if days_overdue >= 7.0 {
+        1.0
+    } else if days_overdue >= -14.0 {
+        (days_overdue + 14.0) * 0.8 / 21.0 + 0.2
+    } else {
+        0.2
+    }
+}
+
+/// 0.0 in the future, 0.5 on the scheduled day, growing to 1.0 as it gets older
+fn scheduled_factor(scheduled: NaiveDate, today: NaiveDate) -> f64 {
+    let days_late = (today - scheduled).num_days() as f64;
+    if days_late < 0.0 {
+        0.0
+    } else {
+        0.5 + 0.5 * days_late.min(SCHEDULED_MAX_DAYS) / SCHEDULED_MAX_DAYS
+    }
+}
+
+fn age_factor(created: NaiveDate, today: NaiveDate) -> f64 {
+    let days = (today - created).num_days().max(0) as f64;
+    days.min(AGE_MAX_DAYS) / AGE_MAX_DAYS
+}
+
+fn tags_factor(count: usize) -> f64 {
+    match count {
+        0 => 0.0,
+        1 => 0.8,
+        2 => 0.9,
+        _ => 1.0,
+    }
+}
+
+/// 1.0 for a running clock, 0.5 when clocked during the last week
+fn clock_factor(task: &Task, today: NaiveDate) -> f64 {
+    let Some(clocks) = task.clocks.as_ref() else {
+        return 0.0;
+    };
+    if clocks.iter().any(|c| c.end.is_none()) {
+        return 1.0;
+    }
+    let recent = clocks.iter().any(|c| {
+        c.start
+            .get(..10)
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+            .is_some_and(|d| (0..=RECENT_CLOCK_DAYS).contains(&(today - d).num_days()))
+    });
+    if recent {
+        0.5
+    } else {
+        0.0
+    }
+}
+
+fn round(value: f64) -> f64 {
+    (value * 1000.0).round() / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ClockEntry;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn task(priority: Option<Priority>, ts_type: Option<&str>, ts_date: Option<&str>) -> Task {
+        Task {
+            file: "a.md".to_string(),
+            line: 1,
+            heading: "Task".to_string(),
+            content: String::new(),
+            task_type: Some(TaskType::Todo),
+            priority,
+            created: None,
+            timestamp: None,
+            timestamp_type: ts_type.map(str::to_string),
+            timestamp_date: ts_date.map(str::to_string),
+            timestamp_time: None,
+            timestamp_end_time: None,
+            clocks: None,
+            total_clock_time: None,
+            tags: None,
+            properties: None,
+            urgency: None,
+            uid: None,
+            parsed_timestamp: None,
+        }
+    }
+
+    fn score(task: &Task) -> f64 {
+        compute_urgency(task, date("2025-01-10"), &UrgencyCoefficients::default())
+            .unwrap()
+            .score
+    }
+
+    #[test]
+    fn test_deadline_outweighs_priority() {
+        let a_next_year = task(Some(Priority::A), Some("DEADLINE"), Some("2026-01-10"));
+        let c_tomorrow = task(Some(Priority::C), Some("DEADLINE"), Some("2025-01-11"));
+        assert!(score(&c_tomorrow) > score(&a_next_year));
+    }
+
+    #[test]
+    fn test_breakdown_sums_to_score() {
+        let mut t = task(Some(Priority::B), Some("SCHEDULED"), Some("2025-01-03"));
+        t.created = Some("CREATED: <2024-01-10 Wed>".to_string());
+        t.tags = Some(vec!["work".to_string()]);
+        t.clocks = Some(vec![ClockEntry { start: "2025-01-10 Fri 09:00".to_string(), end: None, duration: None }]);
+
+        let u = compute_urgency(&t, date("2025-01-10"), &UrgencyCoefficients::default()).unwrap();
+        assert_eq!(u.priority, 3.9);
+        assert_eq!(u.scheduled, 3.75);
+        assert_eq!(u.age, 2.0);
+        assert_eq!(u.tags, 0.8);
+        assert_eq!(u.clock, 4.0);
+        assert_eq!(u.score, 14.45);
+    }
+
+    #[test]
+    fn test_done_has_no_urgency() {
+        let mut t = task(Some(Priority::A), None, None);
+        t.task_type = Some(TaskType::Done);
+        assert!(compute_urgency(&t, date("2025-01-10"), &UrgencyCoefficients::default()).is_none());
+    }
+
+    #[test]
+    fn test_parse_coefficients() {
+        let c: UrgencyCoefficients = "priority=10, tags=0".parse().unwrap();
+        assert_eq!(c.priority, 10.0);
+        assert_eq!(c.tags, 0.0);
+        assert_eq!(c.deadline, UrgencyCoefficients::default().deadline);
+        assert!("speed=1".parse::<UrgencyCoefficients>().is_err());
+        assert!("priority".parse::<UrgencyCoefficients>().is_err());
+        assert!("age=nan".parse::<UrgencyCoefficients>().is_err());
+        assert!("deadline=inf".parse::<UrgencyCoefficients>().is_err());
+        assert!("tags=-infinity".parse::<UrgencyCoefficients>().is_err());
+    }
+}